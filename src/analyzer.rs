@@ -0,0 +1,298 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use memchr::memmem;
+use serde::{Deserialize, Serialize};
+
+/// Counts of its own that an analyzer found in one file, by metric name.
+pub type Metrics = BTreeMap<String, u64>;
+
+/// How long a plugin has to answer for one file before it's stopped for the rest of the scan.
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Something run on every counted file during a scan, adding metrics of its own to the
+/// file and to the scan's totals: matches of a security pattern, custom annotations and so
+/// on. Register one on [`Analyzers`] to have it run.
+pub trait FileAnalyzer: Send + Sync {
+    /// Goes in front of the analyzer's metric names, which `analyze` returns without it.
+    fn name(&self) -> &str;
+
+    /// The metrics for the file at `path`, which was counted as `language`. Returning none
+    /// leaves the file out of this analyzer's totals.
+    fn analyze(&self, path: &Path, language: &str) -> Result<Metrics, String>;
+
+    /// Called once a scan has been through every file.
+    fn finish(&self) {}
+}
+
+/// The analyzers run on each file: the config's `[[analyzers]]` and any registered in code.
+/// Pattern analyzers aren't run on their own but looked for while the file's lines are
+/// counted, see [`patterns`](Self::patterns).
+#[derive(Clone, Default)]
+pub struct Analyzers {
+    patterns: Vec<PatternAnalyzer>,
+    registered: Vec<Arc<dyn FileAnalyzer>>,
+}
+
+impl fmt::Debug for Analyzers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl Analyzers {
+    pub fn register(&mut self, analyzer: Arc<dyn FileAnalyzer>) {
+        self.registered.push(analyzer);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty() && self.registered.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.patterns
+            .iter()
+            .map(PatternAnalyzer::name)
+            .chain(self.registered.iter().map(|analyzer| analyzer.name()))
+            .collect()
+    }
+
+    /// The pattern analyzers, whose counts come with the file's [`FileCount`](crate::scan::FileCount).
+    pub fn patterns(&self) -> &[PatternAnalyzer] {
+        &self.patterns
+    }
+
+    /// Every registered analyzer's metrics for one file, named `analyzer.metric`, or just
+    /// `analyzer` for a metric without a name of its own. An analyzer that fails is logged
+    /// and skipped.
+    pub fn analyze(&self, path: &Path, language: &str) -> Metrics {
+        let mut metrics = Metrics::new();
+        for analyzer in &self.registered {
+            match analyzer.analyze(path, language) {
+                Ok(found) => {
+                    for (metric, count) in found {
+                        let name = if metric.is_empty() {
+                            analyzer.name().to_string()
+                        } else {
+                            format!("{}.{}", analyzer.name(), metric)
+                        };
+                        metrics.insert(name, count);
+                    }
+                }
+                Err(err) => tracing::warn!(name = analyzer.name(), path = %path.display(), error = %err, "analyzer failed"),
+            }
+        }
+        metrics
+    }
+
+    pub fn finish(&self) {
+        for analyzer in &self.registered {
+            analyzer.finish();
+        }
+    }
+}
+
+/// An `[[analyzers]]` table of the config: either `patterns` to look for, or an external
+/// `command` to ask.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnalyzerConfig {
+    name: String,
+    #[serde(default)]
+    patterns: Vec<String>,
+    #[serde(default)]
+    command: Vec<String>,
+    /// Only files in these languages are analyzed; all of them when empty.
+    #[serde(default)]
+    languages: Vec<String>,
+}
+
+impl AnalyzerConfig {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether it's a plugin, which starts a program.
+    pub fn runs_command(&self) -> bool {
+        !self.command.is_empty()
+    }
+
+    pub fn add_to(self, analyzers: &mut Analyzers) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("analyzers: an analyzer needs a name".to_string());
+        }
+        match (self.patterns.is_empty(), self.command.is_empty()) {
+            (false, true) => analyzers.patterns.push(PatternAnalyzer {
+                name: self.name,
+                patterns: self.patterns,
+                languages: self.languages,
+            }),
+            (true, false) => analyzers.register(Arc::new(CommandAnalyzer {
+                name: self.name,
+                command: self.command,
+                languages: self.languages,
+                plugin: Mutex::new(None),
+                gave_up: AtomicBool::new(false),
+            })),
+            _ => return Err(format!("analyzers: {}: needs either patterns or a command", self.name)),
+        }
+        Ok(())
+    }
+}
+
+fn applies(languages: &[String], language: &str) -> bool {
+    languages.is_empty() || languages.iter().any(|wanted| wanted.eq_ignore_ascii_case(language))
+}
+
+/// Counts the lines holding any of its patterns, as plain text. The line counter looks for
+/// them as it goes through the file, so the count is cached along with its lines.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PatternAnalyzer {
+    name: String,
+    patterns: Vec<String>,
+    languages: Vec<String>,
+}
+
+impl PatternAnalyzer {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn applies(&self, language: &str) -> bool {
+        applies(&self.languages, language)
+    }
+
+    pub fn finders(&self) -> Vec<memmem::Finder<'_>> {
+        self.patterns.iter().map(memmem::Finder::new).collect()
+    }
+}
+
+/// A program started for the scan and kept running through it. It gets a line of JSON per
+/// file, `{"path": "…", "language": "…"}`, and answers each with a line holding a JSON
+/// object of metric names and counts. One that takes longer than [`ANSWER_TIMEOUT`] is
+/// killed and not asked about the rest of the scan's files.
+struct CommandAnalyzer {
+    name: String,
+    command: Vec<String>,
+    languages: Vec<String>,
+    plugin: Mutex<Option<Plugin>>,
+    /// Set once the plugin let a file go unanswered, until the scan is over.
+    gave_up: AtomicBool,
+}
+
+struct Plugin {
+    child: Child,
+    stdin: ChildStdin,
+    /// The plugin's answers, read off its output by a thread of their own so that waiting
+    /// for one can time out.
+    answers: Receiver<io::Result<String>>,
+    timed_out: bool,
+}
+
+impl Plugin {
+    fn start(command: &[String]) -> io::Result<Self> {
+        let mut child = Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let mut stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+        let (tx, answers) = mpsc::channel();
+        thread::spawn(move || {
+            loop {
+                let mut answer = String::new();
+                let read = stdout.read_line(&mut answer);
+                // An error or the end of the output is the last there is to pass on.
+                let last = !matches!(read, Ok(1..));
+                if tx.send(read.map(|_| answer)).is_err() || last {
+                    return;
+                }
+            }
+        });
+        Ok(Self {
+            child,
+            stdin,
+            answers,
+            timed_out: false,
+        })
+    }
+
+    fn ask(&mut self, path: &Path, language: &str) -> Result<Metrics, String> {
+        let request = serde_json::json!({ "path": path, "language": language });
+        writeln!(self.stdin, "{}", request)
+            .and_then(|()| self.stdin.flush())
+            .map_err(|err| err.to_string())?;
+        let answer = match self.answers.recv_timeout(ANSWER_TIMEOUT) {
+            Ok(answer) => answer.map_err(|err| err.to_string())?,
+            Err(RecvTimeoutError::Timeout) => {
+                self.timed_out = true;
+                return Err(format!("no answer within {}s", ANSWER_TIMEOUT.as_secs()));
+            }
+            Err(RecvTimeoutError::Disconnected) => String::new(),
+        };
+        if answer.is_empty() {
+            return Err("the plugin exited".to_string());
+        }
+        serde_json::from_str(&answer).map_err(|err| format!("unexpected answer {:?}: {}", answer.trim_end(), err))
+    }
+
+    /// Closing its input tells the plugin there are no more files.
+    fn stop(self) {
+        let Self { mut child, stdin, .. } = self;
+        drop(stdin);
+        if let Err(err) = child.wait() {
+            tracing::warn!(error = %err, "could not wait for an analyzer plugin");
+        }
+    }
+}
+
+impl FileAnalyzer for CommandAnalyzer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn analyze(&self, path: &Path, language: &str) -> Result<Metrics, String> {
+        if !applies(&self.languages, language) || self.gave_up.load(Ordering::Relaxed) {
+            return Ok(Metrics::new());
+        }
+        let mut plugin = self.plugin.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Another file may have timed out while this one waited for the lock.
+        if self.gave_up.load(Ordering::Relaxed) {
+            return Ok(Metrics::new());
+        }
+        if plugin.is_none() {
+            *plugin = Some(Plugin::start(&self.command).map_err(|err| format!("{}: {}", self.command[0], err))?);
+        }
+        let answer = plugin.as_mut().expect("started above").ask(path, language);
+        // A plugin that has gone wrong is started afresh for the next file, unless it hung,
+        // which it would likely do again on every file left.
+        if answer.is_err()
+            && let Some(broken) = plugin.take()
+        {
+            if broken.timed_out {
+                self.gave_up.store(true, Ordering::Relaxed);
+            }
+            let mut child = broken.child;
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        answer
+    }
+
+    fn finish(&self) {
+        self.gave_up.store(false, Ordering::Relaxed);
+        let plugin = self.plugin.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+        if let Some(plugin) = plugin {
+            plugin.stop();
+        }
+    }
+}
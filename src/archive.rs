@@ -0,0 +1,139 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+use flate2::read::MultiGzDecoder;
+use tar::EntryType;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveKind {
+    /// Recognizes an archive by its file name; `.crate` files are gzipped tarballs.
+    pub fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") || name.ends_with(".crate") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Hands each regular file in the archive at `path` to `visit`, with its path inside the
+/// archive, its size and a reader for its contents, until `visit` returns false. Entries
+/// are read straight out of the archive one at a time, so nothing is unpacked to disk and
+/// memory use doesn't grow with the size of the archive. Entries whose names would escape
+/// the archive, and encrypted zip entries, are passed over.
+pub fn for_each_entry(
+    path: &Path,
+    kind: ArchiveKind,
+    visit: &mut dyn FnMut(&Path, u64, &mut dyn Read) -> bool,
+) -> io::Result<()> {
+    let file = File::open(path)?;
+    match kind {
+        ArchiveKind::Tar => tar_entries(BufReader::new(file), visit),
+        // A gzip file may hold several members one after another, like `cat a.gz b.gz`.
+        ArchiveKind::TarGz => tar_entries(MultiGzDecoder::new(BufReader::new(file)), visit),
+        ArchiveKind::Zip => zip_entries(file, visit),
+    }
+}
+
+/// An entry name as a relative path inside the archive, or `None` for names that would escape it.
+fn entry_path(name: &Path) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
+
+fn tar_entries(reader: impl Read, visit: &mut dyn FnMut(&Path, u64, &mut dyn Read) -> bool) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    // Tarballs joined with `cat` have an end-of-archive marker between them.
+    archive.set_ignore_zeros(true);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !matches!(entry.header().entry_type(), EntryType::Regular | EntryType::Continuous) {
+            continue;
+        }
+        // GNU long names and pax `path` records are already applied here.
+        let Some(relative) = entry_path(&entry.path()?) else {
+            continue;
+        };
+        let size = entry.size();
+        if !visit(&relative, size, &mut entry) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Goes by the central directory at the end of the file, which is what says which
+/// entries are in the archive, rather than by the local headers in front of the data.
+fn zip_entries(file: File, visit: &mut dyn FnMut(&Path, u64, &mut dyn Read) -> bool) -> io::Result<()> {
+    let mut archive = ZipArchive::new(BufReader::new(file))?;
+    for index in 0..archive.len() {
+        let mut entry = match archive.by_index(index) {
+            Ok(entry) => entry,
+            Err(ZipError::UnsupportedArchive(message)) if message == ZipError::PASSWORD_REQUIRED => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if !entry.is_file() {
+            continue;
+        }
+        let Some(relative) = entry.enclosed_name().as_deref().and_then(entry_path) else {
+            continue;
+        };
+        let size = entry.size();
+        if !visit(&relative, size, &mut entry) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str) -> Option<PathBuf> {
+        entry_path(Path::new(name))
+    }
+
+    #[test]
+    fn keeps_relative_names() {
+        assert_eq!(entry("src/main.rs"), Some(PathBuf::from("src/main.rs")));
+        assert_eq!(entry("./pkg/./lib.rs"), Some(PathBuf::from("pkg/lib.rs")));
+        assert_eq!(entry("pkg/"), Some(PathBuf::from("pkg")));
+    }
+
+    #[test]
+    fn rejects_names_that_escape_the_archive() {
+        assert_eq!(entry("../etc/passwd"), None);
+        assert_eq!(entry("pkg/../../secret"), None);
+        assert_eq!(entry("pkg/.."), None);
+        assert_eq!(entry("/etc/passwd"), None);
+    }
+
+    #[test]
+    fn rejects_empty_names() {
+        assert_eq!(entry(""), None);
+        assert_eq!(entry("."), None);
+        assert_eq!(entry("./"), None);
+    }
+}
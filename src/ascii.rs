@@ -0,0 +1,441 @@
+use serde::Deserialize;
+
+use crate::font::Font;
+use crate::format::{format_compact_precise, format_with_commas, number_format};
+
+/// Compact numbers are short enough to draw bigger. For scalable fonts each of these,
+/// in halves of the font's own scale, is tried in turn before the usual sizes.
+const COMPACT_GROWTH: &[(usize, usize)] = &[(4, 3), (3, 3), (2, 2), (1, 1)];
+/// Scale2x passes at most, beyond which the digits stop getting any rounder.
+const MAX_SMOOTHING: u32 = 3;
+/// Rows in `SMALL_DIGITS`.
+pub const SMALL_DIGIT_ROWS: usize = 3;
+/// Three-row digits for when no size of the chosen font fits, whatever the font.
+const SMALL_DIGITS: [[&str; SMALL_DIGIT_ROWS]; 10] = [
+    ["╭─╮", "│ │", "╰─╯"],
+    ["  ╷", "  │", "  ╵"],
+    ["╶─╮", "╭─╯", "╰─╴"],
+    ["╶─╮", " ─┤", "╶─╯"],
+    ["╷ ╷", "╰─┤", "  ╵"],
+    ["╭─╴", "╰─╮", "╶─╯"],
+    ["╭─╴", "├─╮", "╰─╯"],
+    ["╶─┐", "  │", "  ╵"],
+    ["╭─╮", "├─┤", "╰─╯"],
+    ["╭─╮", "╰─┤", "╶─╯"],
+];
+
+/// How the cells of a solid-block font become terminal characters. Half blocks and
+/// braille pack 1×2 and 2×4 pixels into each cell, so the same digits come out smoother,
+/// or smaller when the full-size number doesn't fit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DigitRenderer {
+    /// Full blocks while they fit, braille below that.
+    #[default]
+    Auto,
+    Blocks,
+    HalfBlocks,
+    Braille,
+}
+
+impl DigitRenderer {
+    pub fn name(self) -> &'static str {
+        match self {
+            DigitRenderer::Auto => "auto",
+            DigitRenderer::Blocks => "blocks",
+            DigitRenderer::HalfBlocks => "half-blocks",
+            DigitRenderer::Braille => "braille",
+        }
+    }
+
+    /// Pixels per terminal cell, across and down.
+    fn cell(self) -> (usize, usize) {
+        match self {
+            DigitRenderer::Auto | DigitRenderer::Blocks => (1, 1),
+            DigitRenderer::HalfBlocks => (1, 2),
+            DigitRenderer::Braille => (2, 4),
+        }
+    }
+}
+
+/// Where and how the big number is drawn.
+pub struct BigNumber<'a> {
+    pub font: &'a Font,
+    pub renderer: DigitRenderer,
+    /// Cells available across; smaller sizes are tried until the number fits.
+    pub max_width: usize,
+    /// Rows available down. Below the font's smallest size come the small digits, then
+    /// the number as a single line of plain text.
+    pub max_height: usize,
+}
+
+/// One character of the big number, in the font's unscaled pattern. A digit whose lower
+/// neighbours are passing 9 is part-way (`roll`) through turning over to `next`. Narrow
+/// glyphs (separators) sit right against their neighbours.
+struct Glyph {
+    rows: Vec<String>,
+    next: Option<Vec<String>>,
+    roll: f64,
+    narrow: bool,
+}
+
+impl Glyph {
+    fn still(font: &Font, ch: char) -> Self {
+        Self {
+            rows: font.rows(ch),
+            next: None,
+            roll: 0.0,
+            narrow: !ch.is_alphanumeric(),
+        }
+    }
+}
+
+impl BigNumber<'_> {
+    pub fn number(&self, value: u64) -> Vec<String> {
+        let text = format_with_commas(value);
+        let glyphs = text.chars().map(|ch| Glyph::still(self.font, ch)).collect::<Vec<_>>();
+        self.draw(&glyphs, false, &text)
+    }
+
+    /// `value` as `1.24M`, drawn as large as fits.
+    pub fn compact(&self, value: u64) -> Vec<String> {
+        let text = format_compact_precise(value);
+        let glyphs = text.chars().map(|ch| Glyph::still(self.font, ch)).collect::<Vec<_>>();
+        self.draw(&glyphs, true, &text)
+    }
+
+    /// The big number part-way between two counts, drawn like an odometer: `value` may be
+    /// fractional, and each digit turns over to the next as the digits below it pass 9.
+    pub fn rolling(&self, value: f64) -> Vec<String> {
+        let font = self.font;
+        let value = value.max(0.0);
+        let places = (value.ceil() as u64).max(1).to_string().len();
+        let mut glyphs = Vec::new();
+        for place in (0..places).rev() {
+            let unit = 10f64.powi(place as i32);
+            let whole = (value / unit).floor();
+            let below = value - whole * unit;
+            let leading = place > 0 && whole == 0.0;
+            let roll = (below - (unit - 1.0)).clamp(0.0, 1.0);
+            let digit = (whole % 10.0) as u32;
+            glyphs.push(Glyph {
+                rows: if leading {
+                    font.blank_digit()
+                } else {
+                    font.rows(char::from_digit(digit, 10).unwrap_or('0'))
+                },
+                next: (roll > 0.0).then(|| {
+                    let next = if leading { 1 } else { (digit + 1) % 10 };
+                    font.rows(char::from_digit(next, 10).unwrap_or('0'))
+                }),
+                roll,
+                narrow: false,
+            });
+            if number_format().separates(place) && (!leading || roll > 0.0) {
+                glyphs.extend(number_format().group.chars().map(|ch| Glyph::still(font, ch)));
+            }
+        }
+        // The smaller tiers can't roll, so they show the whole count reached so far.
+        self.draw(&glyphs, false, &format_with_commas(value.floor() as u64))
+    }
+
+    /// Text fonts are drawn as they are. Block fonts go through a bitmap, trying sizes
+    /// from the largest down (and renderers from full blocks to braille for `Auto`). When
+    /// nothing fits, `text` is drawn in the small digits or, failing that, as it is.
+    fn draw(&self, glyphs: &[Glyph], grow: bool, text: &str) -> Vec<String> {
+        let font = self.font;
+        let drawn = if font.scalable {
+            let candidates = match self.renderer {
+                // Full blocks at their own size (or larger), then smaller numbers in braille,
+                // which stays legible long after halved blocks have turned into smudges.
+                DigitRenderer::Auto => {
+                    let mut candidates = sizes(font, DigitRenderer::Blocks, grow);
+                    candidates.truncate(candidates.len() - halvings(font, DigitRenderer::Blocks));
+                    let smaller = sizes(font, DigitRenderer::Braille, false);
+                    candidates.extend(smaller.into_iter().skip(1));
+                    candidates
+                }
+                renderer => sizes(font, renderer, grow),
+            };
+            candidates
+                .into_iter()
+                .find(|(renderer, pixels)| {
+                    bitmap_width(glyphs, font, *renderer, *pixels) <= self.max_width
+                        && (font.height() * pixels.1).div_ceil(renderer.cell().1) <= self.max_height
+                })
+                .map(|(renderer, pixels)| render_bitmap(glyphs, font, renderer, pixels))
+        } else {
+            Some(render_text(glyphs, font)).filter(|rows| self.fits(rows))
+        };
+        drawn
+            .or_else(|| Some(small_digits(text)).filter(|rows| self.fits(rows)))
+            .unwrap_or_else(|| vec![text.to_string()])
+    }
+
+    fn fits(&self, rows: &[String]) -> bool {
+        rows.len() <= self.max_height && rows.iter().all(|row| row.chars().count() <= self.max_width)
+    }
+}
+
+/// `text` in `SMALL_DIGITS`, with anything but a digit on the bottom row as it is.
+fn small_digits(text: &str) -> Vec<String> {
+    let mut rows = vec![String::new(); SMALL_DIGIT_ROWS];
+    let mut after_digit = false;
+    for ch in text.chars() {
+        match ch.to_digit(10) {
+            Some(digit) => {
+                for (row, part) in rows.iter_mut().zip(SMALL_DIGITS[digit as usize]) {
+                    if after_digit {
+                        row.push(' ');
+                    }
+                    row.push_str(part);
+                }
+                after_digit = true;
+            }
+            None => {
+                rows[0].push(' ');
+                rows[1].push(' ');
+                rows[2].push(ch);
+                after_digit = false;
+            }
+        }
+    }
+    rows
+}
+
+/// Pixels per pattern cell to try with `renderer`, largest first: the compact sizes when
+/// `grow` is set, then the font's own size, then halving down to single pixels.
+fn sizes(font: &Font, renderer: DigitRenderer, grow: bool) -> Vec<(DigitRenderer, (usize, usize))> {
+    let (cell_x, cell_y) = renderer.cell();
+    let full = (font.scale.0 * cell_x, font.scale.1 * cell_y);
+    let mut sizes = Vec::new();
+    if grow {
+        sizes.extend(
+            COMPACT_GROWTH
+                .iter()
+                .filter(|(x, y)| x * y > 4)
+                .map(|(x, y)| (renderer, ((full.0 * x).div_ceil(2), (full.1 * y).div_ceil(2)))),
+        );
+    }
+    sizes.push((renderer, full));
+    let mut pixels = (full.0 / 2, full.1 / 2);
+    while pixels.0 >= 1 && pixels.1 >= 1 {
+        sizes.push((renderer, pixels));
+        pixels = (pixels.0 / 2, pixels.1 / 2);
+    }
+    sizes
+}
+
+/// How many sizes below the font's own `sizes` tries for `renderer`.
+fn halvings(font: &Font, renderer: DigitRenderer) -> usize {
+    let (cell_x, cell_y) = renderer.cell();
+    let smallest = (font.scale.0 * cell_x).min(font.scale.1 * cell_y);
+    smallest.ilog2() as usize
+}
+
+fn gap_before(glyphs: &[Glyph], index: usize) -> bool {
+    index > 0 && !glyphs[index].narrow && !glyphs[index - 1].narrow
+}
+
+fn render_text(glyphs: &[Glyph], font: &Font) -> Vec<String> {
+    let scale = font.scale;
+    let height = font.height() * scale.1;
+    let columns = glyphs.iter().map(|glyph| glyph_rows(glyph, scale.1)).collect::<Vec<_>>();
+    let gap = " ".repeat(font.spacing);
+    let mut lines = Vec::with_capacity(height);
+    for row in 0..height {
+        let mut line = String::new();
+        for (index, column) in columns.iter().enumerate() {
+            if gap_before(glyphs, index) {
+                line.push_str(&gap);
+            }
+            line.push_str(&expand_scaled_row(&column[row], scale.0));
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// The pattern for each output row of a glyph. A rolling digit shows the bottom of its
+/// own pattern above the top of the next one, like a wheel turning upwards.
+fn glyph_rows(glyph: &Glyph, scale_y: usize) -> Vec<String> {
+    let scaled = |rows: &[String]| {
+        rows.iter()
+            .flat_map(|row| std::iter::repeat_n(row.clone(), scale_y))
+            .collect::<Vec<_>>()
+    };
+    let mut strip = scaled(&glyph.rows);
+    let height = strip.len();
+    let shift = (glyph.roll * height as f64).round() as usize;
+    let Some(next) = glyph.next.as_ref().filter(|_| shift > 0) else {
+        return strip;
+    };
+    strip.extend(scaled(next));
+    // Digits of a user font may differ in width; keep the wheel a rectangle.
+    let width = strip.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+    strip
+        .split_off(shift)
+        .into_iter()
+        .take(height)
+        .map(|row| format!("{:<width$}", row, width = width))
+        .collect()
+}
+
+fn expand_scaled_row(row: &str, scale_x: usize) -> String {
+    let mut out = String::with_capacity(row.len() * scale_x);
+    for ch in row.chars() {
+        out.extend(std::iter::repeat_n(ch, scale_x));
+    }
+    out
+}
+
+/// A glyph as pixels, `true` where the font has a block.
+type Bitmap = Vec<Vec<bool>>;
+
+fn bitmap(rows: &[String]) -> Bitmap {
+    rows.iter().map(|row| row.chars().map(|ch| ch != ' ').collect()).collect()
+}
+
+/// The gap between two digits in pixels: the font's spacing, shrunk along with the digits.
+fn gap_pixels(font: &Font, pixels: (usize, usize), renderer: DigitRenderer) -> usize {
+    (font.spacing * pixels.0 / (font.scale.0 * renderer.cell().0).max(1)).max(1)
+}
+
+fn bitmap_width(glyphs: &[Glyph], font: &Font, renderer: DigitRenderer, pixels: (usize, usize)) -> usize {
+    let dots = glyphs
+        .iter()
+        .map(|glyph| glyph.rows.first().map_or(0, |row| row.chars().count()) * pixels.0)
+        .sum::<usize>();
+    let gaps = (0..glyphs.len()).filter(|index| gap_before(glyphs, *index)).count();
+    (dots + gaps * gap_pixels(font, pixels, renderer)).div_ceil(renderer.cell().0)
+}
+
+/// Draws a block font: every pattern cell becomes `pixels` pixels, rounded off with
+/// Scale2x where the factor allows (except for plain full blocks, which keep their
+/// square look), then packed into characters by `renderer`.
+fn render_bitmap(glyphs: &[Glyph], font: &Font, renderer: DigitRenderer, pixels: (usize, usize)) -> Vec<String> {
+    let smoothing = if renderer == DigitRenderer::Blocks {
+        0
+    } else {
+        pixels.0.trailing_zeros().min(pixels.1.trailing_zeros()).min(MAX_SMOOTHING)
+    };
+    let upscale = |rows: &[String]| {
+        let mut bitmap = bitmap(rows);
+        for _ in 0..smoothing {
+            bitmap = scale2x(&bitmap);
+        }
+        stretch(&bitmap, pixels.0 >> smoothing, pixels.1 >> smoothing)
+    };
+    let height = font.height() * pixels.1;
+    let gap = gap_pixels(font, pixels, renderer);
+    let mut canvas: Bitmap = vec![Vec::new(); height];
+    for (index, glyph) in glyphs.iter().enumerate() {
+        if gap_before(glyphs, index) {
+            for row in &mut canvas {
+                row.extend(std::iter::repeat_n(false, gap));
+            }
+        }
+        let mut strip = upscale(&glyph.rows);
+        let shift = (glyph.roll * height as f64).round() as usize;
+        if let Some(next) = glyph.next.as_ref().filter(|_| shift > 0) {
+            strip.extend(upscale(next));
+            strip.drain(..shift);
+        }
+        let width = strip.iter().map(Vec::len).max().unwrap_or(0);
+        for (row, line) in canvas.iter_mut().enumerate() {
+            let pixels = strip.get(row).map(Vec::as_slice).unwrap_or_default();
+            line.extend(pixels.iter().copied().chain(std::iter::repeat(false)).take(width));
+        }
+    }
+    pack(&canvas, renderer)
+}
+
+/// Scale2x (EPX): doubles a bitmap, filling in corners so diagonals come out smooth.
+fn scale2x(bitmap: &Bitmap) -> Bitmap {
+    let height = bitmap.len();
+    let width = bitmap.first().map_or(0, Vec::len);
+    let at = |x: isize, y: isize| -> bool {
+        if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+            return false;
+        }
+        bitmap[y as usize][x as usize]
+    };
+    let mut out = vec![vec![false; width * 2]; height * 2];
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let p = at(x, y);
+            let (a, b, c, d) = (at(x, y - 1), at(x + 1, y), at(x - 1, y), at(x, y + 1));
+            let mut quad = [p; 4];
+            if c == a && c != d && a != b {
+                quad[0] = a;
+            }
+            if a == b && a != c && b != d {
+                quad[1] = b;
+            }
+            if d == c && d != b && c != a {
+                quad[2] = c;
+            }
+            if b == d && b != a && d != c {
+                quad[3] = d;
+            }
+            let (ox, oy) = (x as usize * 2, y as usize * 2);
+            out[oy][ox] = quad[0];
+            out[oy][ox + 1] = quad[1];
+            out[oy + 1][ox] = quad[2];
+            out[oy + 1][ox + 1] = quad[3];
+        }
+    }
+    out
+}
+
+fn stretch(bitmap: &Bitmap, across: usize, down: usize) -> Bitmap {
+    bitmap
+        .iter()
+        .flat_map(|row| {
+            let row = row
+                .iter()
+                .flat_map(|pixel| std::iter::repeat_n(*pixel, across))
+                .collect::<Vec<_>>();
+            std::iter::repeat_n(row, down)
+        })
+        .collect()
+}
+
+fn pack(canvas: &Bitmap, renderer: DigitRenderer) -> Vec<String> {
+    let (cell_x, cell_y) = renderer.cell();
+    let width = canvas.first().map_or(0, Vec::len);
+    let pixel = |x: usize, y: usize| canvas.get(y).and_then(|row| row.get(x)).copied().unwrap_or(false);
+    (0..canvas.len().div_ceil(cell_y))
+        .map(|row| {
+            (0..width.div_ceil(cell_x))
+                .map(|column| {
+                    let (x, y) = (column * cell_x, row * cell_y);
+                    match renderer {
+                        DigitRenderer::Auto | DigitRenderer::Blocks => {
+                            if pixel(x, y) { '█' } else { ' ' }
+                        }
+                        DigitRenderer::HalfBlocks => match (pixel(x, y), pixel(x, y + 1)) {
+                            (true, true) => '█',
+                            (true, false) => '▀',
+                            (false, true) => '▄',
+                            (false, false) => ' ',
+                        },
+                        DigitRenderer::Braille => {
+                            const DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+                            let mut bits = 0;
+                            for (dx, column) in DOTS.iter().enumerate() {
+                                for (dy, bit) in column.iter().enumerate() {
+                                    if pixel(x + dx, y + dy) {
+                                        bits |= bit;
+                                    }
+                                }
+                            }
+                            // Blank braille still takes up a cell; a space keeps copy-paste clean.
+                            if bits == 0 { ' ' } else { char::from_u32(0x2800 + bits).unwrap_or(' ') }
+                        }
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
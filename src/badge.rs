@@ -0,0 +1,68 @@
+use std::io::{self, Write};
+
+use crate::format::format_compact;
+use crate::scan::ScanResult;
+
+const LABEL: &str = "lines of code";
+const LABEL_COLOR: &str = "#555";
+const VALUE_COLOR: &str = "#007ec6";
+
+pub fn write_svg(scan: &ScanResult, out: &mut dyn Write) -> io::Result<()> {
+    let value = format_compact(scan.lines);
+    let label_width = text_width(LABEL);
+    let value_width = text_width(&value);
+    let width = label_width + value_width;
+
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"20\" role=\"img\" aria-label=\"{}: {}\">",
+        width, LABEL, value
+    )?;
+    writeln!(out, "<title>{}: {}</title>", LABEL, value)?;
+    writeln!(
+        out,
+        "<linearGradient id=\"s\" x2=\"0\" y2=\"100%\"><stop offset=\"0\" stop-color=\"#bbb\" stop-opacity=\".1\"/>\
+         <stop offset=\"1\" stop-opacity=\".1\"/></linearGradient>"
+    )?;
+    writeln!(
+        out,
+        "<clipPath id=\"r\"><rect width=\"{}\" height=\"20\" rx=\"3\" fill=\"#fff\"/></clipPath>",
+        width
+    )?;
+    writeln!(
+        out,
+        "<g clip-path=\"url(#r)\"><rect width=\"{}\" height=\"20\" fill=\"{}\"/>\
+         <rect x=\"{}\" width=\"{}\" height=\"20\" fill=\"{}\"/><rect width=\"{}\" height=\"20\" fill=\"url(#s)\"/></g>",
+        label_width, LABEL_COLOR, label_width, value_width, VALUE_COLOR, width
+    )?;
+    writeln!(
+        out,
+        "<g fill=\"#fff\" text-anchor=\"middle\" font-family=\"Verdana,Geneva,DejaVu Sans,sans-serif\" font-size=\"11\">"
+    )?;
+    for (text, x) in [(LABEL, label_width / 2), (value.as_str(), label_width + value_width / 2)] {
+        writeln!(
+            out,
+            "<text x=\"{}\" y=\"15\" fill=\"#010101\" fill-opacity=\".3\">{}</text><text x=\"{}\" y=\"14\">{}</text>",
+            x, text, x, text
+        )?;
+    }
+    writeln!(out, "</g>")?;
+    writeln!(out, "</svg>")?;
+    Ok(())
+}
+
+/// shields.io "endpoint" badge, see https://shields.io/badges/endpoint-badge
+pub fn write_shields_json(scan: &ScanResult, out: &mut dyn Write) -> io::Result<()> {
+    let endpoint = serde_json::json!({
+        "schemaVersion": 1,
+        "label": LABEL,
+        "message": format_compact(scan.lines),
+        "color": "blue",
+    });
+    writeln!(out, "{}", endpoint)
+}
+
+/// Rough Verdana 11px text width plus padding; close enough for short labels.
+fn text_width(text: &str) -> u32 {
+    text.chars().count() as u32 * 7 + 10
+}
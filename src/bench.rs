@@ -0,0 +1,154 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use codecounter::cache::LineCache;
+use codecounter::cli::BenchArgs;
+use codecounter::config::Settings;
+use codecounter::format::{format_decimal, format_with_commas};
+use codecounter::scan::scan;
+
+/// The timed runs of one way of scanning.
+#[derive(Debug, Serialize)]
+struct Configuration {
+    cache: bool,
+    threads: usize,
+    min_ms: f64,
+    median_ms: f64,
+    mean_ms: f64,
+    max_ms: f64,
+    stddev_ms: f64,
+    files_per_second: f64,
+}
+
+impl Configuration {
+    fn new(cache: bool, threads: usize, mut times: Vec<Duration>, files: u64) -> Self {
+        times.sort();
+        let ms = times.iter().map(|time| time.as_secs_f64() * 1000.0).collect::<Vec<_>>();
+        let count = ms.len() as f64;
+        let mean = ms.iter().sum::<f64>() / count;
+        let middle = ms.len() / 2;
+        let median = if ms.len().is_multiple_of(2) { (ms[middle - 1] + ms[middle]) / 2.0 } else { ms[middle] };
+        let variance = ms.iter().map(|time| (time - mean).powi(2)).sum::<f64>() / count;
+        Self {
+            cache,
+            threads,
+            min_ms: ms[0],
+            median_ms: median,
+            mean_ms: mean,
+            max_ms: ms[ms.len() - 1],
+            stddev_ms: variance.sqrt(),
+            files_per_second: files as f64 * 1000.0 / median.max(f64::EPSILON),
+        }
+    }
+
+    fn label(&self) -> String {
+        format!(
+            "{}, {} {}",
+            if self.cache { "cached" } else { "no cache" },
+            self.threads,
+            if self.threads == 1 { "thread" } else { "threads" }
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct BenchReport {
+    roots: Vec<PathBuf>,
+    files: u64,
+    lines: u64,
+    runs: u32,
+    configurations: Vec<Configuration>,
+}
+
+/// Times `args.runs` scans of `roots` for each thread count with an empty line cache, then
+/// with the cache full, after one untimed scan.
+pub fn run(args: &BenchArgs, roots: &[PathBuf], settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let cancel = AtomicBool::new(false);
+    // The untimed scan warms the OS's file cache, so the first configuration isn't the only
+    // one reading from disk, and fills the line cache for the cached runs.
+    let mut warm = LineCache::default();
+    let baseline = scan(roots, settings, &mut warm, &|_| {}, &cancel)?;
+    let mut thread_counts = if args.thread_counts.is_empty() {
+        vec![1, settings.threads]
+    } else {
+        args.thread_counts.iter().map(|threads| *threads as usize).collect()
+    };
+    thread_counts.dedup();
+
+    let mut configurations = Vec::new();
+    for threads in thread_counts {
+        let settings = Settings {
+            threads,
+            ..settings.clone()
+        };
+        let times = (0..args.runs)
+            .map(|_| time(|| scan(roots, &settings, &mut LineCache::default(), &|_| {}, &cancel).map(drop)))
+            .collect::<Result<Vec<_>, _>>()?;
+        configurations.push(Configuration::new(false, threads, times, baseline.files));
+    }
+    let times = (0..args.runs)
+        .map(|_| time(|| scan(roots, settings, &mut warm, &|_| {}, &cancel).map(drop)))
+        .collect::<Result<Vec<_>, _>>()?;
+    configurations.push(Configuration::new(true, settings.threads, times, baseline.files));
+
+    let report = BenchReport {
+        roots: roots.to_vec(),
+        files: baseline.files,
+        lines: baseline.lines,
+        runs: args.runs,
+        configurations,
+    };
+    let mut out = io::stdout().lock();
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &report)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+    write_text(&report, &mut out)?;
+    Ok(())
+}
+
+fn time(run: impl FnOnce() -> Result<(), Box<dyn Error>>) -> Result<Duration, Box<dyn Error>> {
+    let started = Instant::now();
+    run()?;
+    Ok(started.elapsed())
+}
+
+fn write_text(report: &BenchReport, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} files, {} lines of code; {} timed {} per configuration after a warm-up scan.",
+        format_with_commas(report.files),
+        format_with_commas(report.lines),
+        report.runs,
+        if report.runs == 1 { "scan" } else { "scans" }
+    )?;
+    writeln!(out)?;
+    let labels = report.configurations.iter().map(Configuration::label).collect::<Vec<_>>();
+    let label_width = labels.iter().map(String::len).max().unwrap_or(0).max("Configuration".len());
+    writeln!(
+        out,
+        "{:<label_width$}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}",
+        "Configuration", "Min", "Median", "Mean", "Max", "Std dev", "Files/s"
+    )?;
+    let ms = |value: f64| format!("{} ms", format_decimal(value, 1));
+    for (configuration, label) in report.configurations.iter().zip(&labels) {
+        writeln!(
+            out,
+            "{:<label_width$}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}  {:>10}",
+            label,
+            ms(configuration.min_ms),
+            ms(configuration.median_ms),
+            ms(configuration.mean_ms),
+            ms(configuration.max_ms),
+            ms(configuration.stddev_ms),
+            format_with_commas(configuration.files_per_second.round() as u64)
+        )?;
+    }
+    Ok(())
+}
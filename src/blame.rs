@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+
+use codecounter::cache::{BlameCache, ObjectCache};
+use codecounter::config::Settings;
+use codecounter::format::{format_share, format_with_commas};
+use codecounter::git;
+use codecounter::scan::{counted_category, is_counted};
+
+#[derive(Debug, Clone)]
+pub struct AuthorLines {
+    pub name: String,
+    pub lines: u64,
+    /// Files with at least one line by this author.
+    pub files: u64,
+}
+
+/// Lines of code per author as of HEAD, most lines first. Only the files a scan would count
+/// are blamed, and each file's lines are shared out by blame to make what it adds to the
+/// total, so the leaderboard adds up to the headline number. Blaming is slow, so results
+/// are cached per file content and only new or changed files are blamed again.
+pub fn ownership(dir: &Path, settings: &Settings) -> Result<Vec<AuthorLines>, Box<dyn Error>> {
+    let files = git::tree_files(dir, "HEAD")?
+        .into_iter()
+        .filter(|(_, path)| is_counted(path, settings))
+        .collect::<Vec<_>>();
+    let mut objects = ObjectCache::load();
+    let counts = git::blob_counts(dir, &files, &mut objects)?;
+    objects.save()?;
+    let files = files
+        .into_iter()
+        .zip(counts)
+        .filter_map(|(file, count)| {
+            let count = count?;
+            let category = counted_category(&file.1, &count, settings)?;
+            Some((file, count.weighted_lines(category, &settings.categories)))
+        })
+        .collect::<Vec<_>>();
+
+    let mut cache = BlameCache::load(git::repo_id(dir)?);
+    let missing = files
+        .iter()
+        .map(|(file, _)| file)
+        .filter(|(hash, path)| cache.get(hash, path).is_none())
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        eprintln!("Running git blame on {} files…", format_with_commas(missing.len() as u64));
+    }
+    for ((hash, path), authors) in blame_all(dir, &missing, settings.threads)? {
+        cache.insert(&hash, &path, authors);
+    }
+
+    let mut authors = HashMap::<String, AuthorLines>::new();
+    for ((hash, path), weighted) in &files {
+        let blamed = cache.get(hash, path).unwrap_or_default();
+        for (name, lines) in share_out(blamed, *weighted).into_iter().filter(|(_, lines)| *lines > 0) {
+            let author = authors.entry(name.clone()).or_insert_with(|| AuthorLines {
+                name: name.clone(),
+                lines: 0,
+                files: 0,
+            });
+            author.lines += lines;
+            author.files += 1;
+        }
+    }
+    cache.save()?;
+    let mut authors = authors.into_values().collect::<Vec<_>>();
+    authors.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.name.cmp(&b.name)));
+    Ok(authors)
+}
+
+/// Splits `total` between the authors in proportion to the lines blamed on each, rounding
+/// by largest remainder so the shares add up to `total` exactly.
+fn share_out(blamed: &[(String, u64)], total: u64) -> Vec<(String, u64)> {
+    let blamed_lines = u128::from(blamed.iter().map(|(_, lines)| lines).sum::<u64>());
+    if blamed_lines == 0 {
+        return Vec::new();
+    }
+    let mut shares = blamed
+        .iter()
+        .map(|(name, lines)| {
+            let exact = u128::from(*lines) * u128::from(total);
+            (name.clone(), (exact / blamed_lines) as u64, exact % blamed_lines)
+        })
+        .collect::<Vec<_>>();
+    let left = total - shares.iter().map(|(_, share, _)| share).sum::<u64>();
+    let mut order = (0..shares.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| shares[*b].2.cmp(&shares[*a].2).then_with(|| shares[*a].0.cmp(&shares[*b].0)));
+    for index in order.into_iter().take(left as usize) {
+        shares[index].1 += 1;
+    }
+    shares.into_iter().map(|(name, share, _)| (name, share)).collect()
+}
+
+type Blamed = ((String, PathBuf), Vec<(String, u64)>);
+
+/// Blames `files` on `threads` workers; each `git blame` is its own process.
+fn blame_all(dir: &Path, files: &[&(String, PathBuf)], threads: usize) -> Result<Vec<Blamed>, Box<dyn Error>> {
+    let queue = Mutex::new(files.iter());
+    let results = Mutex::new(Vec::with_capacity(files.len()));
+    let failure = Mutex::new(None);
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| {
+                loop {
+                    let Some(&file) = queue.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).next() else {
+                        return;
+                    };
+                    match git::blame_authors(dir, "HEAD", &file.1) {
+                        Ok(authors) => results
+                            .lock()
+                            .unwrap_or_else(|poisoned| poisoned.into_inner())
+                            .push((file.clone(), authors)),
+                        Err(err) => {
+                            let message = format!("{}: {}", dir.join(&file.1).display(), err);
+                            failure.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get_or_insert(message);
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    if let Some(message) = failure.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+        return Err(message.into());
+    }
+    Ok(results.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner()))
+}
+
+pub fn write_text(authors: &[AuthorLines], out: &mut dyn Write) -> io::Result<()> {
+    let total = authors.iter().map(|author| author.lines).sum::<u64>();
+    let lines_width = authors
+        .iter()
+        .map(|author| format_with_commas(author.lines).len())
+        .max()
+        .unwrap_or(0);
+    let files_width = authors
+        .iter()
+        .map(|author| format_with_commas(author.files).len())
+        .max()
+        .unwrap_or(0);
+    for author in authors {
+        writeln!(
+            out,
+            "{:>lines_width$} lines  {:>6}  {:>files_width$} files  {}",
+            format_with_commas(author.lines),
+            format_share(author.lines, total),
+            format_with_commas(author.files),
+            author.name,
+            lines_width = lines_width,
+            files_width = files_width
+        )?;
+    }
+    Ok(())
+}
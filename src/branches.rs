@@ -0,0 +1,134 @@
+use std::error::Error;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+use serde::Serialize;
+
+use codecounter::cache::LineCache;
+use codecounter::cli::BranchesArgs;
+use codecounter::config::Settings;
+use codecounter::format::{format_signed, format_with_commas};
+use codecounter::git;
+use codecounter::scan::{scan, ScanResult};
+
+use crate::diff::{self, format_relative, ScanDiff};
+use crate::tempdir::TempDir;
+
+#[derive(Debug, Serialize)]
+pub struct BranchTotals {
+    pub reference: String,
+    pub commit: String,
+    pub lines: u64,
+    pub files: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct BranchReport {
+    branches: Vec<BranchTotals>,
+    /// Each branch after the first, language by language against the first.
+    comparisons: Vec<ScanDiff>,
+}
+
+/// Counts each of `args.refs` from a scratch copy of its tree and compares them with the first.
+pub fn run(args: &BranchesArgs, dir: &Path, settings: &Settings, plain: bool) -> Result<(), Box<dyn Error>> {
+    for reference in &args.refs {
+        git::verify_ref(dir, reference)?;
+    }
+    let mut branches = Vec::new();
+    let mut scans = Vec::new();
+    for reference in &args.refs {
+        let scan = scan_ref(dir, reference, settings)?;
+        branches.push(BranchTotals {
+            reference: reference.clone(),
+            commit: git::short_hash(dir, reference)?,
+            lines: scan.lines,
+            files: scan.files,
+        });
+        scans.push(scan);
+    }
+    let comparisons = scans
+        .iter()
+        .zip(&args.refs)
+        .skip(1)
+        .map(|(scan, reference)| ScanDiff {
+            a: PathBuf::from(&args.refs[0]),
+            b: PathBuf::from(reference),
+            ..ScanDiff::new(&scans[0], scan)
+        })
+        .collect::<Vec<_>>();
+
+    if args.json {
+        let mut out = io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &BranchReport { branches, comparisons })?;
+        writeln!(out)?;
+        return Ok(());
+    }
+    if let [comparison] = comparisons.as_slice()
+        && !plain
+        && io::stdout().is_terminal()
+    {
+        return diff::show(comparison, settings);
+    }
+    let mut out = io::stdout().lock();
+    write_totals(&branches, &mut out)?;
+    for comparison in &comparisons {
+        writeln!(out)?;
+        diff::write_text(comparison, &mut out)?;
+    }
+    Ok(())
+}
+
+fn scan_ref(dir: &Path, reference: &str, settings: &Settings) -> Result<ScanResult, Box<dyn Error>> {
+    let tree = TempDir::new("branch")?;
+    git::extract_tree(dir, reference, tree.path())?;
+    // The scratch copy is gone afterwards, so there is nothing worth caching.
+    scan(
+        &[tree.path().to_path_buf()],
+        settings,
+        &mut LineCache::default(),
+        &|_| {},
+        &AtomicBool::new(false),
+    )
+}
+
+fn write_totals(branches: &[BranchTotals], out: &mut dyn Write) -> io::Result<()> {
+    let Some(first) = branches.first() else {
+        return Ok(());
+    };
+    let reference_width = branches.iter().map(|branch| branch.reference.chars().count()).max().unwrap_or(0);
+    let lines_width = branches
+        .iter()
+        .map(|branch| format_with_commas(branch.lines).len())
+        .max()
+        .unwrap_or(0);
+    let files_width = branches
+        .iter()
+        .map(|branch| format_with_commas(branch.files).len())
+        .max()
+        .unwrap_or(0);
+    for branch in branches {
+        let change = if std::ptr::eq(branch, first) {
+            String::new()
+        } else {
+            format!(
+                "  {} ({})",
+                format_signed(branch.lines as i64 - first.lines as i64),
+                format_relative(first.lines, branch.lines)
+            )
+        };
+        writeln!(
+            out,
+            "{:<reference_width$}  {}  {:>lines_width$} lines  {:>files_width$} files{}",
+            branch.reference,
+            branch.commit,
+            format_with_commas(branch.lines),
+            format_with_commas(branch.files),
+            change,
+            reference_width = reference_width,
+            lines_width = lines_width,
+            files_width = files_width
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::analyzer::PatternAnalyzer;
+use crate::config::{data_home, Settings};
+use crate::scan::FileCount;
+
+pub const CACHE_DIR: &str = ".codecounter-cache";
+const CACHE_FILE: &str = "files.json";
+const CACHE_VERSION: u32 = 14;
+const OBJECT_CACHE_FILE: &str = "git-objects.json";
+const OBJECT_CACHE_VERSION: u32 = 2;
+const BLAME_CACHE_FILE: &str = "git-blame.json";
+const BLAME_CACHE_VERSION: u32 = 1;
+/// Git object and blame entries that no run has read for this many days are dropped.
+const UNUSED_DAYS: u64 = 30;
+
+/// Days since the Unix epoch, the clock the git caches age their entries by.
+fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    modified_ns: u64,
+    size: u64,
+}
+
+impl FileStamp {
+    fn from_metadata(metadata: &Metadata) -> Option<Self> {
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            modified_ns: modified.as_nanos() as u64,
+            size: metadata.len(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    stamp: FileStamp,
+    /// `None` for binary files.
+    count: Option<FileCount>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    /// TODO markers the entries were counted with; other markers need a fresh read.
+    todo_markers: Vec<String>,
+    /// Whether the entries were counted with `--complexity`.
+    #[serde(default)]
+    complexity: bool,
+    /// The pattern analyzers the entries were counted with.
+    #[serde(default)]
+    patterns: Vec<PatternAnalyzer>,
+    entries: HashMap<PathBuf, CacheEntry>,
+}
+
+#[derive(Debug, Default)]
+pub struct LineCache {
+    file: CacheFile,
+    seen: HashSet<PathBuf>,
+    disk_path: Option<PathBuf>,
+    dirty: bool,
+}
+
+impl LineCache {
+    pub fn new(dir: &Path, settings: &Settings) -> Self {
+        if !settings.persist_cache {
+            return Self::default();
+        }
+        let disk_path = dir.join(CACHE_DIR).join(CACHE_FILE);
+        let file = fs::read(&disk_path)
+            .ok()
+            .and_then(|raw| serde_json::from_slice::<CacheFile>(&raw).ok())
+            .filter(|file| {
+                file.version == CACHE_VERSION
+                    && file.todo_markers == settings.todo_markers
+                    && file.complexity == settings.complexity
+                    && file.patterns == settings.analyzers.patterns()
+            })
+            .unwrap_or_else(|| CacheFile {
+                todo_markers: settings.todo_markers.clone(),
+                complexity: settings.complexity,
+                patterns: settings.analyzers.patterns().to_vec(),
+                ..CacheFile::default()
+            });
+        Self {
+            file,
+            disk_path: Some(disk_path),
+            ..Self::default()
+        }
+    }
+
+    pub fn get(&mut self, path: &Path, metadata: &Metadata) -> Option<Option<FileCount>> {
+        self.seen.insert(path.to_path_buf());
+        let stamp = FileStamp::from_metadata(metadata)?;
+        self.file
+            .entries
+            .get(path)
+            .filter(|entry| entry.stamp == stamp)
+            .map(|entry| entry.count.clone())
+    }
+
+    pub fn insert(&mut self, path: &Path, metadata: &Metadata, count: Option<FileCount>) {
+        if let Some(stamp) = FileStamp::from_metadata(metadata) {
+            self.file.entries.insert(path.to_path_buf(), CacheEntry { stamp, count });
+            self.dirty = true;
+        }
+    }
+
+    pub fn finish_scan(&mut self) -> io::Result<()> {
+        let before = self.file.entries.len();
+        let seen = std::mem::take(&mut self.seen);
+        self.file.entries.retain(|path, _| seen.contains(path));
+        self.dirty |= self.file.entries.len() != before;
+
+        let Some(disk_path) = &self.disk_path else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(parent) = disk_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.file.version = CACHE_VERSION;
+        fs::write(disk_path, serde_json::to_vec(&self.file)?)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ObjectCacheFile {
+    version: u32,
+    counts: HashMap<String, ObjectEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ObjectEntry {
+    /// `None` for binary blobs.
+    count: Option<FileCount>,
+    /// The day, see [`today`], the entry was last read or written.
+    used: u64,
+}
+
+/// Counts of git blobs, keyed by object hash, with what placing them in the total needs. A blob's content never changes, so
+/// entries stay valid across commits and repositories; they're only dropped once unused
+/// for [`UNUSED_DAYS`].
+#[derive(Debug, Default)]
+pub struct ObjectCache {
+    file: ObjectCacheFile,
+    disk_path: Option<PathBuf>,
+    today: u64,
+    dirty: bool,
+}
+
+impl ObjectCache {
+    pub fn load() -> Self {
+        let disk_path = data_home().map(|home| home.join("codecounter").join(OBJECT_CACHE_FILE));
+        let file = disk_path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|raw| serde_json::from_slice::<ObjectCacheFile>(&raw).ok())
+            .filter(|file| file.version == OBJECT_CACHE_VERSION)
+            .unwrap_or_default();
+        Self {
+            file,
+            disk_path,
+            today: today(),
+            dirty: false,
+        }
+    }
+
+    pub fn get(&mut self, hash: &str) -> Option<Option<FileCount>> {
+        let entry = self.file.counts.get_mut(hash)?;
+        if entry.used != self.today {
+            entry.used = self.today;
+            self.dirty = true;
+        }
+        Some(entry.count.clone())
+    }
+
+    pub fn insert(&mut self, hash: String, count: Option<FileCount>) {
+        self.file.counts.insert(hash, ObjectEntry { count, used: self.today });
+        self.dirty = true;
+    }
+
+    pub fn save(&mut self) -> io::Result<()> {
+        let Some(disk_path) = &self.disk_path else {
+            return Ok(());
+        };
+        let before = self.file.counts.len();
+        let today = self.today;
+        self.file.counts.retain(|_, entry| today.saturating_sub(entry.used) < UNUSED_DAYS);
+        if !self.dirty && self.file.counts.len() == before {
+            return Ok(());
+        }
+        if let Some(parent) = disk_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.file.version = OBJECT_CACHE_VERSION;
+        fs::write(disk_path, serde_json::to_vec(&self.file)?)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BlameCacheFile {
+    version: u32,
+    /// Keyed by `<repository> <blob hash> <path>`, see [`BlameCache::load`].
+    authors: HashMap<String, BlameEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BlameEntry {
+    /// Lines per author.
+    authors: Vec<(String, u64)>,
+    /// The day, see [`today`], the entry was last read or written.
+    used: u64,
+}
+
+/// `git blame` results per file of one repository. The same content at the same path
+/// blames the same way no matter which commit is checked out, so unchanged files are never
+/// blamed twice. Entries unused for [`UNUSED_DAYS`] are dropped.
+#[derive(Debug, Default)]
+pub struct BlameCache {
+    file: BlameCacheFile,
+    disk_path: Option<PathBuf>,
+    repo: String,
+    today: u64,
+    dirty: bool,
+}
+
+impl BlameCache {
+    /// The cache for the repository `repo` names, see [`git::repo_id`](crate::git::repo_id):
+    /// the same file in an unrelated repository has other history, and other authors.
+    pub fn load(repo: String) -> Self {
+        let disk_path = data_home().map(|home| home.join("codecounter").join(BLAME_CACHE_FILE));
+        let file = disk_path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .and_then(|raw| serde_json::from_slice::<BlameCacheFile>(&raw).ok())
+            .filter(|file| file.version == BLAME_CACHE_VERSION)
+            .unwrap_or_default();
+        Self {
+            file,
+            disk_path,
+            repo,
+            today: today(),
+            dirty: false,
+        }
+    }
+
+    fn key(&self, hash: &str, path: &Path) -> String {
+        format!("{} {} {}", self.repo, hash, path.display())
+    }
+
+    pub fn get(&mut self, hash: &str, path: &Path) -> Option<&[(String, u64)]> {
+        let key = self.key(hash, path);
+        let entry = self.file.authors.get_mut(&key)?;
+        if entry.used != self.today {
+            entry.used = self.today;
+            self.dirty = true;
+        }
+        Some(&entry.authors)
+    }
+
+    pub fn insert(&mut self, hash: &str, path: &Path, authors: Vec<(String, u64)>) {
+        let key = self.key(hash, path);
+        self.file.authors.insert(key, BlameEntry { authors, used: self.today });
+        self.dirty = true;
+    }
+
+    pub fn save(&mut self) -> io::Result<()> {
+        let Some(disk_path) = &self.disk_path else {
+            return Ok(());
+        };
+        let before = self.file.authors.len();
+        let today = self.today;
+        self.file.authors.retain(|_, entry| today.saturating_sub(entry.used) < UNUSED_DAYS);
+        if !self.dirty && self.file.authors.len() == before {
+            return Ok(());
+        }
+        if let Some(parent) = disk_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        self.file.version = BLAME_CACHE_VERSION;
+        fs::write(disk_path, serde_json::to_vec(&self.file)?)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+/// File in a scanned directory that excludes paths with gitignore syntax.
+pub const CCIGNORE_FILE: &str = ".ccignore";
+
+#[derive(Debug, Clone)]
+struct Rule {
+    glob: GlobMatcher,
+    /// `!pattern`: puts back what an earlier rule excluded.
+    negated: bool,
+    /// `pattern/`: only matches directories.
+    dir_only: bool,
+}
+
+/// The rules of a `.ccignore`, matched like `.gitignore`: the last matching rule wins,
+/// and nothing below an excluded directory can be put back.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    /// Reads `dir/.ccignore`, or no rules at all when there isn't one.
+    pub fn load(dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = dir.join(CCIGNORE_FILE);
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text).map_err(|err| format!("{}: {}", path.display(), err).into()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(format!("{}: {}", path.display(), err).into()),
+        }
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, pattern) = match pattern.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, pattern),
+            };
+            // A slash anywhere but the end ties the pattern to the directory of the file;
+            // otherwise it matches at any depth.
+            let anchored = pattern.contains('/');
+            let pattern = pattern.trim_start_matches('/');
+            let pattern = if anchored { pattern.to_string() } else { format!("**/{}", pattern) };
+            let glob = GlobBuilder::new(&pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|err| format!("line {}: {}", number + 1, err))?;
+            rules.push(Rule {
+                glob: glob.compile_matcher(),
+                negated,
+                dir_only,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether `relative` (to the directory holding the `.ccignore`) is excluded, either
+    /// itself or through one of its parent directories.
+    pub fn is_ignored(&self, relative: &Path, is_dir: bool) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+        let mut parents = relative.ancestors().skip(1).filter(|parent| !parent.as_os_str().is_empty());
+        parents.any(|parent| self.matches(parent, true)) || self.matches(relative, is_dir)
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.glob.is_match(path))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> IgnoreRules {
+        IgnoreRules::parse(text).unwrap()
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines() {
+        let rules = parse("# generated\n\n   \n*.log\n\\#kept\n");
+        assert_eq!(rules.len(), 2);
+        assert!(rules.is_ignored(Path::new("#kept"), false));
+    }
+
+    #[test]
+    fn unanchored_patterns_match_at_any_depth() {
+        let rules = parse("*.log\n");
+        assert!(rules.is_ignored(Path::new("build.log"), false));
+        assert!(rules.is_ignored(Path::new("a/b/build.log"), false));
+        assert!(!rules.is_ignored(Path::new("build.rs"), false));
+    }
+
+    #[test]
+    fn anchored_patterns_match_from_the_root() {
+        let rules = parse("/target\ndocs/*.md\n");
+        assert!(rules.is_ignored(Path::new("target"), true));
+        assert!(!rules.is_ignored(Path::new("crates/target"), true));
+        assert!(rules.is_ignored(Path::new("docs/intro.md"), false));
+        assert!(!rules.is_ignored(Path::new("docs/api/intro.md"), false));
+    }
+
+    #[test]
+    fn directory_patterns_only_match_directories() {
+        let rules = parse("out/\n");
+        assert!(rules.is_ignored(Path::new("out"), true));
+        assert!(!rules.is_ignored(Path::new("out"), false));
+        assert!(rules.is_ignored(Path::new("out/main.rs"), false));
+        assert!(rules.is_ignored(Path::new("src/out/main.rs"), false));
+    }
+
+    #[test]
+    fn the_last_matching_rule_wins() {
+        let rules = parse("*.rs\n!keep.rs\n");
+        assert!(rules.is_ignored(Path::new("main.rs"), false));
+        assert!(!rules.is_ignored(Path::new("src/keep.rs"), false));
+        let rules = parse("!keep.rs\n*.rs\n");
+        assert!(rules.is_ignored(Path::new("keep.rs"), false));
+    }
+
+    #[test]
+    fn nothing_under_an_excluded_directory_comes_back() {
+        let rules = parse("vendor/\n!vendor/keep.rs\n");
+        assert!(rules.is_ignored(Path::new("vendor/keep.rs"), false));
+    }
+
+    #[test]
+    fn bad_globs_name_their_line() {
+        let err = IgnoreRules::parse("*.rs\n[z-a\n").unwrap_err();
+        assert!(err.starts_with("line 2:"), "{}", err);
+    }
+}
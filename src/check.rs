@@ -0,0 +1,103 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+use codecounter::cache::LineCache;
+use codecounter::cli::CheckArgs;
+use codecounter::config::Settings;
+use codecounter::format::format_with_commas;
+use codecounter::scan::{scan, ScanResult};
+
+/// Scans `roots` once and prints every limit in `args` the code is over. `Ok(false)` means
+/// the budget was exceeded, or that the scan was partial and `--allow-partial` wasn't
+/// given, since a short total could pass a budget the code is really over; scan failures
+/// are errors instead.
+pub fn run(args: &CheckArgs, roots: &[PathBuf], base: &Path, settings: &Settings) -> Result<bool, Box<dyn Error>> {
+    let mut cache = LineCache::new(base, settings);
+    let scan = scan(roots, settings, &mut cache, &|_| {}, &AtomicBool::new(false))?;
+    let partial = partial_reasons(&scan);
+    let level = if args.allow_partial { "warning" } else { "error" };
+    for reason in &partial {
+        eprintln!("{}: {}", level, reason);
+    }
+    let mut report = Vec::new();
+    let violations = write_violations(args, &scan, &mut report)?;
+    // Limits a partial scan is already over still stand; being within them proves nothing.
+    if violations == 0 && !partial.is_empty() && !args.allow_partial {
+        eprintln!("The scan was partial, so the budget couldn't be checked; --allow-partial checks it anyway.");
+        return Ok(false);
+    }
+    io::stdout().lock().write_all(&report)?;
+    Ok(violations == 0)
+}
+
+/// Why the scan's totals may fall short of the code's, one reason each.
+fn partial_reasons(scan: &ScanResult) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if scan.truncated {
+        reasons.push("stopped early at --max-files, so the total is partial".to_string());
+    }
+    if scan.over_budget_files > 0 {
+        reasons.push(format!(
+            "skipped {} files once --max-bytes had been read, so the total is partial",
+            format_with_commas(scan.over_budget_files)
+        ));
+    }
+    if !scan.errors.is_empty() {
+        reasons.push(format!(
+            "couldn't read {} paths, so the total may be short",
+            format_with_commas(scan.errors.len() as u64)
+        ));
+    }
+    reasons
+}
+
+/// Writes one line per exceeded limit, or a summary when there are none, and returns how
+/// many there were.
+fn write_violations(args: &CheckArgs, scan: &ScanResult, out: &mut dyn Write) -> io::Result<usize> {
+    let mut violations = 0;
+    if let Some(limit) = args.max_lines
+        && scan.lines > limit
+    {
+        writeln!(
+            out,
+            "{} lines of code, over the limit of {}",
+            format_with_commas(scan.lines),
+            format_with_commas(limit)
+        )?;
+        violations += 1;
+    }
+    if let Some(limit) = args.max_file_lines {
+        let mut files = scan.file_stats.iter().filter(|file| file.lines > limit).collect::<Vec<_>>();
+        files.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.path.cmp(&b.path)));
+        for file in files {
+            writeln!(
+                out,
+                "{}: {} lines, over the per-file limit of {}",
+                scan.display_path(file),
+                format_with_commas(file.lines),
+                format_with_commas(limit)
+            )?;
+            violations += 1;
+        }
+    }
+
+    if violations > 0 {
+        writeln!(out, "Over budget: {} violations.", format_with_commas(violations as u64))?;
+        return Ok(violations);
+    }
+    let mut summary = format!("Within budget: {} lines of code", format_with_commas(scan.lines));
+    if let Some(limit) = args.max_lines {
+        summary.push_str(&format!(" (limit {})", format_with_commas(limit)));
+    }
+    if let (Some(limit), Some(largest)) = (args.max_file_lines, scan.largest.first()) {
+        summary.push_str(&format!(
+            ", largest file {} lines (limit {})",
+            format_with_commas(largest.lines),
+            format_with_commas(limit)
+        ));
+    }
+    writeln!(out, "{}.", summary)?;
+    Ok(0)
+}
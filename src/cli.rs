@@ -0,0 +1,347 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{ArgGroup, Args, Parser, Subcommand};
+
+use crate::export::ExportFormat;
+use crate::format::{parse_amount, parse_byte_size, parse_duration, parse_size};
+use crate::logging::LogFormat;
+use crate::theme::ThemePreset;
+
+#[derive(Debug, Clone, Parser)]
+#[command(name = "codecounter", version, about = "Counts the lines of code in one or more directories")]
+#[command(group(ArgGroup::new("export").args(["output", "report"])))]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Directories to scan (defaults to the current directory)
+    #[arg(value_name = "DIR")]
+    pub paths: Vec<PathBuf>,
+
+    /// Read configuration from this file instead of the default locations
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Extra language definitions (TOML or JSON) instead of ~/.config/codecounter/languages.toml
+    #[arg(long, global = true, value_name = "FILE")]
+    pub languages: Option<PathBuf>,
+
+    /// Rescan automatically on this interval, e.g. `30s`, `5m` or plain seconds
+    #[arg(long, global = true, value_name = "INTERVAL", value_parser = parse_duration)]
+    pub refresh: Option<Duration>,
+
+    /// Skip files and directories with this name (repeatable)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub ignore: Vec<String>,
+
+    /// Skip paths matching this glob, relative to the scanned directory (repeatable)
+    #[arg(long, global = true, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Only count files matching this glob, relative to the scanned directory (repeatable)
+    #[arg(long, global = true, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// Only count files with these extensions, e.g. `--ext rs,py`
+    #[arg(long, global = true, value_name = "EXTS", value_delimiter = ',')]
+    pub ext: Vec<String>,
+
+    /// Never count files with these extensions, e.g. `--no-ext md,json`
+    #[arg(long, global = true, value_name = "EXTS", value_delimiter = ',')]
+    pub no_ext: Vec<String>,
+
+    /// Count generated files and vendored directories in the total instead of setting them aside
+    #[arg(long, global = true)]
+    pub count_generated: bool,
+
+    /// Count minified files (`*.min.js`, or code averaging 300+ characters a line) in the total instead of setting them aside
+    #[arg(long, global = true)]
+    pub count_minified: bool,
+
+    /// Count docs, config and data in the total at full weight, not only code
+    #[arg(long, global = true)]
+    pub all_categories: bool,
+
+    /// Count git submodules (the paths in `.gitmodules`), which are left out by default
+    #[arg(long, global = true)]
+    pub include_submodules: bool,
+
+    /// Count what git ignores too (`.gitignore`, `.git/info/exclude`, the global excludes file)
+    #[arg(long, global = true)]
+    pub no_gitignore: bool,
+
+    /// Estimate each file's complexity (branch keywords, nesting depth) and list the most complex files
+    #[arg(long, global = true)]
+    pub complexity: bool,
+
+    /// Descend into symlinked directories and count symlinked files (each file at most once)
+    #[arg(long, global = true)]
+    pub follow_symlinks: bool,
+
+    /// Count every dotfile and dot-directory, not just well-known ones like `.github` and `.eslintrc.js`
+    #[arg(long, global = true, conflicts_with = "no_hidden")]
+    pub hidden: bool,
+
+    /// Skip every dotfile and dot-directory, well-known ones included
+    #[arg(long, global = true)]
+    pub no_hidden: bool,
+
+    /// Don't descend more than N directories below each root
+    #[arg(long, global = true, value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Stop after N code files, for a quick look at huge trees
+    #[arg(long, global = true, value_name = "N")]
+    pub max_files: Option<usize>,
+
+    /// Skip files bigger than SIZE, e.g. `10M`, `512K` or plain bytes, such as dumps and logs
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_byte_size)]
+    pub max_file_size: Option<u64>,
+
+    /// Count JSON, SQL, XML and YAML files bigger than SIZE as data dumps, outside the total (default 1M)
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_byte_size)]
+    pub dump_size: Option<u64>,
+
+    /// Stop reading files once SIZE has been read in a scan (e.g. `2G`); the rest are skipped and reported
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_byte_size)]
+    pub max_bytes: Option<u64>,
+
+    /// Count the files listed in FILE, one per line, instead of walking the directories (`-` reads stdin)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub files_from: Option<PathBuf>,
+
+    /// Read and count files on N threads (defaults to the number of cores)
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub threads: Option<u32>,
+
+    /// Keep line counts in `.codecounter-cache` so later runs only re-read changed files
+    #[arg(long, global = true)]
+    pub cache: bool,
+
+    /// Don't read or write the on-disk cache
+    #[arg(long, global = true)]
+    pub no_cache: bool,
+
+    /// Append scan timings, skipped files, cache hits and errors to FILE (the TUI hides stderr)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub log: Option<PathBuf>,
+
+    /// Write the `--log` file as text lines or as JSON, one object per event
+    #[arg(long, global = true, value_enum, value_name = "FORMAT", default_value = "text", requires = "log")]
+    pub log_format: LogFormat,
+
+    /// Record the scan history (with per-language detail) in this SQLite database instead of history.jsonl
+    #[cfg(feature = "sqlite")]
+    #[arg(long, global = true, value_name = "FILE")]
+    pub history_db: Option<PathBuf>,
+
+    /// Don't record this run in the scan history
+    #[arg(long, global = true)]
+    pub no_history: bool,
+
+    /// Don't restore the last session's count and view, or save this one on quit
+    #[arg(long)]
+    pub no_session: bool,
+
+    /// Stay running with a warm cache and answer scans from other codecounter runs over a local socket
+    #[arg(long, conflicts_with_all = ["output", "report", "badge", "plain", "git_history"])]
+    pub daemon: bool,
+
+    /// Scan here even when a `--daemon` is running
+    #[arg(long, conflicts_with = "daemon")]
+    pub no_daemon: bool,
+
+    /// Also report lines added and removed since this git branch, tag or commit
+    #[arg(long, global = true, value_name = "REF")]
+    pub since: Option<String>,
+
+    /// Celebrate in the TUI (and mark the history) each time the total passes a multiple of N lines
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub milestone_every: Option<u64>,
+
+    /// Show the COCOMO estimate panel, costing a developer-month at AMOUNT
+    #[arg(long, value_name = "AMOUNT", value_parser = parse_amount)]
+    pub cost_per_month: Option<f64>,
+
+    /// Font for the big number: block (default), rounded, segment or one from ~/.config/codecounter/fonts
+    #[arg(long, value_name = "NAME")]
+    pub font: Option<String>,
+
+    /// Color theme for the TUI (overrides the `[theme]` preset from the config)
+    #[arg(long, value_name = "THEME")]
+    pub theme: Option<ThemePreset>,
+
+    /// Chart the line count across the last COUNT commits (default 20, `--history=50` for more)
+    /// instead of the working tree
+    #[arg(
+        long = "history",
+        value_name = "COUNT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "20",
+        conflicts_with_all = ["output", "report", "badge"]
+    )]
+    pub git_history: Option<usize>,
+
+    /// With `--history`, walk the most recent tags instead of commits
+    #[arg(long, requires = "git_history")]
+    pub tags: bool,
+
+    /// Also rank authors by the lines of code `git blame` gives them (slow the first time; cached per file)
+    #[arg(long, conflicts_with_all = ["output", "report", "badge"])]
+    pub authors: bool,
+
+    /// Print the totals as plain text and exit instead of starting the TUI
+    /// (the default when stdout is not a terminal)
+    #[arg(long, conflicts_with_all = ["output", "report"])]
+    pub plain: bool,
+
+    /// Write a "lines of code" badge to FILE and exit: SVG, or shields.io endpoint JSON for `.json`
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["output", "report", "plain"])]
+    pub badge: Option<PathBuf>,
+
+    /// Render the summary (big number and breakdown) to FILE and exit: PNG, or SVG for `.svg`
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["output", "report", "badge", "plain"])]
+    pub image: Option<PathBuf>,
+
+    /// Size of the `--image` in terminal cells
+    #[arg(long, value_name = "COLSxROWS", value_parser = parse_size, default_value = "100x32", requires = "image")]
+    pub image_size: (u16, u16),
+
+    /// Print the scan results in this format instead of starting the TUI
+    #[arg(long, value_name = "FORMAT")]
+    pub output: Option<ExportFormat>,
+
+    /// Write `--output` or `--report` to this file instead of stdout
+    #[arg(long, value_name = "FILE", requires = "export")]
+    pub out: Option<PathBuf>,
+
+    /// Same as `--output FORMAT`, for reports: e.g. `--report html --out out.html`
+    #[arg(long, value_name = "FORMAT", ignore_case = true)]
+    pub report: Option<ExportFormat>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Compare two directories language by language
+    Diff(DiffArgs),
+    /// Count two or more git branches (or any refs) without touching the working copy
+    Branches(BranchesArgs),
+    /// Rescan on a timer (`--refresh`, default 1m) and serve the result over HTTP
+    Serve(ServeArgs),
+    /// Scan once and exit non-zero when the code is over a size budget, for CI
+    Check(CheckArgs),
+    /// Time repeated scans with and without the line cache and at several thread counts
+    Bench(BenchArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DiffArgs {
+    /// The directory to compare against
+    #[arg(value_name = "DIR_A")]
+    pub a: PathBuf,
+
+    /// The directory to compare
+    #[arg(value_name = "DIR_B")]
+    pub b: PathBuf,
+
+    /// Print the comparison as JSON instead of showing it in the TUI
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct BranchesArgs {
+    /// Branches, tags or commits to count; the first is the one the others are compared with
+    #[arg(value_name = "REF", num_args = 2.., required = true)]
+    pub refs: Vec<String>,
+
+    /// The git repository (or a directory inside it) to count
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+
+    /// Print the totals and comparisons as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ServeArgs {
+    /// Directories to scan (defaults to the current directory)
+    #[arg(value_name = "DIR")]
+    pub paths: Vec<PathBuf>,
+
+    /// Port for `/metrics` (Prometheus) and `/api/scan` (JSON)
+    #[arg(long, default_value_t = 9000)]
+    pub port: u16,
+
+    /// Address to listen on; use 0.0.0.0 to accept connections from other machines
+    #[arg(long, default_value = "127.0.0.1")]
+    pub host: String,
+
+    /// Another name clients may reach the server by, besides localhost and `--host`
+    /// (repeatable); requests with any other `Host` or `Origin` are refused
+    #[arg(long, value_name = "NAME")]
+    pub allow_host: Vec<String>,
+
+    /// A directory `POST /api/roots` may point the server at, besides the ones it was started
+    /// with, along with everything under it (repeatable)
+    #[arg(long, value_name = "DIR")]
+    pub allow_root: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CheckArgs {
+    /// Directories to scan (defaults to the current directory)
+    #[arg(value_name = "DIR")]
+    pub paths: Vec<PathBuf>,
+
+    /// Fail when the total is over N lines of code
+    #[arg(long, value_name = "N", required_unless_present = "max_file_lines")]
+    pub max_lines: Option<u64>,
+
+    /// Fail when any single file is over N lines
+    #[arg(long, value_name = "N")]
+    pub max_file_lines: Option<u64>,
+
+    /// Check the budget even when the scan was partial (cut short by --max-files or
+    /// --max-bytes, or with unreadable paths) instead of failing
+    #[arg(long)]
+    pub allow_partial: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct BenchArgs {
+    /// Directories to scan (defaults to the current directory)
+    #[arg(value_name = "DIR")]
+    pub paths: Vec<PathBuf>,
+
+    /// Timed scans per configuration, after one untimed warm-up scan
+    #[arg(long, value_name = "N", default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    pub runs: u32,
+
+    /// Thread counts to time without the cache, e.g. `1,2,4,8` (defaults to 1 and the number of cores)
+    #[arg(long, value_name = "N,...", value_delimiter = ',', value_parser = clap::value_parser!(u32).range(1..))]
+    pub thread_counts: Vec<u32>,
+
+    /// Print the timings as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl Cli {
+    pub fn export_target(&self) -> Option<(ExportFormat, Option<PathBuf>)> {
+        if let Some(format) = self.output.or(self.report) {
+            return Some((format, self.out.clone()));
+        }
+        if let Some(path) = &self.badge {
+            let format = match path.extension().and_then(|ext| ext.to_str()) {
+                Some(ext) if ext.eq_ignore_ascii_case("json") => ExportFormat::Shields,
+                _ => ExportFormat::Badge,
+            };
+            return Some((format, Some(path.clone())));
+        }
+        self.plain.then_some((ExportFormat::Text, None))
+    }
+}
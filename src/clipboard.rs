@@ -0,0 +1,74 @@
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Clipboard commands to try in order, for the platform and display server in use.
+fn tools() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        return vec![("pbcopy", &[])];
+    }
+    if cfg!(windows) {
+        return vec![("clip", &[])];
+    }
+    let mut tools: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        tools.push(("wl-copy", &[]));
+    }
+    if env::var_os("DISPLAY").is_some() {
+        tools.push(("xclip", &["-selection", "clipboard"]));
+        tools.push(("xsel", &["--clipboard", "--input"]));
+    }
+    tools
+}
+
+/// Puts `text` on the system clipboard and says how: through the platform's clipboard
+/// command, or else by asking the terminal with an OSC 52 escape sequence, which also
+/// works over SSH in the terminals that support it.
+pub fn copy(text: &str) -> io::Result<&'static str> {
+    for (program, args) in tools() {
+        if pipe_to(program, args, text).is_ok() {
+            return Ok(program);
+        }
+    }
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?;
+    stdout.flush()?;
+    Ok("the terminal")
+}
+
+fn pipe_to(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} exited with {}", program, status)));
+    }
+    Ok(())
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |group, (index, byte)| group | (u32::from(*byte) << (16 - 8 * index)));
+        for index in 0..4 {
+            if index <= chunk.len() {
+                out.push(BASE64[((group >> (18 - 6 * index)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
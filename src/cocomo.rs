@@ -0,0 +1,33 @@
+/// Basic COCOMO (Boehm 1981) in "organic" mode, the same model scc reports.
+const EFFORT_FACTOR: f64 = 2.4;
+const EFFORT_EXPONENT: f64 = 1.05;
+const SCHEDULE_FACTOR: f64 = 2.5;
+const SCHEDULE_EXPONENT: f64 = 0.38;
+
+/// scc's default: a $56,286 yearly salary with a 2.4 overhead multiplier, per month.
+pub const DEFAULT_COST_PER_MONTH: f64 = 56_286.0 * 2.4 / 12.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Estimate {
+    pub effort_months: f64,
+    pub schedule_months: f64,
+    pub developers: f64,
+    pub cost: f64,
+}
+
+pub fn estimate(lines: u64, cost_per_month: f64) -> Estimate {
+    let kloc = lines as f64 / 1000.0;
+    let effort_months = EFFORT_FACTOR * kloc.powf(EFFORT_EXPONENT);
+    let schedule_months = SCHEDULE_FACTOR * effort_months.powf(SCHEDULE_EXPONENT);
+    let developers = if schedule_months > 0.0 {
+        effort_months / schedule_months
+    } else {
+        0.0
+    };
+    Estimate {
+        effort_months,
+        schedule_months,
+        developers,
+        cost: effort_months * cost_per_month,
+    }
+}
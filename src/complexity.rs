@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+use crate::language::{Language, Nesting};
+
+/// Spaces a tab counts as when measuring indentation.
+const TAB_WIDTH: usize = 4;
+
+/// A rough measure of how hard a file is to follow: the branch keywords in its code and
+/// how deeply it nests. Comments are left out; strings aren't told apart from code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Complexity {
+    pub branches: u64,
+    pub max_depth: u64,
+}
+
+/// Works through a file a line at a time, for a language with branch keywords.
+#[derive(Debug)]
+pub struct ComplexityCounter<'a> {
+    language: &'a Language,
+    complexity: Complexity,
+    /// Open braces, for brace-nested languages.
+    depth: u64,
+    /// The end marker of a block comment still open at the end of the last line.
+    comment_end: Option<&'a str>,
+    /// The narrowest indentation seen, taken as one level for indent-nested languages.
+    indent_unit: Option<usize>,
+}
+
+impl<'a> ComplexityCounter<'a> {
+    pub fn new(language: &'a Language) -> Option<Self> {
+        (!language.branch_keywords.is_empty()).then_some(Self {
+            language,
+            complexity: Complexity::default(),
+            depth: 0,
+            comment_end: None,
+            indent_unit: None,
+        })
+    }
+
+    pub fn line(&mut self, line: &[u8]) {
+        let line = String::from_utf8_lossy(line);
+        let code = self.strip_comments(&line);
+        if code.trim().is_empty() {
+            return;
+        }
+        for keyword in &self.language.branch_keywords {
+            self.complexity.branches += count_keyword(&code, keyword);
+        }
+        match self.language.nesting {
+            Nesting::Braces => {
+                for ch in code.chars() {
+                    match ch {
+                        '{' => {
+                            self.depth += 1;
+                            self.complexity.max_depth = self.complexity.max_depth.max(self.depth);
+                        }
+                        '}' => self.depth = self.depth.saturating_sub(1),
+                        _ => {}
+                    }
+                }
+            }
+            Nesting::Indent => {
+                let indent = code
+                    .chars()
+                    .take_while(|ch| ch.is_whitespace())
+                    .map(|ch| if ch == '\t' { TAB_WIDTH } else { 1 })
+                    .sum::<usize>();
+                if indent > 0 {
+                    let unit = self.indent_unit.map_or(indent, |unit| unit.min(indent));
+                    self.indent_unit = Some(unit);
+                    self.complexity.max_depth = self.complexity.max_depth.max((indent / unit) as u64);
+                }
+            }
+        }
+    }
+
+    pub fn finish(self) -> Complexity {
+        self.complexity
+    }
+
+    /// The parts of `line` outside comments, with a block comment carried across lines.
+    fn strip_comments(&mut self, line: &str) -> String {
+        let mut code = String::new();
+        let mut rest = line;
+        loop {
+            if let Some(end) = self.comment_end {
+                match rest.find(end) {
+                    Some(at) => {
+                        rest = &rest[at + end.len()..];
+                        self.comment_end = None;
+                    }
+                    None => return code,
+                }
+            }
+            let line_comment = self
+                .language
+                .line_comment
+                .iter()
+                .filter_map(|marker| rest.find(marker.as_str()))
+                .min();
+            let block_comment = self
+                .language
+                .block_comment
+                .iter()
+                .filter_map(|[start, end]| rest.find(start.as_str()).map(|at| (at, start.len(), end.as_str())))
+                .min_by_key(|(at, _, _)| *at);
+            match (line_comment, block_comment) {
+                (Some(at), Some((block_at, _, _))) if at < block_at => {
+                    code.push_str(&rest[..at]);
+                    return code;
+                }
+                (_, Some((at, start_len, end))) => {
+                    code.push_str(&rest[..at]);
+                    code.push(' ');
+                    rest = &rest[at + start_len..];
+                    self.comment_end = Some(end);
+                }
+                (Some(at), None) => {
+                    code.push_str(&rest[..at]);
+                    return code;
+                }
+                (None, None) => {
+                    code.push_str(rest);
+                    return code;
+                }
+            }
+        }
+    }
+}
+
+/// Occurrences of `keyword` in `code`; a word only counts where it stands on its own.
+fn count_keyword(code: &str, keyword: &str) -> u64 {
+    let is_word = |ch: char| ch.is_alphanumeric() || ch == '_';
+    if !keyword.chars().all(is_word) {
+        return code.matches(keyword).count() as u64;
+    }
+    code.match_indices(keyword)
+        .filter(|(at, _)| {
+            let before = code[..*at].chars().next_back();
+            let after = code[at + keyword.len()..].chars().next();
+            !before.is_some_and(is_word) && !after.is_some_and(is_word)
+        })
+        .count() as u64
+}
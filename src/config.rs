@@ -0,0 +1,678 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ratatui::style::Color;
+use serde::Deserialize;
+
+use crate::analyzer::{AnalyzerConfig, Analyzers};
+use crate::ascii::DigitRenderer;
+use crate::cache::CACHE_DIR;
+use crate::ccignore::IgnoreRules;
+use crate::cli::Cli;
+use crate::cocomo::DEFAULT_COST_PER_MONTH;
+use crate::dashboard::Repo;
+use crate::font::FontSet;
+use crate::format::{parse_byte_size, parse_duration, Grouping, NumberFormat};
+use crate::history::Milestones;
+use crate::keymap::{Action, Keymap};
+use crate::language::{Categories, Category, LanguageDb};
+use crate::scan::ExtensionFilter;
+use crate::theme::{Theme, ThemePreset};
+use crate::todo::DEFAULT_MARKERS;
+
+const PROJECT_CONFIG: &str = "codecounter.toml";
+const USER_LANGUAGES: &[&str] = &["languages.toml", "languages.json"];
+/// Directory under `~/.config/codecounter` with extra fonts for the big number.
+const USER_FONTS: &str = "fonts";
+const DEFAULT_IGNORES: &[&str] = &[".git", "target", "node_modules", CACHE_DIR];
+/// Globs, relative to the scanned directory, for files counted as tests rather than production code.
+const DEFAULT_TEST_PATTERNS: &[&str] = &[
+    "**/tests/**",
+    "**/test/**",
+    "**/__tests__/**",
+    "**/spec/**",
+    "**/*_test.{go,py,rs,c,cc,cpp,exs}",
+    "**/test_*.py",
+    "**/*.{test,spec}.{js,jsx,mjs,cjs,ts,tsx}",
+    "**/*_spec.rb",
+    "**/*Test.{java,kt,scala,php}",
+    "**/*Tests.{java,kt,cs,swift}",
+];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigFile {
+    extensions: Vec<String>,
+    ignore: Vec<String>,
+    exclude: Vec<String>,
+    include: Vec<String>,
+    test_patterns: Vec<String>,
+    refresh_interval: Option<Interval>,
+    max_file_size: Option<ByteSize>,
+    max_bytes: Option<ByteSize>,
+    dump_size: Option<ByteSize>,
+    cache: Option<bool>,
+    history: Option<bool>,
+    #[cfg(feature = "sqlite")]
+    history_db: Option<PathBuf>,
+    session: Option<bool>,
+    count_generated: Option<bool>,
+    count_minified: Option<bool>,
+    include_submodules: Option<bool>,
+    gitignore: Option<bool>,
+    complexity: Option<bool>,
+    todo_markers: Vec<String>,
+    follow_symlinks: Option<bool>,
+    hidden: Option<bool>,
+    threads: Option<usize>,
+    milestone_every: Option<u64>,
+    milestones: Vec<u64>,
+    estimate: Option<bool>,
+    cost_per_month: Option<f64>,
+    compact_number: Option<bool>,
+    font: Option<String>,
+    digit_renderer: Option<DigitRenderer>,
+    number_locale: Option<String>,
+    thousands_separator: Option<String>,
+    decimal_separator: Option<char>,
+    digit_grouping: Option<Grouping>,
+    theme: ThemeConfig,
+    keys: BTreeMap<Action, Vec<String>>,
+    categories: BTreeMap<Category, CategoryConfig>,
+    analyzers: Vec<AnalyzerConfig>,
+    repos: Vec<RepoConfig>,
+}
+
+/// A `[[repos]]` table: a repository for the dashboard view.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RepoConfig {
+    /// The directory's name when left out.
+    name: Option<String>,
+    path: PathBuf,
+}
+
+/// A `[categories.<name>]` table: extensions that belong in the category whatever their
+/// language, and how much its lines weigh in the headline total.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CategoryConfig {
+    extensions: Vec<String>,
+    weight: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Interval {
+    Seconds(u64),
+    Text(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ByteSize {
+    Bytes(u64),
+    Text(String),
+}
+
+impl ByteSize {
+    fn bytes(&self, key: &str, path: &Path) -> Result<u64, String> {
+        match self {
+            ByteSize::Bytes(bytes) => Ok(*bytes),
+            ByteSize::Text(text) => parse_byte_size(text).map_err(|err| format!("{}: {}: {}", path.display(), key, err)),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeConfig {
+    preset: Option<ThemePreset>,
+    number: Option<String>,
+    label: Option<String>,
+    text: Option<String>,
+    border: Option<String>,
+    background: Option<String>,
+    highlight: Option<String>,
+}
+
+impl ThemeConfig {
+    fn apply(&self, theme: &mut Theme, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(color) = &self.number {
+            theme.number = parse_color(color, path)?;
+        }
+        if let Some(color) = &self.label {
+            theme.label = parse_color(color, path)?;
+        }
+        if let Some(color) = &self.text {
+            theme.text = parse_color(color, path)?;
+        }
+        if let Some(color) = &self.border {
+            theme.border = parse_color(color, path)?;
+        }
+        if let Some(color) = &self.background {
+            theme.background = parse_color(color, path)?;
+        }
+        if let Some(color) = &self.highlight {
+            theme.highlight = Some(parse_color(color, path)?);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub extensions: ExtensionFilter,
+    pub languages: LanguageDb,
+    /// The category of each file, and what each category adds to the headline total.
+    pub categories: Categories,
+    pub ignore: Vec<String>,
+    pub exclude: GlobSet,
+    /// When not empty, only files matching one of these are counted.
+    pub include: GlobSet,
+    /// The `exclude` and `include` globs as written, for [`Settings::fingerprint`].
+    pub filter_globs: Vec<String>,
+    /// Files counted as test code; they stay in the total but are also reported on their own.
+    pub tests: GlobSet,
+    /// The scanned directory's `.ccignore`.
+    pub ccignore: IgnoreRules,
+    pub refresh: Option<Duration>,
+    pub persist_cache: bool,
+    pub history: bool,
+    /// Record the history in this SQLite database instead of `history.jsonl`.
+    #[cfg(feature = "sqlite")]
+    pub history_db: Option<PathBuf>,
+    /// Save the TUI's last scan, view and toggles on quit and show them again on the next launch.
+    pub session: bool,
+    /// Ask a running `--daemon` for scans instead of walking the tree in this process.
+    pub daemon: bool,
+    pub since: Option<String>,
+    pub count_generated: bool,
+    pub count_minified: bool,
+    /// Count the directories `.gitmodules` lists instead of leaving them out.
+    pub include_submodules: bool,
+    /// Skip what git ignores: `.gitignore` files, `.git/info/exclude` and the global excludes file.
+    pub gitignore: bool,
+    /// Estimate branches and nesting per file for languages that list branch keywords.
+    pub complexity: bool,
+    pub todo_markers: Vec<String>,
+    /// Run on every counted file for metrics of their own.
+    pub analyzers: Analyzers,
+    /// Repositories for the dashboard view, each scanned in the background.
+    pub repos: Vec<Repo>,
+    pub follow_symlinks: bool,
+    /// Dotfiles and dot-directories: `Some(true)` counts them all, `Some(false)` none, and
+    /// `None` only the well-known ones in [`WELL_KNOWN_HIDDEN`](crate::scan::WELL_KNOWN_HIDDEN).
+    pub hidden: Option<bool>,
+    pub max_depth: Option<usize>,
+    pub max_files: Option<usize>,
+    /// Files bigger than this many bytes are skipped without being read.
+    pub max_file_size: Option<u64>,
+    /// Once this many bytes have been read in a scan, the files left to read are skipped.
+    pub max_bytes: Option<u64>,
+    /// Count exactly these files (canonical paths, from `--files-from`) instead of walking the roots.
+    pub files_from: Option<Vec<PathBuf>>,
+    /// Worker threads that read and count files.
+    pub threads: usize,
+    pub milestones: Milestones,
+    pub estimate: bool,
+    pub cost_per_month: f64,
+    /// Show the big number as `1.24M` rather than in full.
+    pub compact_number: bool,
+    pub fonts: FontSet,
+    /// The font the big number starts in, as an index into `fonts`.
+    pub font: usize,
+    pub digit_renderer: DigitRenderer,
+    pub numbers: NumberFormat,
+    pub theme: Theme,
+    pub keymap: Keymap,
+}
+
+impl Settings {
+    pub fn resolve(cli: &Cli, dir: &Path) -> Result<Self, Box<dyn Error>> {
+        let paths = match &cli.config {
+            Some(path) => vec![path.clone()],
+            None => default_config_paths(dir),
+        };
+
+        let mut exclude = Vec::new();
+        let mut include = Vec::new();
+        let mut tests = DEFAULT_TEST_PATTERNS.iter().map(|pattern| pattern.to_string()).collect::<Vec<_>>();
+        let mut themes = Vec::new();
+        let mut settings = Settings {
+            extensions: ExtensionFilter::default(),
+            languages: LanguageDb::default(),
+            categories: Categories::default(),
+            ignore: DEFAULT_IGNORES.iter().map(|name| name.to_string()).collect(),
+            exclude: GlobSet::empty(),
+            include: GlobSet::empty(),
+            filter_globs: Vec::new(),
+            tests: GlobSet::empty(),
+            ccignore: IgnoreRules::load(dir)?,
+            refresh: None,
+            persist_cache: false,
+            history: true,
+            #[cfg(feature = "sqlite")]
+            history_db: None,
+            session: true,
+            daemon: !cli.no_daemon,
+            since: cli.since.clone(),
+            count_generated: false,
+            count_minified: false,
+            include_submodules: false,
+            gitignore: true,
+            complexity: false,
+            todo_markers: DEFAULT_MARKERS.iter().map(|marker| marker.to_string()).collect(),
+            analyzers: Analyzers::default(),
+            repos: Vec::new(),
+            follow_symlinks: false,
+            hidden: None,
+            max_depth: cli.max_depth,
+            max_files: cli.max_files,
+            max_file_size: None,
+            max_bytes: None,
+            files_from: None,
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            milestones: Milestones::default(),
+            estimate: false,
+            cost_per_month: DEFAULT_COST_PER_MONTH,
+            compact_number: false,
+            fonts: FontSet::default(),
+            font: 0,
+            digit_renderer: DigitRenderer::default(),
+            numbers: NumberFormat::from_env(),
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+        };
+
+        match &cli.languages {
+            Some(path) => settings.languages.load_overrides(path)?,
+            None => {
+                for path in user_language_paths() {
+                    if path.is_file() {
+                        settings.languages.load_overrides(&path)?;
+                    }
+                }
+            }
+        }
+
+        if let Some(config_home) = config_home() {
+            settings.fonts.load_dir(&config_home.join("codecounter").join(USER_FONTS))?;
+        }
+
+        for path in paths {
+            if cli.config.is_none() && !path.is_file() {
+                continue;
+            }
+            let mut config = load_config(&path)?;
+            // Scanning a checkout mustn't run programs it names, so plugins only come from
+            // the user's own config or one passed with --config.
+            if cli.config.is_none() && path == dir.join(PROJECT_CONFIG) {
+                config.analyzers.retain(|analyzer| {
+                    if analyzer.runs_command() {
+                        eprintln!(
+                            "warning: {}: ignored the `{}` analyzer; only the user config or --config can run a command",
+                            path.display(),
+                            analyzer.name()
+                        );
+                    }
+                    !analyzer.runs_command()
+                });
+            }
+            exclude.append(&mut config.exclude);
+            include.append(&mut config.include);
+            tests.append(&mut config.test_patterns);
+            themes.push((std::mem::take(&mut config.theme), path.clone()));
+            settings.apply(config, &path)?;
+        }
+
+        // The preset is picked first so that individual colors from any config file
+        // override it, whichever file named the preset.
+        let preset = cli
+            .theme
+            .or_else(|| themes.iter().rev().find_map(|(theme, _)| theme.preset))
+            .unwrap_or(ThemePreset::Dark);
+        settings.theme = Theme::preset(preset);
+        for (theme, path) in &themes {
+            theme.apply(&mut settings.theme, path)?;
+        }
+
+        settings.ignore.extend(cli.ignore.iter().cloned());
+        exclude.extend(cli.exclude.iter().cloned());
+        settings.exclude = build_globs(&exclude)?;
+        include.extend(cli.include.iter().cloned());
+        settings.include = build_globs(&include)?;
+        settings.filter_globs = exclude
+            .iter()
+            .map(|pattern| format!("exclude {}", pattern))
+            .chain(include.iter().map(|pattern| format!("include {}", pattern)))
+            .collect();
+        settings.tests = build_globs(&tests)?;
+        settings.extensions.only = normalize_extensions(&cli.ext);
+        settings.extensions.excluded = normalize_extensions(&cli.no_ext);
+        if let Some(interval) = cli.refresh {
+            settings.refresh = Some(interval);
+        }
+        if let Some(size) = cli.max_file_size {
+            settings.max_file_size = Some(size);
+        }
+        if let Some(size) = cli.max_bytes {
+            settings.max_bytes = Some(size);
+        }
+        if let Some(size) = cli.dump_size {
+            settings.categories.set_dump_size(size);
+        }
+        if cli.cache {
+            settings.persist_cache = true;
+        }
+        if cli.no_cache {
+            settings.persist_cache = false;
+        }
+        if cli.no_history {
+            settings.history = false;
+        }
+        #[cfg(feature = "sqlite")]
+        if let Some(path) = &cli.history_db {
+            settings.history_db = Some(path.clone());
+        }
+        if cli.no_session {
+            settings.session = false;
+        }
+        if cli.count_generated {
+            settings.count_generated = true;
+        }
+        if cli.count_minified {
+            settings.count_minified = true;
+        }
+        if cli.all_categories {
+            for category in Category::ALL {
+                settings.categories.set_weight(category, 1.0);
+            }
+        }
+        if cli.include_submodules {
+            settings.include_submodules = true;
+        }
+        if cli.no_gitignore {
+            settings.gitignore = false;
+        }
+        if cli.complexity {
+            settings.complexity = true;
+        }
+        if cli.follow_symlinks {
+            settings.follow_symlinks = true;
+        }
+        if cli.hidden {
+            settings.hidden = Some(true);
+        }
+        if cli.no_hidden {
+            settings.hidden = Some(false);
+        }
+        if let Some(threads) = cli.threads {
+            settings.threads = threads as usize;
+        }
+        if let Some(every) = cli.milestone_every {
+            settings.milestones.every = Some(every);
+        }
+        if let Some(font) = &cli.font {
+            settings.font = settings.find_font(font)?;
+        }
+        if let Some(cost) = cli.cost_per_month {
+            settings.cost_per_month = cost;
+            settings.estimate = true;
+        }
+        settings.refresh = settings.refresh.filter(|interval| !interval.is_zero());
+
+        Ok(settings)
+    }
+
+    /// A digest of the settings that decide which files and lines count, so the history
+    /// only compares a scan with earlier ones counted the same way.
+    pub fn fingerprint(&self) -> String {
+        let filters = format!(
+            "{:?} {:?} {:?} {:?} {:?} {} {} {} {} {} {:?} {:?} {:?} {:?} {:?}",
+            self.extensions,
+            self.categories,
+            self.ignore,
+            self.filter_globs,
+            self.files_from,
+            self.count_generated,
+            self.count_minified,
+            self.include_submodules,
+            self.gitignore,
+            self.follow_symlinks,
+            self.hidden,
+            self.max_depth,
+            self.max_files,
+            self.max_file_size,
+            self.max_bytes,
+        );
+        // FNV-1a, which unlike std's hashers is the same from one build to the next.
+        let hash = filters
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+        format!("{:016x}", hash)
+    }
+
+    fn find_font(&self, name: &str) -> Result<usize, String> {
+        self.fonts.position(name).ok_or_else(|| {
+            format!("unknown font `{}` (available: {})", name, self.fonts.names().join(", "))
+        })
+    }
+
+    fn apply(&mut self, config: ConfigFile, path: &Path) -> Result<(), Box<dyn Error>> {
+        self.extensions.extra.extend(normalize_extensions(&config.extensions));
+        self.ignore.extend(config.ignore);
+        for marker in config.todo_markers {
+            if !marker.is_empty() && !self.todo_markers.contains(&marker) {
+                self.todo_markers.push(marker);
+            }
+        }
+        match config.refresh_interval {
+            Some(Interval::Seconds(secs)) => self.refresh = Some(Duration::from_secs(secs)),
+            Some(Interval::Text(text)) => {
+                self.refresh = Some(parse_duration(&text).map_err(|err| format!("{}: {}", path.display(), err))?);
+            }
+            None => {}
+        }
+        if let Some(size) = &config.max_file_size {
+            self.max_file_size = Some(size.bytes("max-file-size", path)?);
+        }
+        if let Some(size) = &config.max_bytes {
+            self.max_bytes = Some(size.bytes("max-bytes", path)?);
+        }
+        if let Some(size) = &config.dump_size {
+            self.categories.set_dump_size(size.bytes("dump-size", path)?);
+        }
+        if let Some(cache) = config.cache {
+            self.persist_cache = cache;
+        }
+        if let Some(history) = config.history {
+            self.history = history;
+        }
+        #[cfg(feature = "sqlite")]
+        if let Some(history_db) = config.history_db {
+            self.history_db = Some(history_db);
+        }
+        if let Some(session) = config.session {
+            self.session = session;
+        }
+        if let Some(count_generated) = config.count_generated {
+            self.count_generated = count_generated;
+        }
+        if let Some(count_minified) = config.count_minified {
+            self.count_minified = count_minified;
+        }
+        if let Some(include_submodules) = config.include_submodules {
+            self.include_submodules = include_submodules;
+        }
+        if let Some(gitignore) = config.gitignore {
+            self.gitignore = gitignore;
+        }
+        if let Some(complexity) = config.complexity {
+            self.complexity = complexity;
+        }
+        if let Some(follow_symlinks) = config.follow_symlinks {
+            self.follow_symlinks = follow_symlinks;
+        }
+        if config.hidden.is_some() {
+            self.hidden = config.hidden;
+        }
+        if let Some(threads) = config.threads {
+            if threads == 0 {
+                return Err(format!("{}: threads must be at least 1", path.display()).into());
+            }
+            self.threads = threads;
+        }
+        if let Some(every) = config.milestone_every {
+            if every == 0 {
+                return Err(format!("{}: milestone-every must be at least 1", path.display()).into());
+            }
+            self.milestones.every = Some(every);
+        }
+        self.milestones.at.extend(config.milestones);
+        if let Some(estimate) = config.estimate {
+            self.estimate = estimate;
+        }
+        if let Some(cost) = config.cost_per_month {
+            if !cost.is_finite() || cost <= 0.0 {
+                return Err(format!("{}: cost-per-month must be more than zero", path.display()).into());
+            }
+            self.cost_per_month = cost;
+        }
+        if let Some(compact) = config.compact_number {
+            self.compact_number = compact;
+        }
+        if let Some(font) = config.font {
+            self.font = self.find_font(&font).map_err(|err| format!("{}: {}", path.display(), err))?;
+        }
+        if let Some(renderer) = config.digit_renderer {
+            self.digit_renderer = renderer;
+        }
+        if let Some(locale) = config.number_locale {
+            self.numbers = NumberFormat::for_locale(&locale)
+                .ok_or_else(|| format!("{}: unknown number-locale `{}`", path.display(), locale))?;
+        }
+        if let Some(group) = config.thousands_separator {
+            self.numbers.group = group;
+        }
+        if let Some(decimal) = config.decimal_separator {
+            self.numbers.decimal = decimal;
+        }
+        if let Some(grouping) = config.digit_grouping {
+            self.numbers.grouping = grouping;
+        }
+        for (action, keys) in &config.keys {
+            self.keymap
+                .bind(*action, keys)
+                .map_err(|err| format!("{}: keys: {}", path.display(), err))?;
+        }
+        for (category, config) in config.categories {
+            for ext in normalize_extensions(&config.extensions) {
+                // Naming an extension in a category is also asking for it to be counted.
+                self.extensions.extra.push(ext.clone());
+                self.categories.assign(ext, category);
+            }
+            if let Some(weight) = config.weight {
+                if !weight.is_finite() || weight < 0.0 {
+                    return Err(format!(
+                        "{}: categories: {}: weight must be zero or more",
+                        path.display(),
+                        category.name()
+                    )
+                    .into());
+                }
+                self.categories.set_weight(category, weight);
+            }
+        }
+        for repo in config.repos {
+            // Relative to the config file, like the paths in it are meant.
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let repo_path = dir.join(expand_home(&repo.path));
+            let name = repo.name.unwrap_or_else(|| {
+                repo_path
+                    .file_name()
+                    .map_or_else(|| repo_path.display().to_string(), |name| name.to_string_lossy().into_owned())
+            });
+            self.repos.push(Repo { name, path: repo_path });
+        }
+        for analyzer in config.analyzers {
+            analyzer
+                .add_to(&mut self.analyzers)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+        }
+        Ok(())
+    }
+}
+
+fn build_globs(patterns: &[String]) -> Result<GlobSet, Box<dyn Error>> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|err| format!("invalid glob `{}`: {}", pattern, err))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
+
+fn normalize_extensions(exts: &[String]) -> Vec<String> {
+    exts.iter()
+        .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+        .filter(|ext| !ext.is_empty())
+        .collect()
+}
+
+/// `path` with a leading `~` replaced by the home directory.
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn default_config_paths(dir: &Path) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(config_home) = config_home() {
+        paths.push(config_home.join("codecounter").join("config.toml"));
+    }
+    paths.push(dir.join(PROJECT_CONFIG));
+    paths
+}
+
+fn user_language_paths() -> Vec<PathBuf> {
+    config_home()
+        .map(|home| USER_LANGUAGES.iter().map(|name| home.join("codecounter").join(name)).collect())
+        .unwrap_or_default()
+}
+
+fn config_home() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", &[".config"])
+}
+
+pub fn data_home() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", &[".local", "share"])
+}
+
+fn xdg_dir(var: &str, fallback: &[&str]) -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os(var).filter(|dir| !dir.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| fallback.iter().fold(PathBuf::from(home), |path, part| path.join(part)))
+}
+
+fn load_config(path: &Path) -> Result<ConfigFile, Box<dyn Error>> {
+    let raw = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    toml::from_str(&raw).map_err(|err| format!("{}: {}", path.display(), err).into())
+}
+
+fn parse_color(value: &str, path: &Path) -> Result<Color, Box<dyn Error>> {
+    value
+        .parse()
+        .map_err(|_| format!("{}: unknown color `{}`", path.display(), value).into())
+}
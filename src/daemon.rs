@@ -0,0 +1,234 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::sync::atomic::AtomicBool;
+
+use serde::{Deserialize, Serialize};
+
+use codecounter::config::data_home;
+use codecounter::scan::ScanResult;
+
+#[cfg(not(unix))]
+pub use other::{run, spawn_request};
+#[cfg(unix)]
+pub use unix::{run, spawn_request};
+
+const SOCKET_FILE: &str = "daemon.sock";
+
+/// One scan asked of the daemon. The client's own arguments travel with it so the
+/// daemon resolves exactly the settings the client would have used.
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    args: Vec<String>,
+    /// The client's working directory, for relative `--config` / `--languages` paths.
+    cwd: PathBuf,
+    roots: Vec<PathBuf>,
+    base: PathBuf,
+}
+
+/// `$XDG_RUNTIME_DIR/codecounter/daemon.sock`, falling back to the data directory.
+pub fn socket_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(data_home)
+        .map(|dir| dir.join("codecounter").join(SOCKET_FILE))
+}
+
+/// Asks a running daemon to scan `roots`, or returns `None` when none is listening.
+pub fn request(roots: &[PathBuf], base: &Path) -> Option<Result<ScanResult, Box<dyn Error>>> {
+    #[cfg(unix)]
+    return unix::connect().map(|stream| unix::exchange(stream, roots, base, &AtomicBool::new(false)));
+    #[cfg(not(unix))]
+    return None;
+}
+
+#[cfg(not(unix))]
+mod other {
+    use std::error::Error;
+    use std::path::PathBuf;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::mpsc::Receiver;
+    use std::sync::Arc;
+
+    use codecounter::scan::ScanMessage;
+
+    pub fn run() -> Result<(), Box<dyn Error>> {
+        Err("--daemon needs Unix domain sockets, which this platform doesn't have".into())
+    }
+
+    pub fn spawn_request(_roots: Vec<PathBuf>, _base: PathBuf, _cancel: Arc<AtomicBool>) -> Option<Receiver<ScanMessage>> {
+        None
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::collections::HashMap;
+    use std::error::Error;
+    use std::fs;
+    use std::io::{self, BufRead, BufReader, Read, Write};
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::mpsc::{self, Receiver};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use clap::Parser;
+
+    use super::{socket_path, Request};
+    use codecounter::cache::LineCache;
+    use codecounter::cli::Cli;
+    use codecounter::config::Settings;
+    use codecounter::scan::{scan, ScanMessage, ScanResult};
+
+    /// How often a waiting client checks whether its scan was cancelled.
+    const CANCEL_POLL: Duration = Duration::from_millis(100);
+
+    /// Warm caches, one per set of roots and the settings that change what a cache entry holds.
+    type Caches = Mutex<HashMap<(Vec<PathBuf>, Vec<String>, bool, bool), Arc<Mutex<LineCache>>>>;
+
+    /// Listens on the daemon socket until killed, answering each connection with a fresh scan.
+    pub fn run() -> Result<(), Box<dyn Error>> {
+        let path = socket_path().ok_or("no directory for the daemon socket; set XDG_RUNTIME_DIR or HOME")?;
+        if let Some(parent) = path.parent() {
+            private_dir(parent).map_err(|err| format!("{}: {}", parent.display(), err))?;
+        }
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(format!("a daemon is already listening on {}", path.display()).into());
+            }
+            // Left behind by a daemon that was killed.
+            fs::remove_file(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        }
+        let listener = UnixListener::bind(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        // The daemon reads whatever a client names, so only its owner may connect.
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        eprintln!("Listening on {}", path.display());
+
+        let caches = Arc::new(Caches::default());
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let caches = Arc::clone(&caches);
+            thread::spawn(move || {
+                if let Err(err) = answer(stream, &caches) {
+                    eprintln!("Request failed: {}", err);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Makes `dir` for the socket, readable by its owner only, and makes sure it's a directory
+    /// of this user's, so no one else can connect before the socket's own permissions are set.
+    fn private_dir(dir: &Path) -> Result<(), Box<dyn Error>> {
+        fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+        let metadata = fs::symlink_metadata(dir)?;
+        if !metadata.is_dir() || metadata.uid() != rustix::process::geteuid().as_raw() {
+            return Err("not a directory of this user's, so the daemon socket can't go there".into());
+        }
+        // One made before the daemon made its directory private.
+        if metadata.mode() & 0o077 != 0 {
+            fs::set_permissions(dir, fs::Permissions::from_mode(0o700))?;
+        }
+        Ok(())
+    }
+
+    fn answer(stream: UnixStream, caches: &Caches) -> io::Result<()> {
+        let mut line = String::new();
+        if BufReader::new(&stream).read_line(&mut line)? == 0 {
+            // Someone checking whether the daemon is up.
+            return Ok(());
+        }
+        let reply = serde_json::from_str::<Request>(&line)
+            .map_err(|err| err.to_string())
+            .and_then(|request| scan_for(request, caches).map_err(|err| err.to_string()));
+        let mut stream = stream;
+        serde_json::to_writer(&mut stream, &reply)?;
+        stream.flush()
+    }
+
+    fn scan_for(request: Request, caches: &Caches) -> Result<ScanResult, Box<dyn Error>> {
+        let mut cli = Cli::try_parse_from(std::iter::once("codecounter".to_string()).chain(request.args))?;
+        cli.config = cli.config.map(|path| request.cwd.join(path));
+        cli.languages = cli.languages.map(|path| request.cwd.join(path));
+        let settings = Settings::resolve(&cli, &request.base)?;
+
+        let key = (
+            request.roots.clone(),
+            settings.todo_markers.clone(),
+            settings.complexity,
+            settings.persist_cache,
+        );
+        let cache = {
+            let mut caches = caches.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let cache = caches
+                .entry(key)
+                .or_insert_with(|| Arc::new(Mutex::new(LineCache::new(&request.base, &settings))));
+            Arc::clone(cache)
+        };
+        let mut cache = cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        scan(&request.roots, &settings, &mut cache, &|_| {}, &AtomicBool::new(false))
+    }
+
+    pub(super) fn connect() -> Option<UnixStream> {
+        UnixStream::connect(socket_path()?).ok()
+    }
+
+    /// Sends one request and waits for the scan, giving up early once `cancel` is set.
+    pub(super) fn exchange(
+        mut stream: UnixStream,
+        roots: &[PathBuf],
+        base: &Path,
+        cancel: &AtomicBool,
+    ) -> Result<ScanResult, Box<dyn Error>> {
+        let request = Request {
+            args: std::env::args().skip(1).collect(),
+            cwd: std::env::current_dir()?,
+            roots: roots.to_vec(),
+            base: base.to_path_buf(),
+        };
+        serde_json::to_writer(&mut stream, &request)?;
+        stream.write_all(b"\n")?;
+        stream.set_read_timeout(Some(CANCEL_POLL))?;
+
+        let mut reply = Vec::new();
+        let mut buf = [0; 64 * 1024];
+        loop {
+            match stream.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => reply.extend_from_slice(&buf[..read]),
+                Err(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                    if cancel.load(Ordering::Relaxed) {
+                        return Err("cancelled".into());
+                    }
+                }
+                Err(err) => return Err(format!("daemon: {}", err).into()),
+            }
+        }
+        let reply: Result<ScanResult, String> =
+            serde_json::from_slice(&reply).map_err(|err| format!("daemon: {}", err))?;
+        Ok(reply.map_err(|err| format!("daemon: {}", err))?)
+    }
+
+    /// The daemon counterpart of `spawn_scan`, or `None` when no daemon is listening.
+    pub fn spawn_request(roots: Vec<PathBuf>, base: PathBuf, cancel: Arc<AtomicBool>) -> Option<Receiver<ScanMessage>> {
+        let stream = connect()?;
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let result = exchange(stream, &roots, &base, &cancel);
+            let message = if cancel.load(Ordering::Relaxed) {
+                ScanMessage::Cancelled
+            } else {
+                ScanMessage::Done(result.map(Box::new).map_err(|err| err.to_string()))
+            };
+            let _ = tx.send(message);
+        });
+        Some(rx)
+    }
+}
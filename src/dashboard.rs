@@ -0,0 +1,156 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::cache::LineCache;
+use crate::cli::Cli;
+use crate::config::Settings;
+use crate::history::{History, HistoryEntry};
+use crate::scan::{spawn_scan, ScanMessage};
+
+/// How often each repository is rescanned when its settings don't give a `refresh-interval`.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// A repository named in the config's `[[repos]]`, for the dashboard.
+#[derive(Debug, Clone)]
+pub struct Repo {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// One repository on the dashboard: its own settings, line cache and history, and the scan
+/// running on it if there is one.
+pub struct RepoWatch {
+    pub name: String,
+    pub path: PathBuf,
+    /// `None` when the directory or its config couldn't be read; `error` says why.
+    scanner: Option<(Arc<Settings>, Arc<Mutex<LineCache>>)>,
+    pub history: History,
+    pub error: Option<String>,
+    pending: Option<Receiver<ScanMessage>>,
+    /// The scan running is the session's first or one `r` asked for, not a timed one.
+    requested: bool,
+    last_scan: Option<Instant>,
+}
+
+impl RepoWatch {
+    fn new(cli: &Cli, repo: &Repo) -> Self {
+        let mut watch = Self {
+            name: repo.name.clone(),
+            path: repo.path.clone(),
+            scanner: None,
+            history: History::default(),
+            error: None,
+            pending: None,
+            requested: false,
+            last_scan: None,
+        };
+        let opened = fs::canonicalize(&repo.path)
+            .map_err(|err| format!("{}: {}", repo.path.display(), err).into())
+            .and_then(|path| {
+                let settings = Settings::resolve(cli, &path)?;
+                let history = History::load(std::slice::from_ref(&path), &settings)?;
+                Ok::<_, Box<dyn Error>>((path, settings, history))
+            });
+        match opened {
+            Ok((path, settings, history)) => {
+                let cache = LineCache::new(&path, &settings);
+                watch.scanner = Some((Arc::new(settings), Arc::new(Mutex::new(cache))));
+                watch.path = path;
+                watch.history = history;
+            }
+            Err(err) => watch.error = Some(err.to_string()),
+        }
+        watch
+    }
+
+    pub fn scanning(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// The latest count, from this session or, before its first scan, from the history.
+    pub fn latest(&self) -> Option<&HistoryEntry> {
+        self.history.entries().last()
+    }
+
+    fn due(&self) -> bool {
+        let Some((settings, _)) = &self.scanner else {
+            return false;
+        };
+        let interval = settings.refresh.unwrap_or(DEFAULT_INTERVAL);
+        self.pending.is_none() && self.last_scan.is_none_or(|last| last.elapsed() >= interval)
+    }
+
+    fn start(&mut self) {
+        if let Some((settings, cache)) = &self.scanner {
+            tracing::info!(repo = %self.name, "dashboard scan started");
+            let roots = vec![self.path.clone()];
+            let cancel = Arc::new(AtomicBool::new(false));
+            self.requested = self.last_scan.is_none();
+            self.pending = Some(spawn_scan(roots, Arc::clone(settings), Arc::clone(cache), cancel));
+        }
+    }
+
+    fn poll(&mut self) {
+        let Some(pending) = &self.pending else {
+            return;
+        };
+        let result = loop {
+            match pending.try_recv() {
+                Ok(ScanMessage::Progress(_)) => {}
+                Ok(ScanMessage::Done(result)) => break result.map(|scan| *scan),
+                Ok(ScanMessage::Cancelled) => break Err("scan cancelled".to_string()),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => break Err("scanner thread exited without a result".to_string()),
+            }
+        };
+        self.pending = None;
+        self.last_scan = Some(Instant::now());
+        let recorded = result.and_then(|scan| {
+            let milestones = self.scanner.as_ref().map(|(settings, _)| settings.milestones.clone()).unwrap_or_default();
+            self.history.record(&scan, &milestones, self.requested).map_err(|err| err.to_string())
+        });
+        self.error = recorded.err();
+        if let Some(err) = &self.error {
+            tracing::warn!(repo = %self.name, error = %err, "dashboard scan failed");
+        }
+    }
+}
+
+/// The repositories of the dashboard view, each rescanned on its own interval in the
+/// background. Only one is scanned at a time, so a wall of them doesn't swamp the machine.
+pub struct Dashboard {
+    pub repos: Vec<RepoWatch>,
+}
+
+impl Dashboard {
+    pub fn new(cli: &Cli, repos: &[Repo]) -> Self {
+        Self {
+            repos: repos.iter().map(|repo| RepoWatch::new(cli, repo)).collect(),
+        }
+    }
+
+    /// Collects finished scans and starts the next one that is due.
+    pub fn tick(&mut self) {
+        for repo in &mut self.repos {
+            repo.poll();
+        }
+        if self.repos.iter().any(RepoWatch::scanning) {
+            return;
+        }
+        if let Some(repo) = self.repos.iter_mut().find(|repo| repo.due()) {
+            repo.start();
+        }
+    }
+
+    /// Makes every repository due, so they are all rescanned one after another.
+    pub fn rescan_all(&mut self) {
+        for repo in &mut self.repos {
+            repo.last_scan = None;
+        }
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use crossterm::event::{self, Event, KeyCode};
+use ratatui::widgets::TableState;
+use serde::Serialize;
+
+use codecounter::cache::LineCache;
+use codecounter::cli::DiffArgs;
+use codecounter::config::Settings;
+use codecounter::format::{format_signed, format_with_commas};
+use codecounter::scan::{scan, ScanResult};
+
+use crate::ui::draw_diff;
+use crate::with_terminal;
+
+#[derive(Debug, Serialize)]
+pub struct ScanDiff {
+    pub a: PathBuf,
+    pub b: PathBuf,
+    pub a_lines: u64,
+    pub b_lines: u64,
+    pub a_files: u64,
+    pub b_files: u64,
+    pub languages: Vec<LanguageDiff>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LanguageDiff {
+    pub name: String,
+    pub a_lines: u64,
+    pub b_lines: u64,
+    pub a_files: u64,
+    pub b_files: u64,
+}
+
+impl LanguageDiff {
+    pub fn change(&self) -> i64 {
+        self.b_lines as i64 - self.a_lines as i64
+    }
+}
+
+impl ScanDiff {
+    pub fn new(a: &ScanResult, b: &ScanResult) -> Self {
+        let mut by_name = BTreeMap::<&str, LanguageDiff>::new();
+        let empty = |name: &str| LanguageDiff {
+            name: name.to_string(),
+            a_lines: 0,
+            b_lines: 0,
+            a_files: 0,
+            b_files: 0,
+        };
+        for language in &a.languages {
+            let entry = by_name.entry(&language.name).or_insert_with(|| empty(&language.name));
+            entry.a_lines = language.lines;
+            entry.a_files = language.files;
+        }
+        for language in &b.languages {
+            let entry = by_name.entry(&language.name).or_insert_with(|| empty(&language.name));
+            entry.b_lines = language.lines;
+            entry.b_files = language.files;
+        }
+        let mut languages = by_name.into_values().collect::<Vec<_>>();
+        languages.sort_by(|x, y| {
+            y.change()
+                .unsigned_abs()
+                .cmp(&x.change().unsigned_abs())
+                .then_with(|| x.name.cmp(&y.name))
+        });
+        Self {
+            a: a.roots[0].path.clone(),
+            b: b.roots[0].path.clone(),
+            a_lines: a.lines,
+            b_lines: b.lines,
+            a_files: a.files,
+            b_files: b.files,
+            languages,
+        }
+    }
+
+    pub fn change(&self) -> i64 {
+        self.b_lines as i64 - self.a_lines as i64
+    }
+}
+
+/// "+12.5%" relative to `before`, or "new" when there was nothing before.
+pub fn format_relative(before: u64, after: u64) -> String {
+    if before == 0 {
+        return if after == 0 { "0%".to_string() } else { "new".to_string() };
+    }
+    format!("{:+.1}%", (after as f64 - before as f64) * 100.0 / before as f64)
+}
+
+pub fn run(args: &DiffArgs, settings: &Settings, plain: bool) -> Result<(), Box<dyn Error>> {
+    let a = scan_one(&args.a, settings)?;
+    let b = scan_one(&args.b, settings)?;
+    let diff = ScanDiff::new(&a, &b);
+
+    if args.json {
+        let mut out = io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &diff)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+    if plain || !io::stdout().is_terminal() {
+        return Ok(write_text(&diff, &mut io::stdout().lock())?);
+    }
+
+    show(&diff, settings)
+}
+
+/// Shows a comparison in the TUI until `q`.
+pub fn show(diff: &ScanDiff, settings: &Settings) -> Result<(), Box<dyn Error>> {
+    let mut table = TableState::default().with_selected(Some(0));
+    with_terminal(|terminal| loop {
+        terminal.draw(|frame| draw_diff(frame, diff, settings, &mut table))?;
+        if event::poll(Duration::from_millis(200))?
+            && let Event::Key(key) = event::read()?
+        {
+            let last = diff.languages.len().saturating_sub(1);
+            let selected = table.selected().unwrap_or(0);
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => table.select(Some(selected.saturating_sub(1))),
+                KeyCode::Down | KeyCode::Char('j') => table.select(Some((selected + 1).min(last))),
+                KeyCode::Home => table.select(Some(0)),
+                KeyCode::End => table.select(Some(last)),
+                _ => {}
+            }
+        }
+    })
+}
+
+fn scan_one(dir: &Path, settings: &Settings) -> Result<ScanResult, Box<dyn Error>> {
+    let dir = dir
+        .canonicalize()
+        .map_err(|err| format!("{}: {}", dir.display(), err))?;
+    let mut cache = LineCache::new(&dir, settings);
+    scan(&[dir], settings, &mut cache, &|_| {}, &AtomicBool::new(false))
+}
+
+pub fn write_text(diff: &ScanDiff, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "A: {}", diff.a.display())?;
+    writeln!(out, "B: {}", diff.b.display())?;
+    writeln!(out)?;
+
+    let mut rows = vec![[
+        "Language".to_string(),
+        "A".to_string(),
+        "B".to_string(),
+        "Change".to_string(),
+        "Relative".to_string(),
+    ]];
+    for language in &diff.languages {
+        rows.push([
+            language.name.clone(),
+            format_with_commas(language.a_lines),
+            format_with_commas(language.b_lines),
+            format_signed(language.change()),
+            format_relative(language.a_lines, language.b_lines),
+        ]);
+    }
+    rows.push([
+        "Total".to_string(),
+        format_with_commas(diff.a_lines),
+        format_with_commas(diff.b_lines),
+        format_signed(diff.change()),
+        format_relative(diff.a_lines, diff.b_lines),
+    ]);
+
+    let mut widths = [0; 5];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in &rows {
+        writeln!(
+            out,
+            "{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}  {:>w4$}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            row[4],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3],
+            w4 = widths[4]
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+/// How a file's text is stored. Anything that isn't UTF-16 is read byte by byte, which
+/// counts lines right for UTF-8 and for the ASCII-compatible legacy encodings alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// Guesses the encoding from the first bytes of a file, returning it with the length of
+/// the byte order mark to skip. UTF-16 without a mark is recognized by its zero bytes:
+/// mostly-ASCII text has one in every code unit, always on the same side.
+pub fn sniff(head: &[u8]) -> (Encoding, usize) {
+    match head {
+        [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, 3),
+        [0xFF, 0xFE, ..] => (Encoding::Utf16Le, 2),
+        [0xFE, 0xFF, ..] => (Encoding::Utf16Be, 2),
+        _ => {
+            let units = head.len() / 2;
+            if units < 2 {
+                return (Encoding::Utf8, 0);
+            }
+            let zeros = |offset: usize| head.iter().skip(offset).step_by(2).take(units).filter(|byte| **byte == 0).count();
+            let (even, odd) = (zeros(0), zeros(1));
+            // At least 40% of units zero on one side and next to none on the other.
+            if odd * 5 >= units * 2 && even * 20 <= units {
+                (Encoding::Utf16Le, 0)
+            } else if even * 5 >= units * 2 && odd * 20 <= units {
+                (Encoding::Utf16Be, 0)
+            } else {
+                (Encoding::Utf8, 0)
+            }
+        }
+    }
+}
+
+/// Turns a file's bytes into UTF-8 a chunk at a time. A code unit or surrogate pair split
+/// across two chunks is carried over; malformed UTF-16 comes out as U+FFFD.
+#[derive(Debug)]
+pub struct Decoder {
+    encoding: Encoding,
+    /// The first byte of a code unit the last chunk ended in the middle of.
+    odd_byte: Option<u8>,
+    /// A high surrogate still waiting for its low half.
+    high: Option<u16>,
+}
+
+impl Decoder {
+    pub fn new(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            odd_byte: None,
+            high: None,
+        }
+    }
+
+    /// `bytes` as UTF-8: itself for UTF-8 and legacy files, otherwise decoded into `out`.
+    pub fn decode<'a>(&mut self, bytes: &'a [u8], out: &'a mut Vec<u8>) -> &'a [u8] {
+        if self.encoding == Encoding::Utf8 {
+            return bytes;
+        }
+        out.clear();
+        let mut bytes = self.odd_byte.take().into_iter().chain(bytes.iter().copied());
+        while let Some(first) = bytes.next() {
+            let Some(second) = bytes.next() else {
+                self.odd_byte = Some(first);
+                break;
+            };
+            let unit = match self.encoding {
+                Encoding::Utf16Be => u16::from_be_bytes([first, second]),
+                _ => u16::from_le_bytes([first, second]),
+            };
+            self.push_unit(unit, out);
+        }
+        out
+    }
+
+    /// Whatever was left incomplete at the end of the file.
+    pub fn finish<'a>(&mut self, out: &'a mut Vec<u8>) -> &'a [u8] {
+        out.clear();
+        if self.odd_byte.take().is_some() || self.high.take().is_some() {
+            push_char(char::REPLACEMENT_CHARACTER, out);
+        }
+        out
+    }
+
+    fn push_unit(&mut self, unit: u16, out: &mut Vec<u8>) {
+        if let Some(high) = self.high.take() {
+            if (0xDC00..0xE000).contains(&unit) {
+                let code = 0x10000 + ((u32::from(high) - 0xD800) << 10) + (u32::from(unit) - 0xDC00);
+                push_char(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER), out);
+                return;
+            }
+            push_char(char::REPLACEMENT_CHARACTER, out);
+        }
+        match unit {
+            0xD800..0xDC00 => self.high = Some(unit),
+            0xDC00..0xE000 => push_char(char::REPLACEMENT_CHARACTER, out),
+            _ => push_char(char::from_u32(u32::from(unit)).unwrap_or(char::REPLACEMENT_CHARACTER), out),
+        }
+    }
+}
+
+fn push_char(ch: char, out: &mut Vec<u8>) {
+    out.extend_from_slice(ch.encode_utf8(&mut [0; 4]).as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, encoding: Encoding) -> Vec<u8> {
+        text.encode_utf16()
+            .flat_map(|unit| match encoding {
+                Encoding::Utf16Be => unit.to_be_bytes(),
+                _ => unit.to_le_bytes(),
+            })
+            .collect()
+    }
+
+    /// `bytes` run through a decoder `size` bytes at a time.
+    fn decode_in_chunks(encoding: Encoding, bytes: &[u8], size: usize) -> String {
+        let mut decoder = Decoder::new(encoding);
+        let (mut text, mut out) = (Vec::new(), Vec::new());
+        for chunk in bytes.chunks(size) {
+            text.extend_from_slice(decoder.decode(chunk, &mut out));
+        }
+        text.extend_from_slice(decoder.finish(&mut out));
+        String::from_utf8(text).unwrap()
+    }
+
+    #[test]
+    fn sniffs_byte_order_marks() {
+        assert_eq!(sniff(b"\xEF\xBB\xBFfn main() {}"), (Encoding::Utf8, 3));
+        assert_eq!(sniff(b"\xFF\xFEf\0n\0"), (Encoding::Utf16Le, 2));
+        assert_eq!(sniff(b"\xFE\xFF\0f\0n"), (Encoding::Utf16Be, 2));
+    }
+
+    #[test]
+    fn sniffs_utf16_without_a_mark() {
+        let text = "fn main() {\n    println!(\"héllo\");\n}\n";
+        assert_eq!(sniff(&utf16(text, Encoding::Utf16Le)), (Encoding::Utf16Le, 0));
+        assert_eq!(sniff(&utf16(text, Encoding::Utf16Be)), (Encoding::Utf16Be, 0));
+        assert_eq!(sniff(text.as_bytes()), (Encoding::Utf8, 0));
+        assert_eq!(sniff(b"\0"), (Encoding::Utf8, 0));
+        assert_eq!(sniff(b""), (Encoding::Utf8, 0));
+    }
+
+    #[test]
+    fn utf8_passes_through() {
+        let mut out = Vec::new();
+        assert_eq!(Decoder::new(Encoding::Utf8).decode(b"caf\xC3\xA9", &mut out), b"caf\xC3\xA9");
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn carries_odd_bytes_and_surrogates_across_chunks() {
+        // A surrogate pair (the emoji) and a unit at every split point, for both byte orders.
+        let text = "a\u{1F600}b\nçé\n";
+        for encoding in [Encoding::Utf16Le, Encoding::Utf16Be] {
+            let bytes = utf16(text, encoding);
+            for size in 1..=bytes.len() {
+                assert_eq!(decode_in_chunks(encoding, &bytes, size), text, "{:?} in chunks of {}", encoding, size);
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_utf16_becomes_replacement_characters() {
+        // A lone low surrogate, a high surrogate followed by a plain unit, and a stray last byte.
+        let bytes = [0x00, 0xDC, b'a', 0x00, 0x00, 0xD8, b'b', 0x00, b'c'];
+        assert_eq!(decode_in_chunks(Encoding::Utf16Le, &bytes, 3), "\u{FFFD}a\u{FFFD}b\u{FFFD}");
+        // A high surrogate at the very end.
+        assert_eq!(decode_in_chunks(Encoding::Utf16Le, &[b'x', 0x00, 0x3D, 0xD8], 2), "x\u{FFFD}");
+    }
+}
@@ -0,0 +1,987 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::badge;
+use crate::format::{format_decimal, format_share, format_signed, format_with_commas};
+use crate::package::PackageStat;
+use crate::scan::{root_label, CategoryStat, LanguageStat, RootStat, ScanResult, LONG_LINE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    #[value(alias = "markdown")]
+    Md,
+    Html,
+    #[value(alias = "txt")]
+    Text,
+    Json,
+    #[value(alias = "svg")]
+    Badge,
+    Shields,
+    #[value(alias = "prom")]
+    Prometheus,
+}
+
+impl ExportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Md => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Text => "txt",
+            ExportFormat::Json => "json",
+            ExportFormat::Badge => "svg",
+            ExportFormat::Shields => "json",
+            ExportFormat::Prometheus => "prom",
+        }
+    }
+}
+
+pub fn export(format: ExportFormat, scan: &ScanResult, out: &mut dyn Write) -> io::Result<()> {
+    match format {
+        ExportFormat::Csv => write_csv(scan, out),
+        ExportFormat::Md => write_markdown(scan, out),
+        ExportFormat::Html => write_html(scan, out),
+        ExportFormat::Text => write_text(scan, out),
+        ExportFormat::Json => write_json(scan, out),
+        ExportFormat::Badge => badge::write_svg(scan, out),
+        ExportFormat::Shields => badge::write_shields_json(scan, out),
+        ExportFormat::Prometheus => write_prometheus(scan, out),
+    }
+}
+
+pub fn export_to_file(format: ExportFormat, scan: &ScanResult, path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    export(format, scan, &mut out)?;
+    out.flush()
+}
+
+pub fn default_export_path(dir: &Path, format: ExportFormat, scan: &ScanResult) -> PathBuf {
+    dir.join(format!(
+        "codecounter-{}.{}",
+        scan.scanned_at.format("%Y%m%d-%H%M%S"),
+        format.extension()
+    ))
+}
+
+/// "1,234 lines in 20 files, 0.31 per production line", or `None` without any test code.
+pub fn test_summary(scan: &ScanResult) -> Option<String> {
+    if scan.tests.files == 0 {
+        return None;
+    }
+    let mut summary = format!(
+        "{} lines in {} files",
+        format_with_commas(scan.tests.lines),
+        format_with_commas(scan.tests.files)
+    );
+    if let Some(ratio) = scan.test_ratio() {
+        summary.push_str(&format!(", {} per production line", format_decimal(ratio, 2)));
+    }
+    Some(summary)
+}
+
+/// "120 lines (2.5%) in 4 copies of 3 files", or `None` when no file is a copy of another.
+pub fn duplication_summary(scan: &ScanResult) -> Option<String> {
+    let groups = scan.duplicate_groups();
+    if groups.is_empty() {
+        return None;
+    }
+    let lines = groups.iter().map(|group| group.duplicated_lines()).sum();
+    let copies = groups.iter().map(|group| group.files.len() as u64 - 1).sum();
+    Some(format!(
+        "{} lines ({}) in {} copies of {} files",
+        format_with_commas(lines),
+        format_share(lines, scan.counted_lines()),
+        format_with_commas(copies),
+        format_with_commas(groups.len() as u64)
+    ))
+}
+
+/// "unsafe 12, secrets.keys 3", the analyzers' totals, or `None` when none are configured.
+pub fn metrics_summary(scan: &ScanResult) -> Option<String> {
+    if scan.metrics.is_empty() {
+        return None;
+    }
+    let metrics = scan
+        .metrics
+        .iter()
+        .map(|(name, count)| format!("{} {}", name, format_with_commas(*count)))
+        .collect::<Vec<_>>();
+    Some(metrics.join(", "))
+}
+
+/// What the total leaves out and whether it may be short, one sentence each, for the
+/// reports that don't go into it path by path.
+fn left_out_notes(scan: &ScanResult) -> Vec<String> {
+    let mut notes = Vec::new();
+    if scan.truncated {
+        notes.push("Stopped early at --max-files; the totals are partial.".to_string());
+    }
+    if !scan.errors.is_empty() {
+        notes.push(format!(
+            "Couldn't read {} paths; the totals may be short.",
+            format_with_commas(scan.errors.len() as u64)
+        ));
+    }
+    for (label, bucket) in [("Generated/vendored", scan.generated), ("Minified", scan.minified)] {
+        if bucket.files > 0 {
+            notes.push(format!(
+                "{} (not in the total): {} lines in {} files.",
+                label,
+                format_with_commas(bucket.lines),
+                format_with_commas(bucket.files)
+            ));
+        }
+    }
+    let submodules = scan.skipped_submodules();
+    if !submodules.is_empty() {
+        notes.push(format!("Submodules (not in the total): {}.", submodules.join(", ")));
+    }
+    for (label, files) in [
+        ("Binary files skipped", scan.binary_files),
+        ("Files over --max-file-size skipped", scan.oversized_files),
+        ("Files skipped once --max-bytes had been read", scan.over_budget_files),
+    ] {
+        if files > 0 {
+            notes.push(format!("{}: {}.", label, format_with_commas(files)));
+        }
+    }
+    notes
+}
+
+/// The total and the language table as compact JSON, small enough to paste into a chat.
+pub fn write_summary_json(scan: &ScanResult, out: &mut dyn Write) -> io::Result<()> {
+    let summary = serde_json::json!({
+        "scanned_at": scan.scanned_at,
+        "lines": scan.lines,
+        "files": scan.files,
+        "languages": scan.languages.iter().map(|language| serde_json::json!({
+            "name": language.name,
+            "lines": language.lines,
+            "files": language.files,
+        })).collect::<Vec<_>>(),
+    });
+    serde_json::to_writer_pretty(&mut *out, &summary)?;
+    writeln!(out)
+}
+
+fn write_csv(scan: &ScanResult, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "kind,path,language,lines,files")?;
+    writeln!(out, "total,,,{},{}", scan.lines, scan.files)?;
+    writeln!(out, "generated,,,{},{}", scan.generated.lines, scan.generated.files)?;
+    writeln!(out, "minified,,,{},{}", scan.minified.lines, scan.minified.files)?;
+    writeln!(out, "binary,,,,{}", scan.binary_files)?;
+    for language in &scan.languages {
+        writeln!(
+            out,
+            "language,,{},{},{}",
+            csv_field(&language.name),
+            language.lines,
+            language.files
+        )?;
+    }
+    for file in &scan.file_stats {
+        writeln!(
+            out,
+            "file,{},{},{},1",
+            csv_field(&scan.display_path(file)),
+            csv_field(&file.language),
+            file.lines
+        )?;
+    }
+    for error in &scan.errors {
+        writeln!(out, "error,{},,,", csv_field(&scan.display(error.root, &error.path)))?;
+    }
+    Ok(())
+}
+
+fn write_markdown(scan: &ScanResult, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "# Lines of code")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "**{}** lines in **{}** files, scanned {}.",
+        format_with_commas(scan.lines),
+        format_with_commas(scan.total_files()),
+        scan.scanned_at.format("%Y-%m-%d %H:%M:%S %z")
+    )?;
+    let notes = left_out_notes(scan);
+    if !notes.is_empty() {
+        writeln!(out)?;
+        for note in notes {
+            writeln!(out, "- {}", note)?;
+        }
+    }
+    if let Some(tests) = test_summary(scan) {
+        writeln!(out)?;
+        writeln!(out, "Test code: {}.", tests)?;
+    }
+    if let Some(duplication) = duplication_summary(scan) {
+        writeln!(out)?;
+        writeln!(out, "Duplicated files: {}.", duplication)?;
+    }
+    let licenses = scan.license_stats();
+    if !licenses.is_empty() {
+        let names = licenses
+            .iter()
+            .map(|license| format!("{} ({} files)", license.name, format_with_commas(license.files)))
+            .collect::<Vec<_>>();
+        writeln!(out)?;
+        write!(out, "Licenses in file headers: {}", names.join(", "))?;
+        match scan.unlicensed_files().len() {
+            0 => writeln!(out, ".")?,
+            unlicensed => writeln!(out, "; {} files have none.", format_with_commas(unlicensed as u64))?,
+        }
+    }
+    writeln!(out)?;
+    for root in &scan.roots {
+        writeln!(out, "- `{}`", root.path.display())?;
+    }
+    if let Some(changes) = &scan.since {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "Since `{}`: {} / {} lines.",
+            changes.reference,
+            format_signed(changes.added as i64),
+            format_signed(-(changes.removed as i64))
+        )?;
+    }
+
+    writeln!(out)?;
+    writeln!(out, "| Language | Files | Lines | Share |")?;
+    writeln!(out, "|---|--:|--:|--:|")?;
+    for language in &scan.languages {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} |",
+            markdown_cell(&language.name),
+            format_with_commas(language.files),
+            format_with_commas(language.lines),
+            format_share(language.lines, scan.counted_lines())
+        )?;
+    }
+    writeln!(
+        out,
+        "| **Total** | **{}** | **{}** | **100%** |",
+        format_with_commas(scan.files),
+        format_with_commas(scan.counted_lines())
+    )?;
+
+    if scan.categories.len() > 1 {
+        writeln!(out)?;
+        writeln!(out, "| Category | Files | Lines | Share |")?;
+        writeln!(out, "|---|--:|--:|--:|")?;
+        for (label, files, lines) in category_breakdown(scan) {
+            writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                label,
+                format_with_commas(files),
+                format_with_commas(lines),
+                format_share(lines, scan.counted_lines())
+            )?;
+        }
+    }
+
+    if !scan.crates.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "| Crate | Files | Lines | Share |")?;
+        writeln!(out, "|---|--:|--:|--:|")?;
+        for (label, files, lines) in crate_breakdown(scan) {
+            writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                markdown_cell(&label),
+                format_with_commas(files),
+                format_with_commas(lines),
+                format_share(lines, scan.lines)
+            )?;
+        }
+    }
+
+    if scan.packages.len() > 1 {
+        writeln!(out)?;
+        writeln!(out, "| Package | Files | Lines | Share |")?;
+        writeln!(out, "|---|--:|--:|--:|")?;
+        for (label, files, lines) in package_breakdown(scan) {
+            writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                markdown_cell(&label),
+                format_with_commas(files),
+                format_with_commas(lines),
+                format_share(lines, scan.lines)
+            )?;
+        }
+    }
+
+    if scan.roots.len() > 1 {
+        writeln!(out)?;
+        writeln!(out, "| Directory | Files | Lines | Share |")?;
+        writeln!(out, "|---|--:|--:|--:|")?;
+        for root in &scan.roots {
+            writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                markdown_cell(&root_label(&root.path).display().to_string()),
+                format_with_commas(root.files),
+                format_with_commas(root.lines),
+                format_share(root.lines, scan.lines)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_text(scan: &ScanResult, out: &mut dyn Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} lines of code in {} files",
+        format_with_commas(scan.lines),
+        format_with_commas(scan.total_files())
+    )?;
+    if scan.truncated {
+        writeln!(out, "Stopped early at --max-files; the totals are partial.")?;
+    }
+    if !scan.errors.is_empty() {
+        writeln!(
+            out,
+            "Couldn't read {} paths; the totals may be short:",
+            format_with_commas(scan.errors.len() as u64)
+        )?;
+        for error in &scan.errors {
+            writeln!(out, "  {}: {}", scan.display(error.root, &error.path), error.message)?;
+        }
+    }
+    if scan.generated.files > 0 {
+        writeln!(
+            out,
+            "Generated/vendored (not in the total): {} lines in {} files",
+            format_with_commas(scan.generated.lines),
+            format_with_commas(scan.generated.files)
+        )?;
+    }
+    if scan.minified.files > 0 {
+        writeln!(
+            out,
+            "Minified (not in the total): {} lines in {} files",
+            format_with_commas(scan.minified.lines),
+            format_with_commas(scan.minified.files)
+        )?;
+    }
+    let submodules = scan.skipped_submodules();
+    if !submodules.is_empty() {
+        writeln!(out, "Submodules (not in the total): {}", submodules.join(", "))?;
+    }
+    if let Some(tests) = test_summary(scan) {
+        writeln!(out, "Test code (in the total): {}", tests)?;
+    }
+    if let Some(metrics) = metrics_summary(scan) {
+        writeln!(out, "Analyzers: {}", metrics)?;
+    }
+    if let Some(duplication) = duplication_summary(scan) {
+        writeln!(out, "Duplicated files (in the total): {}", duplication)?;
+        for group in scan.duplicate_groups() {
+            let paths = group.files.iter().map(|file| scan.display_path(file)).collect::<Vec<_>>();
+            writeln!(out, "  {} lines each: {}", format_with_commas(group.lines), paths.join(", "))?;
+        }
+    }
+    if scan.binary_files > 0 {
+        writeln!(out, "Binary files skipped: {}", format_with_commas(scan.binary_files))?;
+    }
+    if scan.oversized_files > 0 {
+        writeln!(out, "Files over --max-file-size skipped: {}", format_with_commas(scan.oversized_files))?;
+    }
+    if scan.over_budget_files > 0 {
+        writeln!(
+            out,
+            "Files skipped once --max-bytes had been read: {}",
+            format_with_commas(scan.over_budget_files)
+        )?;
+    }
+    let licenses = scan.license_stats();
+    if !licenses.is_empty() {
+        let names = licenses
+            .iter()
+            .map(|license| format!("{} ({} files)", license.name, format_with_commas(license.files)))
+            .collect::<Vec<_>>();
+        writeln!(out, "Licenses in file headers: {}", names.join(", "))?;
+        let unlicensed = scan.unlicensed_files();
+        if !unlicensed.is_empty() {
+            writeln!(out, "Files without a license header:")?;
+            for file in unlicensed {
+                writeln!(out, "  {}", scan.display_path(file))?;
+            }
+        }
+    }
+    let complex = scan.most_complex();
+    if !complex.is_empty() {
+        writeln!(out, "Most complex files:")?;
+        for file in complex {
+            let complexity = file.complexity.unwrap_or_default();
+            writeln!(
+                out,
+                "  {}: {} branches, nesting {}",
+                scan.display_path(file),
+                format_with_commas(complexity.branches),
+                complexity.max_depth
+            )?;
+        }
+    }
+    let long_lines = scan.long_line_files();
+    if !long_lines.is_empty() {
+        writeln!(
+            out,
+            "Files with lines of {}+ characters, likely minified or data:",
+            format_with_commas(LONG_LINE)
+        )?;
+        for file in long_lines {
+            writeln!(
+                out,
+                "  {}: longest {}, average {}",
+                scan.display_path(file),
+                format_with_commas(file.longest_line),
+                format_with_commas(file.size.mean_line(file.lines).round() as u64)
+            )?;
+        }
+    }
+    if let Some(changes) = &scan.since {
+        writeln!(
+            out,
+            "Since {}: {} / {} lines",
+            changes.reference,
+            format_signed(changes.added as i64),
+            format_signed(-(changes.removed as i64))
+        )?;
+    }
+
+    let languages = scan
+        .languages
+        .iter()
+        .map(|language| (language.name.clone(), language.files, language.lines))
+        .collect::<Vec<_>>();
+    writeln!(out)?;
+    write_text_table(out, "Language", &languages, scan.counted_lines())?;
+    if scan.categories.len() > 1 {
+        writeln!(out)?;
+        write_text_table(out, "Category", &category_breakdown(scan), scan.counted_lines())?;
+    }
+    if !scan.crates.is_empty() {
+        writeln!(out)?;
+        write_text_table(out, "Crate", &crate_breakdown(scan), scan.lines)?;
+    }
+    if scan.packages.len() > 1 {
+        writeln!(out)?;
+        write_text_table(out, "Package", &package_breakdown(scan), scan.lines)?;
+    }
+
+    if scan.roots.len() > 1 {
+        let roots = scan
+            .roots
+            .iter()
+            .map(|root| (root_label(&root.path).display().to_string(), root.files, root.lines))
+            .collect::<Vec<_>>();
+        writeln!(out)?;
+        write_text_table(out, "Directory", &roots, scan.lines)?;
+    }
+    Ok(())
+}
+
+fn write_text_table(out: &mut dyn Write, label: &str, rows: &[(String, u64, u64)], total: u64) -> io::Result<()> {
+    let rows = rows
+        .iter()
+        .map(|(name, files, lines)| {
+            [
+                name.clone(),
+                format_with_commas(*files),
+                format_with_commas(*lines),
+                format_share(*lines, total),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let header = [label.to_string(), "Files".to_string(), "Lines".to_string(), "Share".to_string()];
+    let mut widths = header.clone().map(|cell| cell.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in std::iter::once(&header).chain(&rows) {
+        writeln!(
+            out,
+            "{:<w0$}  {:>w1$}  {:>w2$}  {:>w3$}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3]
+        )?;
+    }
+    Ok(())
+}
+
+fn write_json(scan: &ScanResult, out: &mut dyn Write) -> io::Result<()> {
+    let errors = scan
+        .errors
+        .iter()
+        .map(|error| serde_json::json!({
+            "path": scan.display(error.root, &error.path),
+            "message": error.message,
+        }))
+        .collect::<Vec<_>>();
+    let roots = scan
+        .roots
+        .iter()
+        .map(|root| serde_json::json!({
+            "path": root.path,
+            "lines": root.lines,
+            "files": root.files,
+            "binary_files": root.binary_files,
+            "oversized_files": root.oversized_files,
+            "generated_lines": root.generated.lines,
+            "generated_files": root.generated.files,
+            "minified_lines": root.minified.lines,
+            "minified_files": root.minified.files,
+        }))
+        .collect::<Vec<_>>();
+    let languages = scan
+        .languages
+        .iter()
+        .map(|language| serde_json::json!({
+            "name": language.name,
+            "category": language.category,
+            "lines": language.lines,
+            "files": language.files,
+            "bytes": language.size.bytes,
+            "chars": language.size.chars,
+            "words": language.size.words,
+            "mean_lines": language.mean_lines,
+            "median_lines": language.median_lines,
+            "p95_lines": language.p95_lines,
+            "longest_line": language.longest_line,
+            "mean_line": language.size.mean_line(language.lines),
+        }))
+        .collect::<Vec<_>>();
+    let file_stats = scan
+        .file_stats
+        .iter()
+        .map(|file| serde_json::json!({
+            "path": scan.display_path(file),
+            "language": file.language,
+            "category": file.category,
+            "lines": file.lines,
+            "docs_lines": file.docs_lines,
+            "test": file.test,
+            "longest_line": file.longest_line,
+            "mean_line": file.size.mean_line(file.lines),
+            "branches": file.complexity.map(|complexity| complexity.branches),
+            "max_depth": file.complexity.map(|complexity| complexity.max_depth),
+            "license": file.license,
+            "metrics": file.metrics,
+            "bytes": file.size.bytes,
+            "chars": file.size.chars,
+            "words": file.size.words,
+        }))
+        .collect::<Vec<_>>();
+    let report = serde_json::json!({
+        "scanned_at": scan.scanned_at,
+        "lines": scan.lines,
+        "files": scan.files,
+        "bytes": scan.size.bytes,
+        "chars": scan.size.chars,
+        "words": scan.size.words,
+        "truncated": scan.truncated,
+        "errors": errors,
+        "binary_files": scan.binary_files,
+        "oversized_files": scan.oversized_files,
+        "over_budget_files": scan.over_budget_files,
+        "generated_lines": scan.generated.lines,
+        "generated_files": scan.generated.files,
+        "minified_lines": scan.minified.lines,
+        "minified_files": scan.minified.files,
+        "skipped_submodules": scan.skipped_submodules(),
+        "since": scan.since,
+        "test_lines": scan.tests.lines,
+        "test_files": scan.tests.files,
+        "test_ratio": scan.test_ratio(),
+        "metrics": scan.metrics,
+        "categories": scan.categories,
+        "crates": scan.crates.iter().map(|member| serde_json::json!({
+            "name": member.name,
+            "path": member.path,
+            "lines": member.lines,
+            "files": member.files,
+            "src": member.src,
+            "tests": member.tests,
+            "benches": member.benches,
+            "other": member.other,
+        })).collect::<Vec<_>>(),
+        "packages": scan.packages.iter().map(|package| serde_json::json!({
+            "name": package.name,
+            "kind": package.kind,
+            "path": scan.display(package.root, &scan.roots[package.root].path.join(&package.path)),
+            "lines": package.lines,
+            "files": package.files,
+        })).collect::<Vec<_>>(),
+        "licenses": scan.license_stats(),
+        "unlicensed_files": scan.unlicensed_files().iter().map(|file| scan.display_path(file)).collect::<Vec<_>>(),
+        "duplicated_lines": scan.duplicated_lines(),
+        "duplicates": scan.duplicate_groups().iter().map(|group| serde_json::json!({
+            "lines": group.lines,
+            "paths": group.files.iter().map(|file| scan.display_path(file)).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "roots": roots,
+        "languages": languages,
+        "file_stats": file_stats,
+    });
+    serde_json::to_writer_pretty(&mut *out, &report)?;
+    writeln!(out)
+}
+
+/// Prometheus text exposition format, served at `/metrics` by `codecounter serve`.
+fn write_prometheus(scan: &ScanResult, out: &mut dyn Write) -> io::Result<()> {
+    let totals = [
+        ("codecounter_lines", "Lines of code counted in the last scan.", scan.lines),
+        ("codecounter_files", "Files counted in the last scan.", scan.files),
+        ("codecounter_bytes", "Bytes in the counted files.", scan.size.bytes),
+        ("codecounter_words", "Words in the counted files.", scan.size.words),
+        ("codecounter_binary_files", "Binary files skipped.", scan.binary_files),
+        ("codecounter_scan_errors", "Paths the last scan couldn't read.", scan.errors.len() as u64),
+        ("codecounter_truncated", "1 when the last scan stopped early at --max-files.", u64::from(scan.truncated)),
+        ("codecounter_oversized_files", "Files over --max-file-size skipped.", scan.oversized_files),
+        (
+            "codecounter_over_budget_files",
+            "Files skipped once --max-bytes had been read.",
+            scan.over_budget_files,
+        ),
+        ("codecounter_generated_lines", "Lines in generated or vendored files, not in the total.", scan.generated.lines),
+        ("codecounter_generated_files", "Generated or vendored files, not in the total.", scan.generated.files),
+        ("codecounter_minified_lines", "Lines in minified files, not in the total.", scan.minified.lines),
+        ("codecounter_minified_files", "Minified files, not in the total.", scan.minified.files),
+        ("codecounter_test_lines", "Lines of test code, included in the total.", scan.tests.lines),
+        ("codecounter_test_files", "Test files, included in the total.", scan.tests.files),
+        (
+            "codecounter_duplicated_lines",
+            "Lines in exact copies of other files, beyond the first copy.",
+            scan.duplicated_lines(),
+        ),
+        ("codecounter_todos", "TODO-style marker comments found.", scan.todos.len() as u64),
+        (
+            "codecounter_last_scan_timestamp_seconds",
+            "When the last scan finished, as a Unix timestamp.",
+            scan.scanned_at.timestamp().max(0) as u64,
+        ),
+    ];
+    for (name, help, value) in totals {
+        write_metric(out, name, help, &[(String::new(), value)])?;
+    }
+
+    let languages = |value: fn(&LanguageStat) -> u64| {
+        scan.languages
+            .iter()
+            .map(|language| (format!("language=\"{}\"", prometheus_label(&language.name)), value(language)))
+            .collect::<Vec<_>>()
+    };
+    write_metric(out, "codecounter_language_lines", "Lines of code per language.", &languages(|language| language.lines))?;
+    write_metric(out, "codecounter_language_files", "Files per language.", &languages(|language| language.files))?;
+
+    let categories = |value: fn(&CategoryStat) -> u64| {
+        scan.categories
+            .iter()
+            .map(|category| (format!("category=\"{}\"", category.category.name()), value(category)))
+            .collect::<Vec<_>>()
+    };
+    let metrics = scan
+        .metrics
+        .iter()
+        .map(|(name, count)| (format!("metric=\"{}\"", prometheus_label(name)), *count))
+        .collect::<Vec<_>>();
+    if !metrics.is_empty() {
+        write_metric(out, "codecounter_analyzer_total", "What the [[analyzers]] found, over every file.", &metrics)?;
+    }
+
+    write_metric(
+        out,
+        "codecounter_category_lines",
+        "Lines per category, whether or not the category is in the total.",
+        &categories(|category| category.lines),
+    )?;
+    write_metric(out, "codecounter_category_files", "Files per category.", &categories(|category| category.files))?;
+
+    let crates = scan
+        .crates
+        .iter()
+        .flat_map(|member| {
+            member.parts().into_iter().map(|(part, bucket)| {
+                (format!("crate=\"{}\",part=\"{}\"", prometheus_label(&member.name), part), bucket.lines)
+            })
+        })
+        .collect::<Vec<_>>();
+    if !crates.is_empty() {
+        write_metric(out, "codecounter_crate_lines", "Lines per Cargo workspace crate and part of it.", &crates)?;
+    }
+
+    let packages = scan
+        .packages
+        .iter()
+        .map(|package| {
+            let labels = format!("package=\"{}\",kind=\"{}\"", prometheus_label(&package.name), package.kind.name());
+            (labels, package.lines)
+        })
+        .collect::<Vec<_>>();
+    if !packages.is_empty() {
+        write_metric(out, "codecounter_package_lines", "Lines per package of a monorepo.", &packages)?;
+    }
+
+    let roots = |value: fn(&RootStat) -> u64| {
+        scan.roots
+            .iter()
+            .map(|root| (format!("root=\"{}\"", prometheus_label(&root.path.display().to_string())), value(root)))
+            .collect::<Vec<_>>()
+    };
+    write_metric(out, "codecounter_root_lines", "Lines of code per scanned directory.", &roots(|root| root.lines))?;
+    write_metric(out, "codecounter_root_files", "Files per scanned directory.", &roots(|root| root.files))
+}
+
+fn write_metric(out: &mut dyn Write, name: &str, help: &str, samples: &[(String, u64)]) -> io::Result<()> {
+    writeln!(out, "# HELP {} {}", name, help)?;
+    writeln!(out, "# TYPE {} gauge", name)?;
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            writeln!(out, "{} {}", name, value)?;
+        } else {
+            writeln!(out, "{}{{{}}} {}", name, labels, value)?;
+        }
+    }
+    Ok(())
+}
+
+fn prometheus_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+const HTML_PALETTE: &[&str] = &[
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7", "#9c755f",
+    "#bab0ac",
+];
+
+const HTML_STYLE: &str = "\
+body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 960px; color: #222; }
+h1 { margin-bottom: 0; }
+.total { font-size: 4rem; font-weight: 700; margin: 0.5rem 0; }
+.muted { color: #666; }
+.share { display: flex; height: 1.5rem; border-radius: 4px; overflow: hidden; margin: 1.5rem 0; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2rem; }
+th, td { padding: 0.3rem 0.6rem; border-bottom: 1px solid #ddd; text-align: left; }
+td.num, th.num { text-align: right; font-variant-numeric: tabular-nums; }
+td.bar { width: 40%; }
+td.bar div { height: 0.9rem; border-radius: 2px; }
+";
+
+fn write_html(scan: &ScanResult, out: &mut dyn Write) -> io::Result<()> {
+    let roots = scan
+        .roots
+        .iter()
+        .map(|root| html_escape(&root.path.display().to_string()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Lines of code: {}</title>", format_with_commas(scan.lines))?;
+    writeln!(out, "<style>\n{}</style>", HTML_STYLE)?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<h1>Lines of code</h1>")?;
+    writeln!(out, "<p class=\"muted\">{}</p>", roots)?;
+    writeln!(out, "<p class=\"total\">{}</p>", format_with_commas(scan.lines))?;
+    writeln!(
+        out,
+        "<p class=\"muted\">in {} files, scanned {}</p>",
+        format_with_commas(scan.total_files()),
+        scan.scanned_at.format("%Y-%m-%d %H:%M:%S %z")
+    )?;
+    let notes = left_out_notes(scan);
+    if !notes.is_empty() {
+        writeln!(out, "<ul class=\"muted\">")?;
+        for note in notes {
+            writeln!(out, "<li>{}</li>", html_escape(&note))?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+    if let Some(changes) = &scan.since {
+        writeln!(
+            out,
+            "<p>Since <code>{}</code>: {} / {} lines</p>",
+            html_escape(&changes.reference),
+            format_signed(changes.added as i64),
+            format_signed(-(changes.removed as i64))
+        )?;
+    }
+
+    write!(out, "<div class=\"share\">")?;
+    for (index, language) in scan.languages.iter().enumerate() {
+        write!(
+            out,
+            "<div title=\"{} {}\" style=\"flex: {} 0 0; background: {}\"></div>",
+            html_escape(&language.name),
+            format_share(language.lines, scan.counted_lines()),
+            language.lines,
+            HTML_PALETTE[index % HTML_PALETTE.len()]
+        )?;
+    }
+    writeln!(out, "</div>")?;
+
+    let languages = scan
+        .languages
+        .iter()
+        .map(|language| (language.name.clone(), language.files, language.lines))
+        .collect::<Vec<_>>();
+    write_html_table(out, "Language", &languages, scan.counted_lines())?;
+    if scan.categories.len() > 1 {
+        write_html_table(out, "Category", &category_breakdown(scan), scan.counted_lines())?;
+    }
+    if !scan.crates.is_empty() {
+        write_html_table(out, "Crate", &crate_breakdown(scan), scan.lines)?;
+    }
+    if scan.packages.len() > 1 {
+        write_html_table(out, "Package", &package_breakdown(scan), scan.lines)?;
+    }
+    write_html_table(out, "Directory", &directory_breakdown(scan), scan.counted_lines())?;
+
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+    Ok(())
+}
+
+fn write_html_table(out: &mut dyn Write, label: &str, rows: &[(String, u64, u64)], total: u64) -> io::Result<()> {
+    let max = rows.iter().map(|(_, _, lines)| *lines).max().unwrap_or(0).max(1);
+    writeln!(out, "<table>")?;
+    writeln!(
+        out,
+        "<tr><th>{}</th><th class=\"num\">Files</th><th class=\"num\">Lines</th><th class=\"num\">Share</th><th></th></tr>",
+        label
+    )?;
+    for (index, (name, files, lines)) in rows.iter().enumerate() {
+        writeln!(
+            out,
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+             <td class=\"bar\"><div style=\"width: {:.1}%; background: {}\"></div></td></tr>",
+            html_escape(name),
+            format_with_commas(*files),
+            format_with_commas(*lines),
+            format_share(*lines, total),
+            *lines as f64 * 100.0 / max as f64,
+            HTML_PALETTE[index % HTML_PALETTE.len()]
+        )?;
+    }
+    writeln!(out, "</table>")?;
+    Ok(())
+}
+
+/// Rows for the category table, each labelled with what it adds to the total.
+fn category_breakdown(scan: &ScanResult) -> Vec<(String, u64, u64)> {
+    scan.categories
+        .iter()
+        .map(|category| (category_label(category), category.files, category.lines))
+        .collect()
+}
+
+/// Rows for the crate table: each workspace member, followed by its `src`, `tests`,
+/// `benches` and other files.
+fn crate_breakdown(scan: &ScanResult) -> Vec<(String, u64, u64)> {
+    let mut rows = Vec::new();
+    for member in &scan.crates {
+        rows.push((member.name.clone(), member.files, member.lines));
+        for (part, bucket) in member.parts() {
+            rows.push((format!("{} · {}", member.name, part), bucket.files, bucket.lines));
+        }
+    }
+    rows
+}
+
+/// Rows for the package table, each labelled with its kind and its directory.
+fn package_breakdown(scan: &ScanResult) -> Vec<(String, u64, u64)> {
+    scan.packages
+        .iter()
+        .map(|package| (package_label(scan, package), package.files, package.lines))
+        .collect()
+}
+
+/// "web (npm, apps/web)", or "api (go)" for a package at the top of its root.
+pub fn package_label(scan: &ScanResult, package: &PackageStat) -> String {
+    let dir = scan.display(package.root, &scan.roots[package.root].path.join(&package.path));
+    if dir.is_empty() {
+        format!("{} ({})", package.name, package.kind.name())
+    } else {
+        format!("{} ({}, {})", package.name, package.kind.name(), dir)
+    }
+}
+
+/// "code", "docs (not in the total)" or "docs (×0.5 in the total)".
+pub fn category_label(category: &CategoryStat) -> String {
+    let name = category.category.name();
+    if category.weight == 0.0 {
+        format!("{} (not in the total)", name)
+    } else if category.weight == 1.0 {
+        name.to_string()
+    } else {
+        format!("{} (×{} in the total)", name, category.weight)
+    }
+}
+
+fn directory_breakdown(scan: &ScanResult) -> Vec<(String, u64, u64)> {
+    let mut by_dir = BTreeMap::<String, (u64, u64)>::new();
+    for file in &scan.file_stats {
+        let path = scan.display_path(file);
+        let mut components = Path::new(&path).components();
+        let dir = match (components.next(), components.next()) {
+            (Some(first), Some(_)) => first.as_os_str().to_string_lossy().into_owned(),
+            _ => ".".to_string(),
+        };
+        let entry = by_dir.entry(dir).or_default();
+        entry.0 += 1;
+        entry.1 += file.lines;
+    }
+    let mut rows = by_dir
+        .into_iter()
+        .map(|(dir, (files, lines))| (dir, files, lines))
+        .collect::<Vec<_>>();
+    rows.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    rows
+}
+
+fn html_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
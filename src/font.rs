@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+const BUILTIN_FONTS: &str = include_str!("fonts.toml");
+/// Separators a font leaves out are drawn this many cells wide.
+const FALLBACK_WIDTH: usize = 2;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Font {
+    pub name: String,
+    /// How many times each pattern cell is repeated, across and down.
+    #[serde(default = "unscaled")]
+    pub scale: (usize, usize),
+    /// Blank columns between two digits, after scaling.
+    #[serde(default = "single_space")]
+    pub spacing: usize,
+    /// Made of solid blocks, so it still looks right drawn at other scales.
+    #[serde(default)]
+    pub scalable: bool,
+    glyphs: BTreeMap<String, Vec<String>>,
+}
+
+fn unscaled() -> (usize, usize) {
+    (1, 1)
+}
+
+fn single_space() -> usize {
+    1
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FontFile {
+    font: Vec<Font>,
+}
+
+impl Font {
+    pub fn height(&self) -> usize {
+        self.glyphs.get("0").map_or(0, Vec::len)
+    }
+
+    /// The pattern for `ch`. A character the font doesn't have is drawn as itself on
+    /// the bottom row, or as a blank gap for whitespace.
+    pub fn rows(&self, ch: char) -> Vec<String> {
+        if let Some(rows) = self.glyphs.get(ch.encode_utf8(&mut [0; 4]) as &str) {
+            return rows.clone();
+        }
+        let height = self.height();
+        let blank = " ".repeat(FALLBACK_WIDTH);
+        let mut rows = vec![blank; height];
+        if !ch.is_whitespace()
+            && let Some(last) = rows.last_mut()
+        {
+            *last = format!("{} ", ch);
+        }
+        rows
+    }
+
+    /// An unlit digit, for a leading place the count hasn't reached yet.
+    pub fn blank_digit(&self) -> Vec<String> {
+        let width = self.glyphs.get("0").and_then(|rows| rows.first()).map_or(0, |row| row.chars().count());
+        vec![" ".repeat(width); self.height()]
+    }
+
+    /// Checks a font read from `path` and pads ragged rows so every glyph is a rectangle.
+    fn validate(&mut self, path: &str) -> Result<(), String> {
+        let Some(height) = self.glyphs.get("0").map(Vec::len) else {
+            return Err(format!("{}: font `{}` has no glyph for 0", path, self.name));
+        };
+        if self.scale.0 == 0 || self.scale.1 == 0 {
+            return Err(format!("{}: font `{}` has a zero scale", path, self.name));
+        }
+        for digit in '0'..='9' {
+            if !self.glyphs.contains_key(&digit.to_string()) {
+                return Err(format!("{}: font `{}` has no glyph for {}", path, self.name, digit));
+            }
+        }
+        for (ch, rows) in &mut self.glyphs {
+            if ch.chars().count() != 1 {
+                return Err(format!("{}: font `{}`: `{}` is not a single character", path, self.name, ch));
+            }
+            if rows.len() != height {
+                return Err(format!(
+                    "{}: font `{}`: `{}` is {} rows tall, but 0 is {}",
+                    path,
+                    self.name,
+                    ch,
+                    rows.len(),
+                    height
+                ));
+            }
+            let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+            for row in rows {
+                let pad = width - row.chars().count();
+                row.extend(std::iter::repeat_n(' ', pad));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FontSet {
+    fonts: Vec<Font>,
+}
+
+impl Default for FontSet {
+    fn default() -> Self {
+        let mut file: FontFile = toml::from_str(BUILTIN_FONTS).expect("built-in font table is valid");
+        for font in &mut file.font {
+            font.validate("fonts.toml").expect("built-in fonts are valid");
+        }
+        Self { fonts: file.font }
+    }
+}
+
+impl FontSet {
+    /// Adds every font in a TOML file, replacing built-in fonts of the same name.
+    pub fn load(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let raw = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let file: FontFile = toml::from_str(&raw).map_err(|err| format!("{}: {}", path.display(), err))?;
+        for mut font in file.font {
+            font.validate(&path.display().to_string())?;
+            match self.fonts.iter_mut().find(|known| known.name == font.name) {
+                Some(known) => *known = font,
+                None => self.fonts.push(font),
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads every `*.toml` in `dir`, in name order so replacements are predictable.
+    pub fn load_dir(&mut self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Ok(());
+        };
+        let mut paths = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        for path in paths {
+            self.load(&path)?;
+        }
+        Ok(())
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.fonts.iter().position(|font| font.name == name)
+    }
+
+    pub fn get(&self, index: usize) -> &Font {
+        &self.fonts[index % self.fonts.len()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.fonts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fonts.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.fonts.iter().map(|font| font.name.as_str()).collect()
+    }
+}
@@ -0,0 +1,373 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+/// How digits are grouped: in threes (`1,234,567`), or the Indian lakh/crore style
+/// (`12,34,567`) with a group of three followed by groups of two.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Grouping {
+    #[default]
+    Thousands,
+    Indian,
+}
+
+/// Separators used for every number shown to people. Machine-readable output (CSV,
+/// JSON, Prometheus) keeps plain digits regardless.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub group: String,
+    pub decimal: char,
+    pub grouping: Grouping,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self {
+            group: ",".to_string(),
+            decimal: '.',
+            grouping: Grouping::Thousands,
+        }
+    }
+}
+
+impl NumberFormat {
+    /// The conventions for a locale such as `de_DE.UTF-8`, `fr` or `en-IN`; `None` if unknown.
+    pub fn for_locale(locale: &str) -> Option<Self> {
+        let locale = locale.split(['.', '@']).next()?.replace('-', "_").to_lowercase();
+        let (language, region) = locale.split_once('_').unwrap_or((&locale, ""));
+        let (group, decimal) = match (language, region) {
+            ("c" | "posix", _) => (",", '.'),
+            (_, "in") | ("hi" | "bn" | "ta" | "te" | "mr" | "gu" | "kn" | "ml", _) => {
+                return Some(Self {
+                    grouping: Grouping::Indian,
+                    ..Self::default()
+                });
+            }
+            ("de" | "it", "ch") | (_, "li") => ("'", '.'),
+            ("de" | "nl" | "it" | "es" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "hr" | "sl", _) => (".", ','),
+            ("fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "uk" | "hu" | "bg", _) => ("\u{a0}", ','),
+            ("en" | "ja" | "zh" | "ko" | "he" | "th", _) => (",", '.'),
+            _ => return None,
+        };
+        Some(Self {
+            group: group.to_string(),
+            decimal,
+            grouping: Grouping::Thousands,
+        })
+    }
+
+    /// From `LC_ALL`, `LC_NUMERIC` or `LANG`, whichever is set first.
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .find_map(|var| std::env::var(var).ok().filter(|value| !value.is_empty()))
+            .and_then(|locale| Self::for_locale(&locale))
+            .unwrap_or_default()
+    }
+
+    /// Whether a separator goes between the digit at `place` (0 = units) and the one below it.
+    pub fn separates(&self, place: usize) -> bool {
+        match self.grouping {
+            Grouping::Thousands => place > 0 && place.is_multiple_of(3),
+            Grouping::Indian => place == 3 || (place > 3 && place % 2 == 1),
+        }
+    }
+}
+
+static NUMBER_FORMAT: OnceLock<NumberFormat> = OnceLock::new();
+
+/// Picks the separators for the rest of the run; only the first call has any effect.
+pub fn set_number_format(format: NumberFormat) {
+    let _ = NUMBER_FORMAT.set(format);
+}
+
+pub fn number_format() -> &'static NumberFormat {
+    NUMBER_FORMAT.get_or_init(NumberFormat::default)
+}
+
+/// `value` with the digits grouped, `1,234,567` by default.
+pub fn format_with_commas(value: u64) -> String {
+    let format = number_format();
+    let digits = value.to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 2 * format.group.len());
+    for (index, digit) in digits.chars().enumerate() {
+        out.push(digit);
+        let place = digits.len() - 1 - index;
+        if format.separates(place) {
+            out.push_str(&format.group);
+        }
+    }
+    out
+}
+
+/// A decimal number with `places` digits after the configured decimal separator.
+pub fn format_decimal(value: f64, places: usize) -> String {
+    localize_decimal(format!("{:.*}", places, value))
+}
+
+fn localize_decimal(text: String) -> String {
+    match number_format().decimal {
+        '.' => text,
+        decimal => text.replace('.', &decimal.to_string()),
+    }
+}
+
+pub fn format_signed(value: i64) -> String {
+    let sign = if value < 0 { '-' } else { '+' };
+    format!("{}{}", sign, format_with_commas(value.unsigned_abs()))
+}
+
+pub fn format_share(part: u64, total: u64) -> String {
+    if total == 0 {
+        return "0%".to_string();
+    }
+    format!("{}%", format_decimal(part as f64 * 100.0 / total as f64, 1))
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let total_ms = duration.as_millis();
+    let ms_in_day = 86_400_000u128;
+    let ms_in_hour = 3_600_000u128;
+    let ms_in_minute = 60_000u128;
+    let ms_in_second = 1_000u128;
+
+    let days = total_ms / ms_in_day;
+    let hours = (total_ms % ms_in_day) / ms_in_hour;
+    let minutes = (total_ms % ms_in_hour) / ms_in_minute;
+    let seconds = (total_ms % ms_in_minute) / ms_in_second;
+    let millis = total_ms % ms_in_second;
+
+    let mut parts = Vec::new();
+    if days > 0 {
+        parts.push(format!("{}d", days));
+    }
+    if hours > 0 {
+        parts.push(format!("{}h", hours));
+    }
+    if minutes > 0 {
+        parts.push(format!("{}m", minutes));
+    }
+    parts.push(format!("{}s", seconds));
+    if millis > 0 || parts.is_empty() {
+        parts.push(format!("{}ms", millis));
+    }
+
+    parts.join(" ")
+}
+
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid duration `{}` (expected e.g. 30, 30s, 5m or 1h30m)", value);
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for ch in value.chars() {
+        if ch.is_ascii_digit() {
+            digits.push(ch);
+            continue;
+        }
+        let unit = match ch {
+            's' => 1,
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            _ => return Err(invalid()),
+        };
+        let amount = digits.parse::<u64>().map_err(|_| invalid())?;
+        total = amount
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        digits.clear();
+    }
+    if !digits.is_empty() || value.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// A size in bytes, plain or with a `K`, `M`, `G` or `T` suffix (`KB` / `KiB` too) in
+/// powers of 1024, as `format_bytes` shows them.
+pub fn parse_byte_size(value: &str) -> Result<u64, String> {
+    let invalid = || format!("invalid size `{}` (expected e.g. 500000, 512K, 10M or 2G)", value);
+    let value = value.trim();
+    let digits = value.find(|ch: char| !ch.is_ascii_digit()).unwrap_or(value.len());
+    let amount = value[..digits].parse::<u64>().map_err(|_| invalid())?;
+    let unit = value[digits..].trim();
+    let unit = unit.strip_suffix("iB").or_else(|| unit.strip_suffix(['B', 'b'])).unwrap_or(unit);
+    let shift = match unit.to_ascii_uppercase().as_str() {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return Err(invalid()),
+    };
+    amount.checked_mul(1 << shift).ok_or_else(invalid)
+}
+
+/// An amount of money, such as a developer-month's cost: a number above zero.
+pub fn parse_amount(value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|amount| amount.is_finite() && *amount > 0.0)
+        .ok_or_else(|| format!("invalid amount `{}` (expected a number above zero, e.g. 15000)", value))
+}
+
+/// Parses an image size in terminal cells, e.g. `120x40`.
+pub fn parse_size(text: &str) -> Result<(u16, u16), String> {
+    let (columns, rows) = text
+        .split_once(['x', 'X'])
+        .ok_or_else(|| format!("expected COLSxROWS, e.g. 120x40, not `{}`", text))?;
+    let parse = |part: &str| {
+        part.trim()
+            .parse::<u16>()
+            .ok()
+            .filter(|cells| (10..=400).contains(cells))
+            .ok_or_else(|| format!("`{}` isn't a size between 10 and 400 cells", part))
+    };
+    Ok((parse(columns)?, parse(rows)?))
+}
+
+const COMPACT_UNITS: &[(u64, &str)] = &[(1_000_000_000, "B"), (1_000_000, "M"), (1_000, "k")];
+
+pub fn format_compact(value: u64) -> String {
+    for &(size, suffix) in COMPACT_UNITS {
+        if value >= size {
+            let tenths = value / (size / 10);
+            return match (tenths < 100, tenths % 10) {
+                (true, 0) | (false, _) => format!("{}{}", tenths / 10, suffix),
+                (true, fraction) => localize_decimal(format!("{}.{}{}", tenths / 10, fraction, suffix)),
+            };
+        }
+    }
+    value.to_string()
+}
+
+/// Like `format_compact` but keeping three significant digits (`1.24M`, `12.4M`, `124M`),
+/// for the big number where the extra precision is still readable.
+pub fn format_compact_precise(value: u64) -> String {
+    for &(size, suffix) in COMPACT_UNITS {
+        if value >= size {
+            let hundredths = value / (size / 100);
+            let number = match hundredths {
+                0..1_000 => format!("{}.{:02}", hundredths / 100, hundredths % 100),
+                1_000..10_000 => format!("{}.{}", hundredths / 100, hundredths / 10 % 10),
+                _ => (hundredths / 100).to_string(),
+            };
+            let number = if number.contains('.') {
+                number.trim_end_matches('0').trim_end_matches('.')
+            } else {
+                &number
+            };
+            return localize_decimal(format!("{}{}", number, suffix));
+        }
+    }
+    value.to_string()
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{} {}", format_decimal(value, 1), UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `digits` grouped the way `format` would, without touching the run's own format.
+    fn group(format: &NumberFormat, digits: &str) -> String {
+        let mut out = String::new();
+        for (index, digit) in digits.chars().enumerate() {
+            out.push(digit);
+            if format.separates(digits.len() - 1 - index) {
+                out.push_str(&format.group);
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn locales() {
+        let german = NumberFormat::for_locale("de_DE.UTF-8").unwrap();
+        assert_eq!((german.group.as_str(), german.decimal), (".", ','));
+        let swiss = NumberFormat::for_locale("de-CH").unwrap();
+        assert_eq!((swiss.group.as_str(), swiss.decimal), ("'", '.'));
+        let french = NumberFormat::for_locale("fr_FR@euro").unwrap();
+        assert_eq!((french.group.as_str(), french.decimal), ("\u{a0}", ','));
+        assert_eq!(NumberFormat::for_locale("C"), Some(NumberFormat::default()));
+        assert_eq!(NumberFormat::for_locale("en_IN").unwrap().grouping, Grouping::Indian);
+        assert_eq!(NumberFormat::for_locale("hi").unwrap().grouping, Grouping::Indian);
+        assert_eq!(NumberFormat::for_locale("xx_YY"), None);
+    }
+
+    #[test]
+    fn thousands_grouping() {
+        let format = NumberFormat::default();
+        assert_eq!(group(&format, "0"), "0");
+        assert_eq!(group(&format, "999"), "999");
+        assert_eq!(group(&format, "1000"), "1,000");
+        assert_eq!(group(&format, "1234567"), "1,234,567");
+    }
+
+    #[test]
+    fn lakh_grouping() {
+        let format = NumberFormat::for_locale("en_IN").unwrap();
+        assert_eq!(group(&format, "999"), "999");
+        assert_eq!(group(&format, "1000"), "1,000");
+        assert_eq!(group(&format, "100000"), "1,00,000");
+        assert_eq!(group(&format, "1234567"), "12,34,567");
+        assert_eq!(group(&format, "123456789"), "12,34,56,789");
+    }
+
+    #[test]
+    fn compact_precise() {
+        assert_eq!(format_compact_precise(999), "999");
+        assert_eq!(format_compact_precise(1_000), "1k");
+        assert_eq!(format_compact_precise(1_240_000), "1.24M");
+        assert_eq!(format_compact_precise(1_200_000), "1.2M");
+        assert_eq!(format_compact_precise(12_345_678), "12.3M");
+        assert_eq!(format_compact_precise(123_456_789), "123M");
+        assert_eq!(format_compact_precise(2_000_000_000), "2B");
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration(" 5m "), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5_400)));
+        assert_eq!(parse_duration("2d"), Ok(Duration::from_secs(172_800)));
+        for invalid in ["", "m", "5x", "1h30", "-5s", "99999999999999999999d"] {
+            assert!(parse_duration(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn byte_sizes() {
+        assert_eq!(parse_byte_size("500000"), Ok(500_000));
+        assert_eq!(parse_byte_size("512K"), Ok(512 << 10));
+        assert_eq!(parse_byte_size("10 MB"), Ok(10 << 20));
+        assert_eq!(parse_byte_size("2GiB"), Ok(2 << 30));
+        assert_eq!(parse_byte_size("1t"), Ok(1 << 40));
+        for invalid in ["", "K", "10X", "1.5M", "-1", "99999999999T"] {
+            assert!(parse_byte_size(invalid).is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Settings;
+use crate::cache::ObjectCache;
+use crate::notebook::is_notebook;
+use crate::scan::{count_file, count_named, counted_category, is_counted, FileCount, Probes};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineChanges {
+    pub reference: String,
+    pub added: u64,
+    pub removed: u64,
+}
+
+pub fn verify_ref(dir: &Path, reference: &str) -> Result<(), Box<dyn Error>> {
+    git(dir, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", reference)])
+        .map(|_| ())
+        .map_err(|_| format!("`{}` is not a commit in the git repository at {}", reference, dir.display()).into())
+}
+
+/// Lines added and removed since `reference`, in the working tree and untracked files. Like
+/// a scan, it leaves out files set aside as generated, vendored or minified, and weighs the
+/// lines of the rest by their category.
+pub fn changes_since(dir: &Path, reference: &str, settings: &Settings) -> Result<LineChanges, Box<dyn Error>> {
+    let mut changes = LineChanges {
+        reference: reference.to_string(),
+        added: 0,
+        removed: 0,
+    };
+
+    // `-z` keeps paths as they are; otherwise git quotes any with non-ASCII characters.
+    let numstat = git(dir, &["diff", "--numstat", "--relative", "--no-renames", "-z", reference, "--"])?;
+    let mut changed = Vec::new();
+    for record in numstat.split('\0') {
+        let mut fields = record.splitn(3, '\t');
+        let (Some(added), Some(removed), Some(path)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        if is_counted(Path::new(path), settings) {
+            changed.push((PathBuf::from(path), added.parse::<u64>().unwrap_or(0), removed.parse::<u64>().unwrap_or(0)));
+        }
+    }
+
+    // Deleted files are placed by what they were, and notebooks are compared by their cells,
+    // so both need the file as of `reference`.
+    let before = tree_files(dir, reference)?
+        .into_iter()
+        .map(|(hash, path)| (path, hash))
+        .collect::<HashMap<_, _>>();
+    let wanted = changed
+        .iter()
+        .filter(|(path, ..)| is_notebook(path) || !dir.join(path).is_file())
+        .filter_map(|(path, ..)| Some((before.get(path)?.clone(), path.clone())))
+        .collect::<Vec<_>>();
+    let mut cache = ObjectCache::load();
+    let counts = blob_counts(dir, &wanted, &mut cache)?;
+    cache.save()?;
+    let before = wanted.into_iter().map(|(_, path)| path).zip(counts).collect::<HashMap<_, _>>();
+
+    for (path, added, removed) in changed {
+        let now = count_file(&dir.join(&path), &Probes::default()).ok().flatten();
+        let then = before.get(&path).cloned().flatten();
+        let Some(category) = now.as_ref().or(then.as_ref()).and_then(|count| counted_category(&path, count, settings))
+        else {
+            continue;
+        };
+        if is_notebook(&path) {
+            // Its JSON's line changes say little about its cells, so the change in what it
+            // adds to the total stands in for them.
+            let weigh = |count: Option<FileCount>| count.map_or(0, |count| count.weighted_lines(category, &settings.categories));
+            let (now, then) = (weigh(now), weigh(then));
+            changes.added += now.saturating_sub(then);
+            changes.removed += then.saturating_sub(now);
+        } else {
+            changes.added += settings.categories.weighted(category, added);
+            changes.removed += settings.categories.weighted(category, removed);
+        }
+    }
+
+    let untracked = git(dir, &["ls-files", "--others", "--exclude-standard", "-z"])?;
+    for path in untracked
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(Path::new)
+        .filter(|path| is_counted(path, settings))
+    {
+        if let Ok(Some(count)) = count_file(&dir.join(path), &Probes::default())
+            && let Some(category) = counted_category(path, &count, settings)
+        {
+            changes.added += count.weighted_lines(category, &settings.categories);
+        }
+    }
+
+    Ok(changes)
+}
+
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub hash: String,
+    pub date: DateTime<FixedOffset>,
+    pub label: String,
+}
+
+/// The last `count` commits on the first-parent history of HEAD, oldest first.
+pub fn recent_commits(dir: &Path, count: usize) -> Result<Vec<Commit>, Box<dyn Error>> {
+    let log = git(
+        dir,
+        &["log", "--first-parent", "-n", &count.to_string(), "--format=%H%x09%cI%x09%s"],
+    )?;
+    let mut commits = log.lines().filter_map(parse_commit).collect::<Vec<_>>();
+    commits.reverse();
+    Ok(commits)
+}
+
+/// The `count` most recent tags, oldest first, labelled with the tag name.
+pub fn recent_tags(dir: &Path, count: usize) -> Result<Vec<Commit>, Box<dyn Error>> {
+    let tags = git(
+        dir,
+        &[
+            "for-each-ref",
+            "--sort=-creatordate",
+            &format!("--count={}", count),
+            "--format=%(refname:short)",
+            "refs/tags",
+        ],
+    )?;
+    let mut commits = Vec::new();
+    for tag in tags.lines() {
+        let log = git(dir, &["log", "-1", "--format=%H%x09%cI", &format!("{}^{{commit}}", tag), "--"])?;
+        if let Some(commit) = log.lines().next().and_then(parse_commit) {
+            commits.push(Commit {
+                label: tag.to_string(),
+                ..commit
+            });
+        }
+    }
+    commits.reverse();
+    Ok(commits)
+}
+
+fn parse_commit(line: &str) -> Option<Commit> {
+    let mut fields = line.splitn(3, '\t');
+    let hash = fields.next()?.to_string();
+    let date = DateTime::parse_from_rfc3339(fields.next()?).ok()?;
+    Some(Commit {
+        hash,
+        date,
+        label: fields.next().unwrap_or_default().to_string(),
+    })
+}
+
+/// Blob hashes and paths (relative to `dir`) of every file in `commit` below `dir`.
+pub fn tree_files(dir: &Path, commit: &str) -> Result<Vec<(String, PathBuf)>, Box<dyn Error>> {
+    let tree = git(dir, &["ls-tree", "-r", "-z", commit])?;
+    Ok(tree
+        .split('\0')
+        .filter_map(|entry| {
+            let (meta, path) = entry.split_once('\t')?;
+            let mut meta = meta.split(' ');
+            let (_mode, kind, hash) = (meta.next()?, meta.next()?, meta.next()?);
+            (kind == "blob").then(|| (hash.to_string(), PathBuf::from(path)))
+        })
+        .collect())
+}
+
+/// Counts of the blobs in `files`, hash and path pairs, in the order given: from `cache`,
+/// or else read out of git in one batch and added to it. Only what placing a file in the
+/// total needs is kept. Binary blobs come back as `None`.
+pub fn blob_counts(
+    dir: &Path,
+    files: &[(String, PathBuf)],
+    cache: &mut ObjectCache,
+) -> Result<Vec<Option<FileCount>>, Box<dyn Error>> {
+    let mut seen = HashSet::new();
+    let missing = files
+        .iter()
+        .filter(|(hash, _)| cache.get(hash).is_none() && seen.insert(hash))
+        .collect::<Vec<_>>();
+    for ((hash, _), count) in missing.iter().zip(read_blobs(dir, &missing)?) {
+        cache.insert(hash.clone(), count.map(|count| FileCount { license: None, ..count }));
+    }
+    Ok(files.iter().map(|(hash, _)| cache.get(hash).flatten()).collect())
+}
+
+/// Counts a batch of blobs via a single `git cat-file --batch`; notebooks by their cells.
+fn read_blobs(dir: &Path, files: &[&(String, PathBuf)]) -> Result<Vec<Option<FileCount>>, Box<dyn Error>> {
+    if files.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = child.stdin.take().ok_or("git cat-file: no stdin")?;
+    let input = files.iter().map(|(hash, _)| format!("{}\n", hash)).collect::<String>();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let mut stdout = BufReader::new(child.stdout.take().ok_or("git cat-file: no stdout")?);
+    let mut counts = Vec::with_capacity(files.len());
+    let mut header = String::new();
+    for (hash, path) in files {
+        header.clear();
+        stdout.read_line(&mut header)?;
+        let size = match header.trim_end().rsplit_once(' ') {
+            Some((_, size)) if !header.ends_with("missing\n") => size.parse::<u64>()?,
+            _ => return Err(format!("git cat-file: object {} is missing", hash).into()),
+        };
+        // Streamed like a file on disk, so a huge blob doesn't have to fit in memory. What
+        // the count leaves unread of a binary blob, and the newline after it, are skipped.
+        let mut blob = (&mut stdout).take(size);
+        counts.push(count_named(path, &mut blob, &Probes::default())?);
+        io::copy(&mut blob, &mut io::sink())?;
+        stdout.read_exact(&mut [0])?;
+    }
+
+    writer.join().map_err(|_| "git cat-file: writer thread panicked")??;
+    child.wait()?;
+    Ok(counts)
+}
+
+/// The submodules `.gitmodules` declares below `dir`, relative to it. The file is looked
+/// for in `dir` and the directories above it, up to the top of the repository.
+pub fn submodule_paths(dir: &Path) -> Vec<PathBuf> {
+    let Ok(dir) = fs::canonicalize(dir) else {
+        return Vec::new();
+    };
+    for top in dir.ancestors() {
+        if let Ok(gitmodules) = fs::read_to_string(top.join(".gitmodules")) {
+            return gitmodules
+                .lines()
+                .filter_map(|line| {
+                    let (key, value) = line.split_once('=')?;
+                    (key.trim() == "path").then(|| top.join(value.trim()))
+                })
+                .filter_map(|path| path.strip_prefix(&dir).ok().map(Path::to_path_buf))
+                .filter(|path| !path.as_os_str().is_empty())
+                .collect();
+        }
+        if top.join(".git").exists() {
+            break;
+        }
+    }
+    Vec::new()
+}
+
+/// The untracked paths below `dir` that git ignores, relative to it: what `.gitignore`
+/// files, `.git/info/exclude` and the user's global excludes file match, as `git status`
+/// sees it. An ignored directory is listed once rather than file by file. Empty outside a
+/// repository, or without git.
+pub fn ignored_paths(dir: &Path) -> HashSet<PathBuf> {
+    match git(dir, &["ls-files", "--others", "--ignored", "--exclude-standard", "--directory", "-z"]) {
+        Ok(output) => output
+            .split('\0')
+            .filter(|path| !path.is_empty())
+            .map(|path| PathBuf::from(path.trim_end_matches('/')))
+            .collect(),
+        Err(_) => HashSet::new(),
+    }
+}
+
+/// Whether a command-line path is really a repository URL, like `https://host/org/repo`
+/// or scp-style `git@host:org/repo.git`.
+pub fn is_remote_url(arg: &str) -> bool {
+    const SCHEMES: &[&str] = &["https://", "http://", "ssh://", "git://", "git+ssh://", "file://"];
+    SCHEMES.iter().any(|scheme| arg.starts_with(scheme))
+        || arg
+            .split_once(':')
+            .is_some_and(|(host, path)| host.contains('@') && !host.contains('/') && !path.starts_with("//"))
+}
+
+/// The directory name `git clone` would pick for `url`: its last component without `.git`.
+pub fn repo_name(url: &str) -> String {
+    let name = url
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(".git");
+    if name.is_empty() { "repo".to_string() } else { name.to_string() }
+}
+
+/// Clones just the latest commit of `url`'s default branch into `dest`.
+pub fn shallow_clone(url: &str, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let output = Command::new("git")
+        .args(["clone", "--depth", "1", "--quiet", "--", url])
+        .arg(dest)
+        .output()?;
+    if !output.status.success() {
+        return Err(format!("git clone {} failed: {}", url, String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(())
+}
+
+/// The abbreviated hash of the commit `reference` names.
+pub fn short_hash(dir: &Path, reference: &str) -> Result<String, Box<dyn Error>> {
+    Ok(git(dir, &["rev-parse", "--short", &format!("{}^{{commit}}", reference)])?.trim().to_string())
+}
+
+/// Writes the files of `reference` (below `dir`) into `dest` with `git archive | tar -x`,
+/// leaving the working copy, index and worktree list alone.
+pub fn extract_tree(dir: &Path, reference: &str, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let mut archive = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["archive", "--format=tar", reference])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let tar_input = archive.stdout.take().ok_or("git archive: no stdout")?;
+    let tar = Command::new("tar")
+        .arg("-x")
+        .arg("-C")
+        .arg(dest)
+        .stdin(tar_input)
+        .output()
+        .map_err(|err| format!("tar: {}", err))?;
+    let archived = archive.wait_with_output()?;
+    if !archived.status.success() {
+        return Err(format!(
+            "git archive {} failed: {}",
+            reference,
+            String::from_utf8_lossy(&archived.stderr).trim()
+        )
+        .into());
+    }
+    if !tar.status.success() {
+        return Err(format!("tar failed: {}", String::from_utf8_lossy(&tar.stderr).trim()).into());
+    }
+    Ok(())
+}
+
+/// What tells a repository apart from unrelated ones: its root commits, as of HEAD. Clones
+/// of the same repository share them; a shallow clone's cut-off commit stands in for them.
+pub fn repo_id(dir: &Path) -> Result<String, Box<dyn Error>> {
+    let roots = git(dir, &["rev-list", "--max-parents=0", "HEAD"])?;
+    let mut roots = roots.lines().collect::<Vec<_>>();
+    roots.sort_unstable();
+    Ok(roots.join(","))
+}
+
+/// Lines per author of `path` as of `commit`, from `git blame --porcelain`.
+pub fn blame_authors(dir: &Path, commit: &str, path: &Path) -> Result<Vec<(String, u64)>, Box<dyn Error>> {
+    let path = path.to_string_lossy();
+    let blame = git(dir, &["blame", "--porcelain", commit, "--", &path])?;
+    // Porcelain output names a commit's author only the first time the commit appears.
+    let mut authors = HashMap::new();
+    let mut lines = HashMap::<String, u64>::new();
+    let mut current = "";
+    for line in blame.lines() {
+        if line.starts_with('\t') {
+            let author = authors.get(current).cloned().unwrap_or_default();
+            *lines.entry(author).or_default() += 1;
+        } else if let Some(name) = line.strip_prefix("author ") {
+            authors.insert(current, name.to_string());
+        } else if let Some((hash, _)) = line.split_once(' ')
+            && hash.len() >= 40
+            && hash.bytes().all(|byte| byte.is_ascii_hexdigit())
+        {
+            current = hash;
+        }
+    }
+    Ok(lines.into_iter().collect())
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
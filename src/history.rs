@@ -0,0 +1,303 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Days, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{data_home, Settings};
+#[cfg(feature = "sqlite")]
+use crate::history_db::HistoryDb;
+use crate::scan::ScanResult;
+
+const HISTORY_FILE: &str = "history.jsonl";
+/// Once `history.jsonl` holds this many scans, it's compacted to a day's last scan each.
+const HISTORY_LIMIT: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub roots: Vec<PathBuf>,
+    /// [`Settings::fingerprint`] of the scan; only scans counted alike are compared.
+    #[serde(default)]
+    pub filters: String,
+    pub scanned_at: DateTime<Local>,
+    pub lines: u64,
+    pub files: u64,
+    /// The milestone this scan was the first to reach.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub milestone: Option<u64>,
+}
+
+/// Line counts worth celebrating: every multiple of `every`, plus any listed in `at`.
+#[derive(Debug, Clone, Default)]
+pub struct Milestones {
+    pub every: Option<u64>,
+    pub at: Vec<u64>,
+}
+
+impl Milestones {
+    /// The highest milestone in `(previous, current]`, so a jump past several is celebrated once.
+    pub fn crossed(&self, previous: u64, current: u64) -> Option<u64> {
+        if current <= previous {
+            return None;
+        }
+        let multiple = self
+            .every
+            .filter(|every| *every > 0)
+            .map(|every| current / every * every)
+            .filter(|multiple| *multiple > previous && *multiple > 0);
+        let listed = self.at.iter().copied().filter(|at| *at > previous && *at <= current).max();
+        multiple.max(listed)
+    }
+}
+
+#[derive(Debug)]
+pub struct Delta {
+    pub change: i64,
+    pub label: String,
+}
+
+/// Where scans are recorded.
+#[derive(Debug)]
+enum Store {
+    Jsonl(PathBuf),
+    #[cfg(feature = "sqlite")]
+    Sqlite(HistoryDb),
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    store: Option<Store>,
+    roots: Vec<PathBuf>,
+    filters: String,
+    entries: Vec<HistoryEntry>,
+    /// Scans in `history.jsonl`, of every set of roots.
+    stored: usize,
+}
+
+impl History {
+    /// The earlier scans of `roots` made with the same filters, from the `history-db` database
+    /// when one is configured and from `history.jsonl` otherwise. With history turned off,
+    /// only this session's are kept.
+    pub fn load(roots: &[PathBuf], settings: &Settings) -> io::Result<Self> {
+        let filters = settings.fingerprint();
+        if !settings.history {
+            return Ok(Self {
+                roots: roots.to_vec(),
+                filters,
+                ..Self::default()
+            });
+        }
+        #[cfg(feature = "sqlite")]
+        if let Some(path) = &settings.history_db {
+            let db = HistoryDb::new(path);
+            return Ok(Self {
+                entries: db.entries(roots, &filters)?,
+                store: Some(Store::Sqlite(db)),
+                roots: roots.to_vec(),
+                filters,
+                stored: 0,
+            });
+        }
+        let mut history = Self {
+            store: None,
+            roots: roots.to_vec(),
+            filters,
+            entries: Vec::new(),
+            stored: 0,
+        };
+        if let Some(path) = data_home().map(|home| home.join("codecounter").join(HISTORY_FILE)) {
+            let all = read_entries(&path);
+            history.stored = all.len();
+            history.keep_own(&all);
+            history.store = Some(Store::Jsonl(path));
+            if history.stored > HISTORY_LIMIT {
+                history.compact(all)?;
+            }
+        }
+        Ok(history)
+    }
+
+    /// Appends `scan`, returning the milestone it crossed since the previous entry, if any.
+    /// A scan nobody asked for (an automatic rescan) is only recorded when the count changed.
+    pub fn record(&mut self, scan: &ScanResult, milestones: &Milestones, requested: bool) -> io::Result<Option<u64>> {
+        let unchanged = self
+            .entries
+            .last()
+            .is_some_and(|previous| previous.lines == scan.lines && previous.files == scan.files);
+        if unchanged && !requested {
+            return Ok(None);
+        }
+        let milestone = self
+            .entries
+            .last()
+            .and_then(|previous| milestones.crossed(previous.lines, scan.lines));
+        let entry = HistoryEntry {
+            roots: self.roots.clone(),
+            filters: self.filters.clone(),
+            scanned_at: scan.scanned_at,
+            lines: scan.lines,
+            files: scan.files,
+            milestone,
+        };
+        match &self.store {
+            Some(Store::Jsonl(path)) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+                writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+                self.stored += 1;
+            }
+            #[cfg(feature = "sqlite")]
+            Some(Store::Sqlite(db)) => db.record(&entry, &scan.languages)?,
+            None => {}
+        }
+        self.entries.push(entry);
+        if self.stored > HISTORY_LIMIT
+            && let Some(Store::Jsonl(path)) = &self.store
+        {
+            let all = read_entries(path);
+            self.compact(all)?;
+        }
+        Ok(milestone)
+    }
+
+    fn keep_own(&mut self, all: &[HistoryEntry]) {
+        self.entries = all
+            .iter()
+            .filter(|entry| entry.roots == self.roots && entry.filters == self.filters)
+            .cloned()
+            .collect();
+    }
+
+    /// Rewrites `history.jsonl` with the last scan of each day of each set of roots, and the
+    /// scans that reached a milestone; if that's still too many, the oldest go.
+    fn compact(&mut self, all: Vec<HistoryEntry>) -> io::Result<()> {
+        let Some(Store::Jsonl(path)) = &self.store else {
+            return Ok(());
+        };
+        let mut days = HashSet::new();
+        let mut kept = all
+            .into_iter()
+            .rev()
+            .filter(|entry| {
+                let day = (entry.roots.clone(), entry.filters.clone(), entry.scanned_at.date_naive());
+                days.insert(day) || entry.milestone.is_some()
+            })
+            .collect::<Vec<_>>();
+        kept.reverse();
+        let excess = kept.len().saturating_sub(HISTORY_LIMIT / 2);
+        kept.drain(..excess);
+
+        let mut text = String::new();
+        for entry in &kept {
+            text.push_str(&serde_json::to_string(entry)?);
+            text.push('\n');
+        }
+        // Written aside and moved over the old file, so a crash can't leave it half written.
+        let partial = path.with_extension("jsonl.tmp");
+        fs::write(&partial, text)?;
+        fs::rename(&partial, path)?;
+        self.stored = kept.len();
+        self.keep_own(&kept);
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    pub fn last_milestone(&self) -> Option<&HistoryEntry> {
+        self.entries.iter().rev().find(|entry| entry.milestone.is_some())
+    }
+
+    /// Changes since earlier scans; `since_last_scan` is left out when the caller shows
+    /// the change since the previous scan itself.
+    pub fn deltas(&self, since_last_scan: bool) -> Vec<Delta> {
+        let Some((current, earlier)) = self.entries.split_last() else {
+            return Vec::new();
+        };
+        let mut deltas = Vec::new();
+        if since_last_scan && let Some(previous) = earlier.last() {
+            deltas.push(Delta {
+                change: current.lines as i64 - previous.lines as i64,
+                label: "since last scan".to_string(),
+            });
+        }
+
+        let today = current.scanned_at.date_naive();
+        if let Some(before_today) = earlier
+            .iter()
+            .rev()
+            .find(|entry| entry.scanned_at.date_naive() < today)
+        {
+            let date = before_today.scanned_at.date_naive();
+            let label = if today.checked_sub_days(Days::new(1)) == Some(date) {
+                "since yesterday".to_string()
+            } else {
+                format!("since {}", date.format("%Y-%m-%d"))
+            };
+            deltas.push(Delta {
+                change: current.lines as i64 - before_today.lines as i64,
+                label,
+            });
+        }
+        deltas
+    }
+}
+
+fn read_entries(path: &Path) -> Vec<HistoryEntry> {
+    let Ok(file) = fs::File::open(path) else {
+        return Vec::new();
+    };
+    BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<HistoryEntry>(&line).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn milestones(every: Option<u64>, at: &[u64]) -> Milestones {
+        Milestones { every, at: at.to_vec() }
+    }
+
+    #[test]
+    fn crosses_multiples() {
+        let every = milestones(Some(1000), &[]);
+        assert_eq!(every.crossed(950, 1010), Some(1000));
+        assert_eq!(every.crossed(999, 1000), Some(1000));
+        assert_eq!(every.crossed(1000, 1999), None);
+        // A jump past several is celebrated once, for the highest.
+        assert_eq!(every.crossed(900, 3500), Some(3000));
+        assert_eq!(every.crossed(0, 999), None);
+    }
+
+    #[test]
+    fn crosses_listed_milestones() {
+        let at = milestones(None, &[500, 10_000, 2500]);
+        assert_eq!(at.crossed(400, 600), Some(500));
+        assert_eq!(at.crossed(0, 20_000), Some(10_000));
+        assert_eq!(at.crossed(500, 2499), None);
+    }
+
+    #[test]
+    fn takes_the_higher_of_both() {
+        let both = milestones(Some(1000), &[1500]);
+        assert_eq!(both.crossed(900, 1600), Some(1500));
+        assert_eq!(both.crossed(1400, 2100), Some(2000));
+    }
+
+    #[test]
+    fn nothing_when_shrinking_or_unset() {
+        assert_eq!(milestones(Some(1000), &[1500]).crossed(2100, 900), None);
+        assert_eq!(milestones(Some(1000), &[]).crossed(1000, 1000), None);
+        assert_eq!(milestones(Some(0), &[]).crossed(0, 5000), None);
+        assert_eq!(milestones(None, &[]).crossed(0, 5000), None);
+    }
+}
@@ -0,0 +1,130 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection};
+
+use crate::history::HistoryEntry;
+use crate::scan::LanguageStat;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS scans (
+    id INTEGER PRIMARY KEY,
+    roots TEXT NOT NULL,
+    filters TEXT NOT NULL DEFAULT '',
+    scanned_at TEXT NOT NULL,
+    lines INTEGER NOT NULL,
+    files INTEGER NOT NULL,
+    milestone INTEGER
+);
+CREATE TABLE IF NOT EXISTS languages (
+    scan_id INTEGER NOT NULL REFERENCES scans (id),
+    name TEXT NOT NULL,
+    lines INTEGER NOT NULL,
+    files INTEGER NOT NULL,
+    bytes INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS scans_by_roots ON scans (roots, scanned_at);
+CREATE INDEX IF NOT EXISTS languages_by_scan ON languages (scan_id);
+";
+
+/// Scan history in an SQLite database, one `scans` row per scan and a `languages` row per
+/// language in it, so it can be queried with plain SQL.
+#[derive(Debug)]
+pub struct HistoryDb {
+    path: PathBuf,
+}
+
+impl HistoryDb {
+    pub fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf() }
+    }
+
+    /// Earlier scans of exactly `roots` with the same `filters`, oldest first. A database that
+    /// doesn't exist yet has none.
+    pub fn entries(&self, roots: &[PathBuf], filters: &str) -> io::Result<Vec<HistoryEntry>> {
+        if !self.path.is_file() {
+            return Ok(Vec::new());
+        }
+        let db = self.open()?;
+        let read = || -> rusqlite::Result<Vec<HistoryEntry>> {
+            let mut statement =
+                db.prepare("SELECT scanned_at, lines, files, milestone FROM scans WHERE roots = ?1 AND filters = ?2 ORDER BY id")?;
+            let rows = statement.query_map(params![roots_key(roots), filters], |row| {
+                Ok(HistoryEntry {
+                    roots: roots.to_vec(),
+                    filters: filters.to_string(),
+                    scanned_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(0)?)
+                        .map_err(|err| rusqlite::Error::FromSqlConversionFailure(0, Type::Text, Box::new(err)))?
+                        .with_timezone(&Local),
+                    lines: row.get(1)?,
+                    files: row.get(2)?,
+                    milestone: row.get(3)?,
+                })
+            })?;
+            rows.collect()
+        };
+        read().map_err(|err| self.error(err))
+    }
+
+    pub fn record(&self, entry: &HistoryEntry, languages: &[LanguageStat]) -> io::Result<()> {
+        if let Some(parent) = self.path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut db = self.open()?;
+        let mut write = || -> rusqlite::Result<()> {
+            let transaction = db.transaction()?;
+            transaction.execute(
+                "INSERT INTO scans (roots, filters, scanned_at, lines, files, milestone) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    roots_key(&entry.roots),
+                    entry.filters,
+                    // Millisecond precision keeps the timestamp in a form SQLite's date functions read.
+                    entry.scanned_at.format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string(),
+                    entry.lines,
+                    entry.files,
+                    entry.milestone
+                ],
+            )?;
+            let scan_id = transaction.last_insert_rowid();
+            {
+                let mut insert = transaction
+                    .prepare("INSERT INTO languages (scan_id, name, lines, files, bytes) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+                for language in languages {
+                    insert.execute(params![scan_id, language.name, language.lines, language.files, language.size.bytes])?;
+                }
+            }
+            transaction.commit()
+        };
+        write().map_err(|err| self.error(err))
+    }
+
+    /// The database, with its tables created or brought up to date.
+    fn open(&self) -> io::Result<Connection> {
+        let db = Connection::open(&self.path).map_err(|err| self.error(err))?;
+        let migrate = || -> rusqlite::Result<()> {
+            db.execute_batch(SCHEMA)?;
+            // Databases from before scans were told apart by their filters.
+            let has_filters = db
+                .prepare("SELECT 1 FROM pragma_table_info('scans') WHERE name = 'filters'")?
+                .exists([])?;
+            if !has_filters {
+                db.execute_batch("ALTER TABLE scans ADD COLUMN filters TEXT NOT NULL DEFAULT ''")?;
+            }
+            Ok(())
+        };
+        migrate().map_err(|err| self.error(err))?;
+        Ok(db)
+    }
+
+    fn error(&self, err: rusqlite::Error) -> io::Error {
+        io::Error::other(format!("{}: {}", self.path.display(), err))
+    }
+}
+
+/// The roots of a scan as one comparable column value.
+fn roots_key(roots: &[PathBuf]) -> String {
+    serde_json::to_string(roots).unwrap_or_default()
+}
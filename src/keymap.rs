@@ -0,0 +1,322 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+/// Something a key does in the TUI. The number keys, which jump straight to a view, and
+/// the mouse aren't rebindable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Rescan,
+    NextView,
+    PreviousView,
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Top,
+    Bottom,
+    Sort,
+    ReverseSort,
+    /// Opens the selected file in the editor in the file list, and rescans anywhere else.
+    Open,
+    EnterDirectory,
+    LeaveDirectory,
+    ExportCsv,
+    ExportMarkdown,
+    CopyText,
+    CopyJson,
+    SavePng,
+    SaveSvg,
+    Chart,
+    Estimate,
+    Errors,
+    Compact,
+    /// Switches the big number between the total, code only by default, and every category.
+    AllCategories,
+    Font,
+    Help,
+    /// Cancels a running scan, and quits when there's none.
+    Cancel,
+    Quit,
+}
+
+impl Action {
+    const ALL: &[Action] = &[
+        Action::Rescan,
+        Action::NextView,
+        Action::PreviousView,
+        Action::Up,
+        Action::Down,
+        Action::PageUp,
+        Action::PageDown,
+        Action::Top,
+        Action::Bottom,
+        Action::Sort,
+        Action::ReverseSort,
+        Action::Open,
+        Action::EnterDirectory,
+        Action::LeaveDirectory,
+        Action::ExportCsv,
+        Action::ExportMarkdown,
+        Action::CopyText,
+        Action::CopyJson,
+        Action::SavePng,
+        Action::SaveSvg,
+        Action::Chart,
+        Action::Estimate,
+        Action::Errors,
+        Action::Compact,
+        Action::AllCategories,
+        Action::Font,
+        Action::Help,
+        Action::Cancel,
+        Action::Quit,
+    ];
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Rescan => &["r", "R"],
+            Action::NextView => &["tab"],
+            Action::PreviousView => &["shift-tab"],
+            Action::Up => &["up", "k"],
+            Action::Down => &["down", "j"],
+            Action::PageUp => &["pageup"],
+            Action::PageDown => &["pagedown"],
+            Action::Top => &["home"],
+            Action::Bottom => &["end"],
+            Action::Sort => &["s"],
+            Action::ReverseSort => &["S"],
+            Action::Open => &["enter"],
+            Action::EnterDirectory => &["right", "l"],
+            Action::LeaveDirectory => &["left", "h", "backspace"],
+            Action::ExportCsv => &["x"],
+            Action::ExportMarkdown => &["e"],
+            Action::CopyText => &["y"],
+            Action::CopyJson => &["Y"],
+            Action::SavePng => &["i"],
+            Action::SaveSvg => &["I"],
+            Action::Chart => &["b"],
+            Action::Estimate => &["$"],
+            Action::Errors => &["!"],
+            Action::Compact => &["c"],
+            Action::AllCategories => &["a"],
+            Action::Font => &["f"],
+            Action::Help => &["?"],
+            Action::Cancel => &["esc"],
+            Action::Quit => &["q", "Q", "ctrl-c"],
+        }
+    }
+}
+
+/// The help overlay's rows: actions that go together, and what they do.
+const HELP: &[(&[Action], &str)] = &[
+    (&[Action::Rescan], "rescan now"),
+    (&[Action::NextView, Action::PreviousView], "next / previous view"),
+    (&[Action::Up, Action::Down], "select the row above / below"),
+    (&[Action::PageUp, Action::PageDown, Action::Top, Action::Bottom], "a page up / down, the first / last row"),
+    (&[Action::Sort, Action::ReverseSort], "change / reverse the sort (languages, files)"),
+    (&[Action::Open], "open the file in $EDITOR (files), otherwise rescan"),
+    (&[Action::EnterDirectory, Action::LeaveDirectory], "open a directory / go up (directories)"),
+    (&[Action::ExportCsv, Action::ExportMarkdown], "export CSV / Markdown"),
+    (&[Action::CopyText, Action::CopyJson], "copy the summary as text / JSON"),
+    (&[Action::SavePng, Action::SaveSvg], "save the summary as a PNG / SVG image"),
+    (&[Action::Chart], "languages chart as bars, a donut or hidden"),
+    (&[Action::Estimate], "show or hide the COCOMO estimate"),
+    (&[Action::Errors], "show or hide the unreadable paths"),
+    (&[Action::Compact], "big number in full or compact (1.24M)"),
+    (&[Action::AllCategories], "big number of code only or of every category"),
+    (&[Action::Font], "next font for the big number"),
+    (&[Action::Help], "this help"),
+    (&[Action::Cancel], "cancel a running scan, otherwise quit"),
+    (&[Action::Quit], "quit"),
+];
+
+/// A key with the modifiers that matter. Shift is left out: it's already in the case of a
+/// letter, and Shift-Tab comes as a key of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    code: KeyCode,
+    ctrl: bool,
+    alt: bool,
+}
+
+/// Names for the keys that aren't a character, as written in the config.
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("shift-tab", KeyCode::BackTab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("space", KeyCode::Char(' ')),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+];
+
+impl Key {
+    pub fn from_event(event: KeyEvent) -> Self {
+        Self {
+            code: event.code,
+            ctrl: event.modifiers.contains(KeyModifiers::CONTROL),
+            alt: event.modifiers.contains(KeyModifiers::ALT),
+        }
+    }
+
+    /// Parses a key as written in the config: a character (`q`, `Q`, `?`), a name such as
+    /// `enter`, `pagedown` or `f5`, with any of `ctrl-` and `alt-` in front.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut key = Self {
+            code: KeyCode::Null,
+            ctrl: false,
+            alt: false,
+        };
+        let mut rest = text;
+        loop {
+            if let Some(after) = strip_prefix_ignore_case(rest, "ctrl-") {
+                key.ctrl = true;
+                rest = after;
+            } else if let Some(after) = strip_prefix_ignore_case(rest, "alt-") {
+                key.alt = true;
+                rest = after;
+            } else {
+                break;
+            }
+        }
+        let mut chars = rest.chars();
+        key.code = match (chars.next(), chars.next()) {
+            (Some(ch), None) => KeyCode::Char(ch),
+            _ => {
+                let name = rest.to_lowercase();
+                let function = name.strip_prefix('f').and_then(|number| number.parse::<u8>().ok());
+                match NAMED_KEYS.iter().find(|(known, _)| *known == name) {
+                    Some((_, code)) => *code,
+                    None => match function {
+                        Some(number @ 1..=24) => KeyCode::F(number),
+                        _ => return Err(format!("unknown key `{}`", text)),
+                    },
+                }
+            }
+        };
+        Ok(key)
+    }
+
+    /// The key as the help overlay shows it.
+    fn label(self) -> String {
+        let name = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(ch) => ch.to_string(),
+            KeyCode::F(number) => format!("F{}", number),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::BackTab => "Shift-Tab".to_string(),
+            code => {
+                let name = NAMED_KEYS.iter().find(|(_, known)| *known == code).map_or("?", |(name, _)| name);
+                let mut chars = name.chars();
+                chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+            }
+        };
+        let mut label = String::new();
+        if self.ctrl {
+            label.push_str("Ctrl-");
+        }
+        if self.alt {
+            label.push_str("Alt-");
+        }
+        label + &name
+    }
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    text.get(..prefix.len())
+        .filter(|start| start.eq_ignore_ascii_case(prefix))
+        .map(|_| &text[prefix.len()..])
+}
+
+/// Which keys do what in the TUI: the defaults, with the `[keys]` tables of the config files
+/// applied over them.
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .iter()
+            .map(|action| {
+                let keys = action
+                    .default_keys()
+                    .iter()
+                    .map(|key| Key::parse(key).expect("default keys parse"))
+                    .collect();
+                (*action, keys)
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Binds `keys` to `action` in place of its current keys. A key taken from another action
+    /// stops doing that, so a key is never bound twice.
+    pub fn bind(&mut self, action: Action, keys: &[String]) -> Result<(), String> {
+        let keys = keys.iter().map(|key| Key::parse(key)).collect::<Result<Vec<_>, _>>()?;
+        for (bound, bound_keys) in &mut self.bindings {
+            if *bound == action {
+                bound_keys.clone_from(&keys);
+            } else {
+                bound_keys.retain(|key| !keys.contains(key));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn action(&self, event: KeyEvent) -> Option<Action> {
+        let key = Key::from_event(event);
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    fn keys(&self, action: Action) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    /// The first key bound to `action`, as the help shows it.
+    pub fn first_key(&self, action: Action) -> Option<String> {
+        self.keys(action).first().map(|key| key.label())
+    }
+
+    /// The help overlay's rows with the keys bound now: an action's keys apart by spaces,
+    /// and the actions on one row by slashes.
+    pub fn help(&self) -> Vec<(String, &'static str)> {
+        HELP.iter()
+            .map(|(actions, description)| {
+                let keys = actions
+                    .iter()
+                    .map(|action| match self.keys(*action) {
+                        [] => "(none)".to_string(),
+                        keys => keys.iter().map(|key| key.label()).collect::<Vec<_>>().join(" "),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" / ");
+                (keys, *description)
+            })
+            .collect()
+    }
+}
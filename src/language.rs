@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+const BUILTIN_LANGUAGES: &str = include_str!("languages.toml");
+
+/// The size past which a file in a language prone to dumps is taken for data, by default.
+pub const DEFAULT_DUMP_SIZE: u64 = 1024 * 1024;
+
+/// What a file is for. Only code counts towards the headline total unless the config gives
+/// the other categories a weight.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    #[default]
+    Code,
+    Docs,
+    Config,
+    Data,
+}
+
+impl Category {
+    pub const ALL: [Category; 4] = [Category::Code, Category::Docs, Category::Config, Category::Data];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Code => "code",
+            Category::Docs => "docs",
+            Category::Config => "config",
+            Category::Data => "data",
+        }
+    }
+
+    /// How much a line of this category adds to the headline total by default.
+    pub fn default_weight(self) -> f64 {
+        match self {
+            Category::Code => 1.0,
+            Category::Docs | Category::Config | Category::Data => 0.0,
+        }
+    }
+}
+
+/// Which category each file goes in and what each category weighs in the headline total:
+/// the languages' own categories and the default weights, with the config's `[categories]`
+/// tables applied over them.
+#[derive(Debug, Clone)]
+pub struct Categories {
+    /// Extensions moved into a category by the config, over their language's.
+    extensions: BTreeMap<String, Category>,
+    weights: BTreeMap<Category, f64>,
+    /// Files bigger than this in a language with `dumps` are data.
+    dump_size: u64,
+}
+
+impl Default for Categories {
+    fn default() -> Self {
+        Self {
+            extensions: BTreeMap::new(),
+            weights: Category::ALL.iter().map(|category| (*category, category.default_weight())).collect(),
+            dump_size: DEFAULT_DUMP_SIZE,
+        }
+    }
+}
+
+impl Categories {
+    pub fn assign(&mut self, ext: String, category: Category) {
+        self.extensions.insert(ext, category);
+    }
+
+    pub fn set_weight(&mut self, category: Category, weight: f64) {
+        self.weights.insert(category, weight);
+    }
+
+    pub fn set_dump_size(&mut self, size: u64) {
+        self.dump_size = size;
+    }
+
+    pub fn weight(&self, category: Category) -> f64 {
+        self.weights.get(&category).copied().unwrap_or_else(|| category.default_weight())
+    }
+
+    /// What `lines` of `category` add to the headline total.
+    pub fn weighted(&self, category: Category, lines: u64) -> u64 {
+        (lines as f64 * self.weight(category)).round() as u64
+    }
+
+    /// The category of a file in `language`, found at `path` and `bytes` long: the one the
+    /// config gives its extension, else data for a dump, else its language's.
+    pub fn of(&self, path: &Path, language: &str, bytes: u64, languages: &LanguageDb) -> Category {
+        let ext = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+        if let Some(category) = ext.and_then(|ext| self.extensions.get(&ext).copied()) {
+            return category;
+        }
+        match languages.by_name(language) {
+            Some(language) if language.dumps && bytes > self.dump_size => Category::Data,
+            Some(language) => language.category,
+            None => Category::default(),
+        }
+    }
+}
+
+/// How a language's nesting shows in its source, for `--complexity`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Nesting {
+    #[default]
+    Braces,
+    Indent,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Language {
+    pub name: String,
+    pub category: Category,
+    pub extensions: Vec<String>,
+    /// Whole file names such as `Makefile`, for files whose extension says nothing.
+    pub filenames: Vec<String>,
+    /// Interpreters named on a `#!` line, without any version suffix.
+    pub interpreters: Vec<String>,
+    pub line_comment: Vec<String>,
+    pub block_comment: Vec<[String; 2]>,
+    /// Words and operators that each add a path through the code. `--complexity` only
+    /// measures languages that list some.
+    pub branch_keywords: Vec<String>,
+    pub nesting: Nesting,
+    /// Big files in the language are likelier a dump or a fixture than written by hand, so
+    /// past the dump size they're data whatever the language's category.
+    pub dumps: bool,
+}
+
+impl Language {
+    /// The first extension listed for a language, used to run extensionless files
+    /// through the same `--ext`/`--no-ext` filters as everything else.
+    pub fn extension(&self) -> Option<&str> {
+        self.extensions.first().map(String::as_str)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct LanguageFile {
+    language: Vec<Language>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LanguageDb {
+    languages: Vec<Language>,
+}
+
+impl Default for LanguageDb {
+    fn default() -> Self {
+        let file: LanguageFile = toml::from_str(BUILTIN_LANGUAGES).expect("built-in language table is valid");
+        Self {
+            languages: file.language,
+        }
+    }
+}
+
+impl LanguageDb {
+    /// Merges a user-supplied TOML (or `.json`) file: entries replace the built-in
+    /// language of the same name, anything else is added.
+    pub fn load_overrides(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let raw = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let file: LanguageFile = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(&raw).map_err(|err| format!("{}: {}", path.display(), err))?
+        } else {
+            toml::from_str(&raw).map_err(|err| format!("{}: {}", path.display(), err))?
+        };
+        for mut language in file.language {
+            if language.name.is_empty() {
+                return Err(format!("{}: language without a name", path.display()).into());
+            }
+            for ext in &mut language.extensions {
+                *ext = ext.trim_start_matches('.').to_lowercase();
+            }
+            match self.languages.iter_mut().find(|known| known.name == language.name) {
+                Some(known) => *known = language,
+                None => self.languages.push(language),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Language> {
+        self.languages.iter().find(|language| language.name == name)
+    }
+
+    pub fn by_extension(&self, ext: &str) -> Option<&Language> {
+        self.languages
+            .iter()
+            .find(|language| language.extensions.iter().any(|known| known == ext))
+    }
+
+    pub fn by_filename(&self, path: &Path) -> Option<&Language> {
+        let name = path.file_name()?.to_str()?;
+        self.languages
+            .iter()
+            .find(|language| language.filenames.iter().any(|known| known == name))
+    }
+
+    /// Reads the interpreter from a `#!` line, looking through `/usr/bin/env` and its flags.
+    pub fn by_shebang(&self, head: &[u8]) -> Option<&Language> {
+        let line = head.strip_prefix(b"#!")?;
+        let line = line.split(|&byte| byte == b'\n').next()?;
+        let line = std::str::from_utf8(line).ok()?;
+        let mut words = line.split_whitespace().map(|word| word.rsplit('/').next().unwrap_or(word));
+        let mut interpreter = words.next()?;
+        if interpreter == "env" {
+            interpreter = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+        }
+        let interpreter = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        self.languages
+            .iter()
+            .find(|language| language.interpreters.iter().any(|known| known == interpreter))
+    }
+
+    pub fn language_name(&self, path: &Path) -> String {
+        if let Some(language) = self.by_filename(path) {
+            return language.name.clone();
+        }
+        let ext = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        self.by_extension(&ext)
+            .map(|language| language.name.clone())
+            .unwrap_or_else(|| format!(".{}", ext))
+    }
+}
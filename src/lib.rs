@@ -0,0 +1,70 @@
+//! The scanner behind the `codecounter` command, for programs that count lines with
+//! analyzers of their own: implement [`FileAnalyzer`], [`register`](Analyzers::register) it
+//! on the settings' analyzers, and [`scan`](fn@scan).
+//!
+//! ```no_run
+//! use std::path::{Path, PathBuf};
+//! use std::sync::atomic::AtomicBool;
+//! use std::sync::Arc;
+//!
+//! use clap::Parser;
+//! use codecounter::cli::Cli;
+//! use codecounter::{scan, FileAnalyzer, LineCache, Metrics, Settings};
+//!
+//! struct Unsafe;
+//!
+//! impl FileAnalyzer for Unsafe {
+//!     fn name(&self) -> &str {
+//!         "unsafe"
+//!     }
+//!
+//!     fn analyze(&self, path: &Path, _language: &str) -> Result<Metrics, String> {
+//!         let text = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+//!         Ok(Metrics::from([("blocks".to_string(), text.matches("unsafe {").count() as u64)]))
+//!     }
+//! }
+//!
+//! let dir = PathBuf::from(".");
+//! let mut settings = Settings::resolve(&Cli::parse_from(["codecounter"]), &dir)?;
+//! settings.analyzers.register(Arc::new(Unsafe));
+//! let result = scan(&[dir.clone()], &settings, &mut LineCache::new(&dir, &settings), &|_| {}, &AtomicBool::new(false))?;
+//! println!("{} lines, {:?}", result.lines, result.metrics);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! The modules are the binary's own and change with it.
+
+pub mod analyzer;
+pub mod archive;
+pub mod ascii;
+pub mod badge;
+pub mod cache;
+pub mod ccignore;
+pub mod cli;
+pub mod cocomo;
+pub mod complexity;
+pub mod config;
+pub mod dashboard;
+pub mod encoding;
+pub mod export;
+pub mod font;
+pub mod format;
+pub mod git;
+pub mod history;
+#[cfg(feature = "sqlite")]
+pub mod history_db;
+pub mod keymap;
+pub mod language;
+pub mod license;
+pub mod logging;
+pub mod notebook;
+pub mod package;
+pub mod scan;
+pub mod theme;
+pub mod todo;
+pub mod workspace;
+
+pub use analyzer::{Analyzers, FileAnalyzer, Metrics};
+pub use cache::LineCache;
+pub use config::Settings;
+pub use scan::{scan, ScanResult};
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+use crate::scan::leading_comments;
+
+/// Only this many lines at the top of a file are searched for a license header.
+const HEADER_LINES: usize = 30;
+const SPDX_TAG: &str = "SPDX-License-Identifier:";
+/// Phrases from the standard headers and notices, with the SPDX name each stands for.
+/// More specific phrases come first.
+const LICENSE_PHRASES: &[(&str, &str)] = &[
+    ("Licensed under the Apache License, Version 2.0", "Apache-2.0"),
+    ("Permission is hereby granted, free of charge", "MIT"),
+    ("GNU Affero General Public License", "AGPL"),
+    ("GNU Lesser General Public License", "LGPL"),
+    ("GNU Library General Public License", "LGPL"),
+    ("GNU General Public License", "GPL"),
+    ("Mozilla Public License", "MPL-2.0"),
+    ("Eclipse Public License", "EPL"),
+    ("Redistribution and use in source and binary forms", "BSD"),
+    ("This is free and unencumbered software released into the public domain", "Unlicense"),
+    ("Permission to use, copy, modify, and/or distribute this software for any purpose", "ISC"),
+];
+
+/// Files carrying one license, and their lines.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LicenseStat {
+    pub name: String,
+    pub files: u64,
+    pub lines: u64,
+}
+
+/// The license named in the comment block at the top of a file: its
+/// `SPDX-License-Identifier` expression, or the license whose standard header it quotes.
+pub fn find_license(head: &[u8]) -> Option<String> {
+    let lines = leading_comments(head)
+        .into_iter()
+        .take(HEADER_LINES)
+        .map(String::from_utf8_lossy)
+        .collect::<Vec<_>>();
+    for line in &lines {
+        if let Some((_, expression)) = line.split_once(SPDX_TAG) {
+            // Whatever closes the comment the tag sits in isn't part of the expression.
+            let expression = expression.trim().trim_end_matches("*/").trim_end_matches("-->").trim();
+            if is_spdx_expression(expression) {
+                return Some(expression.to_string());
+            }
+        }
+    }
+    // Headers wrap anywhere, so phrases are looked for in the lines joined back up.
+    let header = lines
+        .iter()
+        .map(|line| line.trim_start_matches(|ch: char| ch.is_whitespace() || "/*#;-!'%".contains(ch)).trim_end())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let header = header.split_whitespace().collect::<Vec<_>>().join(" ");
+    LICENSE_PHRASES
+        .iter()
+        .find(|(phrase, _)| header.contains(phrase))
+        .map(|(_, name)| name.to_string())
+}
+
+/// Whether `expression` is one in SPDX syntax: license ids joined by `AND`, `OR` and `WITH`,
+/// grouped with parentheses.
+fn is_spdx_expression(expression: &str) -> bool {
+    let spaced = expression.replace('(', " ( ").replace(')', " ) ");
+    let mut depth = 0_u32;
+    let mut want_id = true;
+    for token in spaced.split_whitespace() {
+        match token {
+            "(" if want_id => depth += 1,
+            ")" if !want_id && depth > 0 => depth -= 1,
+            "AND" | "OR" | "WITH" if !want_id => want_id = true,
+            "(" | ")" | "AND" | "OR" | "WITH" => return false,
+            id if want_id && id.chars().all(|ch| ch.is_ascii_alphanumeric() || ".+-".contains(ch)) => {
+                want_id = false;
+            }
+            _ => return false,
+        }
+    }
+    depth == 0 && !want_id
+}
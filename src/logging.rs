@@ -0,0 +1,39 @@
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
+
+use clap::ValueEnum;
+use tracing::Level;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::Layer;
+
+/// How the `--log` file is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// One line per event: timestamp, level, module, message and its fields as `key=value`.
+    #[default]
+    Text,
+    /// One JSON object per line, with the fields under `fields`.
+    Json,
+}
+
+/// Sends `tracing` events to `path`, appending to what earlier runs wrote there.
+pub fn init(path: &Path, format: LogFormat) -> Result<(), Box<dyn Error>> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+    // Dependencies may emit events too; only our own are wanted.
+    let ours = Targets::new().with_target(env!("CARGO_CRATE_NAME"), Level::DEBUG);
+    let layer = tracing_subscriber::fmt::layer().with_writer(Mutex::new(file)).with_ansi(false);
+    let layer = match format {
+        LogFormat::Text => layer.with_filter(ours).boxed(),
+        LogFormat::Json => layer.json().with_filter(ours).boxed(),
+    };
+    tracing_subscriber::registry().with(layer).try_init()?;
+    Ok(())
+}
@@ -1,353 +1,1113 @@
+mod blame;
+mod bench;
+mod branches;
+mod clipboard;
+mod check;
+mod daemon;
+mod diff;
+mod serve;
+mod session;
+mod snapshot;
+mod tempdir;
+mod trend;
+mod ui;
+
+use std::cmp::Ordering;
 use std::error::Error;
-use std::fs::File;
-use std::io::{self, Read};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use chrono::{DateTime, Local};
-use crossterm::event::{self, Event, KeyCode};
+use clap::Parser;
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind,
+};
 use crossterm::execute;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
-use ratatui::layout::{Alignment, Rect};
-use ratatui::style::{Color, Style};
-use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Position, Rect};
+use ratatui::widgets::TableState;
 use ratatui::Terminal;
-use ratatui::{backend::CrosstermBackend, prelude::Frame};
-use walkdir::WalkDir;
-
-const DIGIT_HEIGHT: usize = 5;
-const DIGITS: [[&str; DIGIT_HEIGHT]; 10] = [
-    [" ███ ", "█   █", "█   █", "█   █", " ███ "],
-    ["  █  ", " ██  ", "  █  ", "  █  ", " ███ "],
-    [" ███ ", "█   █", "   █ ", "  █  ", "█████"],
-    [" ███ ", "█   █", "  ██ ", "█   █", " ███ "],
-    ["█   █", "█   █", "█████", "    █", "    █"],
-    ["█████", "█    ", "████ ", "    █", "████ "],
-    [" ███ ", "█    ", "████ ", "█   █", " ███ "],
-    ["█████", "    █", "   █ ", "  █  ", "  █  "],
-    [" ███ ", "█   █", " ███ ", "█   █", " ███ "],
-    [" ███ ", "█   █", " ████", "    █", " ███ "],
-];
-const COMMA: [&str; DIGIT_HEIGHT] = ["   ", "   ", "   ", " █ ", "█  "];
-const SCALE_X: usize = 4;
-const SCALE_Y: usize = 2;
-const CODE_EXTENSIONS: &[&str] = &[
-    "rs", "py", "pyw", "pyi", "ipynb", "js", "mjs", "cjs", "jsm", "ts", "mts", "cts", "jsx", "tsx",
-    "java", "kt", "kts", "groovy", "gradle", "gvy", "gy", "gsh", "scala", "sc", "sbt", "swift",
-    "c", "h", "cc", "cxx", "cpp", "hpp", "hh", "hxx", "inl", "ipp", "tpp", "inc", "idl", "d", "di",
-    "m", "mm", "go", "rs", "zig", "nim", "nimble", "v", "cr", "hs", "lhs", "ml", "mli", "mll",
-    "mly", "re", "rei", "fs", "fsi", "fsx", "fsproj", "cs", "csx", "vb", "vbs", "bas", "pas",
-    "rb", "erb", "rake", "gemspec", "php", "phtml", "phpt", "twig", "blade", "pl", "pm", "r", "rmd",
-    "jl", "dart", "elm", "clj", "cljs", "cljc", "edn", "ex", "exs", "erl", "hrl", "lua", "nu",
-    "sh", "bash", "zsh", "fish", "ps1", "psm1", "psd1", "bat", "cmd", "asm", "s", "sql", "psql",
-    "pgsql", "mysql", "sqlite", "sqlite3", "ddl", "dml", "proto", "thrift", "avsc", "avdl",
-    "graphql", "gql", "prisma", "tf", "tfvars", "hcl", "cue", "rego",
-    "html", "htm", "xhtml", "xml", "xsd", "xsl", "xslt",
-    "css", "scss", "sass", "less", "styl", "stylus", "postcss",
-    "md", "mdx", "markdown", "rst", "adoc", "asciidoc", "org",
-    "tex", "latex", "sty", "cls", "bib",
-    "toml", "yaml", "yml", "json", "jsonc", "json5", "ini", "cfg", "conf", "properties", "env",
-    "make", "mk", "cmake",
-    "vue", "svelte", "astro",
-];
-
-#[derive(Debug)]
-struct ScanResult {
-    lines: u64,
-    files: u64,
-    dir: PathBuf,
-    scanned_at: DateTime<Local>,
-}
+use serde::{Deserialize, Serialize};
+
+use codecounter::cache::LineCache;
+use codecounter::cli::{Cli, Command};
+use codecounter::config::Settings;
+use codecounter::dashboard::Dashboard;
+use codecounter::export::{default_export_path, export, export_to_file, write_summary_json, ExportFormat};
+use codecounter::history::History;
+use codecounter::keymap::Action;
+use codecounter::scan::{scan, spawn_scan, DirNode, Progress, ScanMessage, ScanResult};
+use codecounter::{format, git, logging};
+
+use crate::blame::AuthorLines;
+use crate::session::Session;
+use crate::snapshot::{default_image_path, ImageFormat};
+use crate::tempdir::TempDir;
+use crate::trend::{line_trend, TrendPoint};
+use crate::ui::draw_ui;
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    if let Some(path) = &cli.log {
+        logging::init(path, cli.log_format)?;
+        tracing::info!(args = ?std::env::args().skip(1).collect::<Vec<_>>(), "started");
+    }
+    let result = run(cli);
+    if let Err(err) = &result {
+        tracing::error!(error = %err, "exiting");
+    }
+    result
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let cwd = std::env::current_dir()?;
+    if let Some(Command::Diff(args)) = &cli.command {
+        let settings = Settings::resolve(&cli, &cwd)?;
+        format::set_number_format(settings.numbers.clone());
+        return diff::run(args, &settings, cli.plain);
+    }
+    if let Some(Command::Branches(args)) = &cli.command {
+        let dir = args.dir.as_ref().unwrap_or(&cwd);
+        let dir = fs::canonicalize(dir).map_err(|err| format!("{}: {}", dir.display(), err))?;
+        let settings = Settings::resolve(&cli, &dir)?;
+        format::set_number_format(settings.numbers.clone());
+        return branches::run(args, &dir, &settings, cli.plain);
+    }
+
+    if cli.daemon {
+        return daemon::run();
+    }
+
+    let paths = match &cli.command {
+        Some(Command::Serve(args)) => &args.paths,
+        Some(Command::Check(args)) => &args.paths,
+        Some(Command::Bench(args)) => &args.paths,
+        _ => &cli.paths,
+    };
+    // Temporary clones of repository URLs, deleted when this goes out of scope.
+    let mut scratch = Vec::new();
+    let mut roots = Vec::new();
+    for path in paths {
+        let root = if let Some(url) = path.to_str().filter(|arg| git::is_remote_url(arg)) {
+            let clone = TempDir::new("clone")?;
+            let dest = clone.path().join(git::repo_name(url));
+            eprintln!("Cloning {}…", url);
+            tracing::info!(url, dest = %dest.display(), "cloning");
+            git::shallow_clone(url, &dest)?;
+            scratch.push(clone);
+            dest
+        } else {
+            path.clone()
+        };
+        roots.push(fs::canonicalize(&root).map_err(|err| format!("{}: {}", path.display(), err))?);
+    }
+    if roots.is_empty() {
+        roots.push(cwd.clone());
+    }
+    // Archives are read in place, so their settings come from around the current directory.
+    let base = match roots.as_slice() {
+        [root] if root.is_dir() => root.clone(),
+        _ => cwd.clone(),
+    };
+    let mut settings = Settings::resolve(&cli, &base)?;
+    format::set_number_format(settings.numbers.clone());
+    if let Some(list) = &cli.files_from {
+        settings.files_from = Some(read_file_list(list, &cwd)?);
+        // The daemon can't see the list, and stdin can only be read once anyway.
+        settings.daemon = false;
+        settings.session = false;
+    }
+    if !scratch.is_empty() {
+        // A scratch copy's path is new every time, so its history would never be seen again.
+        settings.history = false;
+        settings.session = false;
+    }
+    if let Some(reference) = &settings.since {
+        for root in &roots {
+            git::verify_ref(root, reference)?;
+        }
+    }
+
+    if let Some(Command::Serve(args)) = &cli.command {
+        return serve::run(&cli, args, roots, &base, settings);
+    }
+    if let Some(Command::Bench(args)) = &cli.command {
+        return bench::run(args, &roots, &settings);
+    }
+    if let Some(Command::Check(args)) = &cli.command {
+        let passed = check::run(args, &roots, &base, &settings)?;
+        // `exit` skips destructors, so the scratch copies go first.
+        drop(scratch);
+        if !passed {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let mut trend = Vec::new();
+    if let Some(points) = cli.git_history {
+        let [root] = roots.as_slice() else {
+            return Err("--history works on a single directory".into());
+        };
+        trend = line_trend(root, &settings, points, cli.tags)?;
+    }
+    let mut authors = Vec::new();
+    if cli.authors {
+        let [root] = roots.as_slice() else {
+            return Err("--authors works on a single directory".into());
+        };
+        authors = blame::ownership(root, &settings)?;
+    }
+    if (cli.git_history.is_some() || cli.authors) && (cli.plain || !io::stdout().is_terminal()) {
+        let mut out = io::stdout().lock();
+        trend::write_text(&trend, &mut out)?;
+        if !trend.is_empty() && !authors.is_empty() {
+            writeln!(out)?;
+        }
+        blame::write_text(&authors, &mut out)?;
+        return Ok(());
+    }
+
+    if let Some(path) = &cli.image {
+        let scan = scan_once(&roots, &base, &settings)?;
+        let mut app = App::build(settings, &base, roots, trend, authors, None)?;
+        app.scan = Some(scan);
+        app.sort_tables();
+        snapshot::export_image(&mut app, ImageFormat::of(path), cli.image_size, path)
+            .map_err(|err| format!("{}: {}", path.display(), err))?;
+        return Ok(());
+    }
+
+    let target = match cli.export_target() {
+        None if !io::stdout().is_terminal() => Some((ExportFormat::Text, None)),
+        target => target,
+    };
+    if let Some((format, out)) = target {
+        let scan = scan_once(&roots, &base, &settings)?;
+        match &out {
+            Some(path) => export_to_file(format, &scan, path)?,
+            None => export(format, &scan, &mut io::stdout().lock())?,
+        }
+        return Ok(());
+    }
+
+    let dashboard = (!settings.repos.is_empty()).then(|| Dashboard::new(&cli, &settings.repos));
+    let mut app = App::new(settings, &base, roots, trend, authors, dashboard)?;
+    with_terminal(|terminal| app.run(terminal))
+}
+
+/// One scan for the non-interactive outputs, by the daemon when one is running.
+fn scan_once(roots: &[PathBuf], base: &Path, settings: &Settings) -> Result<ScanResult, Box<dyn Error>> {
+    if let Some(result) = settings.daemon.then(|| daemon::request(roots, base)).flatten() {
+        tracing::info!("scanned by the daemon");
+        return result;
+    }
+    let mut cache = LineCache::new(base, settings);
+    scan(roots, settings, &mut cache, &|_| {}, &AtomicBool::new(false))
+}
+
+/// Canonical paths of the files named in `list` (or stdin for `-`), one per line and relative
+/// to `cwd`. Names that don't exist are left out.
+fn read_file_list(list: &Path, cwd: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let text = if list == Path::new("-") {
+        io::read_to_string(io::stdin())?
+    } else {
+        fs::read_to_string(list).map_err(|err| format!("{}: {}", list.display(), err))?
+    };
+    Ok(text
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| fs::canonicalize(cwd.join(line)).ok())
+        .filter(|path| path.is_file())
+        .collect())
+}
+
+type Term = Terminal<CrosstermBackend<io::Stdout>>;
+
+/// Runs `f` on the alternate screen in raw mode, restoring the terminal afterwards.
+fn with_terminal<F>(f: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnOnce(&mut Term) -> Result<(), Box<dyn Error>>,
+{
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new()?;
-    let res = app.run(&mut terminal);
+    let res = f(&mut terminal);
 
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
+    suspend_terminal(&mut terminal)?;
 
     res
 }
 
+fn suspend_terminal(terminal: &mut Term) -> io::Result<()> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    terminal.show_cursor()
+}
+
+fn resume_terminal(terminal: &mut Term) -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()
+}
+
+/// Hands the terminal to `$VISUAL` / `$EDITOR` (falling back to `vi`) until it exits.
+fn open_in_editor(terminal: &mut Term, path: &Path) -> Result<(), Box<dyn Error>> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    suspend_terminal(terminal)?;
+    let status = std::process::Command::new(program).args(words).arg(path).status();
+    resume_terminal(terminal)?;
+
+    let status = status.map_err(|err| format!("{}: {}", program, err))?;
+    if !status.success() {
+        return Err(format!("{} exited with {}", program, status).into());
+    }
+    Ok(())
+}
+
+const STATUS_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the big number takes to roll from the previous total to a new one.
+const ODOMETER_TIME: Duration = Duration::from_secs(1);
+/// Redraw rate while something is animating, instead of waiting for input.
+const ANIMATION_TICK: Duration = Duration::from_millis(40);
+const IDLE_TICK: Duration = Duration::from_millis(200);
+/// How long the milestone banner stays up.
+const CELEBRATION_TIME: Duration = Duration::from_secs(6);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum View {
+    Summary,
+    Languages,
+    Files,
+    Directories,
+    /// The packages of a monorepo, by the `package.json`, `go.mod` or `pyproject.toml` at their top.
+    Packages,
+    Todos,
+    LongLines,
+    Duplicates,
+    Trend,
+    Authors,
+    /// Every repository in the config's `[[repos]]`, for a wall monitor.
+    Repos,
+}
+
+impl View {
+    const ALL: &[View] = &[
+        View::Summary,
+        View::Languages,
+        View::Files,
+        View::Directories,
+        View::Packages,
+        View::Todos,
+        View::LongLines,
+        View::Duplicates,
+        View::Trend,
+        View::Authors,
+        View::Repos,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            View::Summary => "Summary",
+            View::Languages => "Languages",
+            View::Files => "Files",
+            View::Directories => "Directories",
+            View::Packages => "Packages",
+            View::Todos => "Tech debt",
+            View::LongLines => "Long lines",
+            View::Duplicates => "Duplicates",
+            View::Trend => "History",
+            View::Authors => "Authors",
+            View::Repos => "Repos",
+        }
+    }
+}
+
+/// How the summary shows each language's share; `b` cycles through them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum LanguageChart {
+    Bars,
+    Donut,
+    Hidden,
+}
+
+impl LanguageChart {
+    fn next(self) -> Self {
+        match self {
+            LanguageChart::Bars => LanguageChart::Donut,
+            LanguageChart::Donut => LanguageChart::Hidden,
+            LanguageChart::Hidden => LanguageChart::Bars,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            LanguageChart::Bars => "bars",
+            LanguageChart::Donut => "donut",
+            LanguageChart::Hidden => "off",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum SortKey {
+    Lines,
+    Files,
+    Bytes,
+    Words,
+    Name,
+}
+
+/// What a click on a region recorded while drawing does.
+#[derive(Debug, Clone, Copy)]
+enum Hit {
+    View(View),
+    Sort(SortKey),
+    Row(usize),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Sort {
+    key: SortKey,
+    descending: bool,
+}
+
+impl Sort {
+    const FILE_KEYS: &[SortKey] = &[SortKey::Lines, SortKey::Name];
+    const LANGUAGE_KEYS: &[SortKey] = &[SortKey::Lines, SortKey::Files, SortKey::Bytes, SortKey::Words, SortKey::Name];
+
+    fn by_lines() -> Self {
+        Self {
+            key: SortKey::Lines,
+            descending: true,
+        }
+    }
+
+    fn cycle(&mut self, keys: &[SortKey]) {
+        let index = keys.iter().position(|key| *key == self.key).unwrap_or(0);
+        self.key = keys[(index + 1) % keys.len()];
+        self.descending = self.key != SortKey::Name;
+    }
+
+    fn apply(self, ordering: Ordering) -> Ordering {
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+}
+
 struct App {
-    scan: ScanResult,
+    settings: Arc<Settings>,
+    base: PathBuf,
+    roots: Vec<PathBuf>,
+    cache: Arc<Mutex<LineCache>>,
+    history: History,
+    scan: Option<ScanResult>,
     last_scan: Instant,
+    pending: Option<Receiver<ScanMessage>>,
+    /// The latest progress report from the running scan, and when it started.
+    progress: Option<(Progress, Instant)>,
+    /// Set to stop the running scan early.
+    cancel: Arc<AtomicBool>,
+    view: View,
+    file_sort: Sort,
+    file_table: TableState,
+    language_sort: Sort,
+    language_table: TableState,
+    dir_path: Vec<String>,
+    dir_table: TableState,
+    todo_table: TableState,
+    long_line_table: TableState,
+    duplicate_table: TableState,
+    trend: Vec<TrendPoint>,
+    /// The `--authors` leaderboard.
+    authors: Vec<AuthorLines>,
+    author_table: TableState,
+    package_table: TableState,
+    /// The `[[repos]]` of the config, scanned in the background for the dashboard view.
+    dashboard: Option<Dashboard>,
+    repo_table: TableState,
+    page: usize,
+    language_chart: LanguageChart,
+    show_estimate: bool,
+    /// The panel listing paths the last scan couldn't read.
+    show_errors: bool,
+    compact_number: bool,
+    /// The big number counts docs, config and data along with code.
+    all_categories: bool,
+    /// Index into the settings' fonts; `f` cycles through them.
+    font: usize,
+    show_help: bool,
+    /// A file picked in the file list, opened in the editor by the event loop.
+    edit: Option<PathBuf>,
+    hits: Vec<(Rect, Hit)>,
+    status: Option<(String, Instant)>,
+    /// The milestone the latest scan crossed, and when the banner went up.
+    celebration: Option<(u64, Instant)>,
+    /// The total before the latest scan changed it, and when the big number started rolling.
+    odometer: Option<(u64, Instant)>,
+    /// How the latest scan's total differs from the one before it in this session.
+    session_change: Option<i64>,
+    /// The scan on screen is the one saved by the last session, not one made in this.
+    restored: bool,
+    /// The scan running was asked for, at launch or with `r`, rather than started by `--refresh`.
+    requested: bool,
 }
 
 impl App {
-    fn new() -> Result<Self, Box<dyn Error>> {
-        let scan = scan_directory(std::env::current_dir()?)?;
+    fn new(
+        settings: Settings,
+        base: &Path,
+        roots: Vec<PathBuf>,
+        trend: Vec<TrendPoint>,
+        authors: Vec<AuthorLines>,
+        dashboard: Option<Dashboard>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut app = Self::build(settings, base, roots, trend, authors, dashboard)?;
+        // `--history` and `--authors` open on their own views rather than the last session's.
+        if app.settings.session && app.view == View::Summary {
+            app.restore_session();
+        }
+        app.refresh(true);
+        Ok(app)
+    }
+
+    /// The app with nothing scanned yet and no scan started.
+    fn build(
+        settings: Settings,
+        base: &Path,
+        roots: Vec<PathBuf>,
+        trend: Vec<TrendPoint>,
+        authors: Vec<AuthorLines>,
+        dashboard: Option<Dashboard>,
+    ) -> Result<Self, Box<dyn Error>> {
         Ok(Self {
-            scan,
+            cache: Arc::new(Mutex::new(LineCache::new(base, &settings))),
+            history: History::load(&roots, &settings)?,
+            language_chart: LanguageChart::Bars,
+            show_estimate: settings.estimate,
+            show_errors: true,
+            compact_number: settings.compact_number,
+            all_categories: false,
+            font: settings.font,
+            show_help: false,
+            edit: None,
+            settings: Arc::new(settings),
+            base: base.to_path_buf(),
+            roots,
+            scan: None,
             last_scan: Instant::now(),
+            pending: None,
+            progress: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            view: match (trend.is_empty(), authors.is_empty(), dashboard.is_some()) {
+                (false, _, _) => View::Trend,
+                (true, false, _) => View::Authors,
+                (true, true, true) => View::Repos,
+                (true, true, false) => View::Summary,
+            },
+            file_sort: Sort::by_lines(),
+            file_table: TableState::default(),
+            language_sort: Sort::by_lines(),
+            language_table: TableState::default(),
+            dir_path: Vec::new(),
+            dir_table: TableState::default(),
+            todo_table: TableState::default(),
+            long_line_table: TableState::default(),
+            duplicate_table: TableState::default(),
+            trend,
+            authors,
+            author_table: TableState::default(),
+            package_table: TableState::default(),
+            dashboard,
+            repo_table: TableState::default(),
+            page: 10,
+            hits: Vec::new(),
+            status: None,
+            celebration: None,
+            odometer: None,
+            session_change: None,
+            restored: false,
+            requested: false,
         })
     }
 
-    fn run(&mut self, terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Result<(), Box<dyn Error>> {
+    /// Puts back the scan, view, sort orders and toggles saved when the TUI last quit on
+    /// these roots; the new scan replaces the count once it's done.
+    fn restore_session(&mut self) {
+        let Some(session) = Session::load(&self.roots) else {
+            return;
+        };
+        tracing::info!(scanned_at = %session.scan.scanned_at, "restored the session");
+        self.scan = Some(session.scan);
+        self.restored = true;
+        if self.views().contains(&session.view) {
+            self.view = session.view;
+        }
+        self.file_sort = session.file_sort;
+        self.language_sort = session.language_sort;
+        self.language_chart = session.language_chart;
+        self.show_estimate = session.show_estimate;
+        self.show_errors = session.show_errors;
+        self.compact_number = session.compact_number;
+        self.all_categories = session.all_categories;
+        if let Some(font) = self.settings.fonts.position(&session.font) {
+            self.font = font;
+        }
+        self.sort_tables();
+    }
+
+    /// Saves what's on screen for the next launch on these roots. Failing to is logged rather
+    /// than standing in the way of quitting.
+    fn save_session(&mut self) {
+        if !self.settings.session {
+            return;
+        }
+        let Some(scan) = self.scan.take() else {
+            return;
+        };
+        let session = Session {
+            roots: self.roots.clone(),
+            scan,
+            view: self.view,
+            file_sort: self.file_sort,
+            language_sort: self.language_sort,
+            language_chart: self.language_chart,
+            show_estimate: self.show_estimate,
+            show_errors: self.show_errors,
+            compact_number: self.compact_number,
+            all_categories: self.all_categories,
+            font: self.settings.fonts.get(self.font).name.clone(),
+        };
+        if let Err(err) = session.save() {
+            tracing::warn!(error = %err, "could not save the session");
+        }
+        self.scan = Some(session.scan);
+    }
+
+    fn run(&mut self, terminal: &mut Term) -> Result<(), Box<dyn Error>> {
         loop {
             terminal.draw(|frame| draw_ui(frame, self))?;
 
-            if event::poll(Duration::from_millis(200))? {
-                if let Event::Key(key) = event::read()? {
-                    match key.code {
-                        KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => return Ok(()),
-                        KeyCode::Char('r') | KeyCode::Char('R') | KeyCode::Enter => self.refresh()?,
-                        _ => {}
+            let tick = if self.animating() { ANIMATION_TICK } else { IDLE_TICK };
+            if event::poll(tick)? {
+                match event::read()? {
+                    Event::Key(key) if !self.handle_key(key) => {
+                        self.save_session();
+                        return Ok(());
                     }
+                    Event::Mouse(mouse) => self.handle_mouse(mouse),
+                    _ => {}
                 }
             }
+
+            if let Some(path) = self.edit.take()
+                && let Err(err) = open_in_editor(terminal, &path)
+            {
+                tracing::warn!(path = %path.display(), error = %err, "editor failed");
+                self.status = Some((format!("Could not open {}: {}", path.display(), err), Instant::now()));
+            }
+
+            self.poll_scan();
+            if let Some(dashboard) = &mut self.dashboard {
+                dashboard.tick();
+            }
+
+            if let Some(interval) = self.settings.refresh
+                && self.last_scan.elapsed() >= interval
+            {
+                self.refresh(false);
+            }
         }
     }
 
-    fn refresh(&mut self) -> Result<(), Box<dyn Error>> {
-        let scan = scan_directory(self.scan.dir.clone())?;
-        self.scan = scan;
-        self.last_scan = Instant::now();
-        Ok(())
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if self.show_help {
+            self.show_help = false;
+            return true;
+        }
+        let Some(action) = self.settings.keymap.action(key) else {
+            if let KeyCode::Char(digit @ '1'..='9') = key.code {
+                let index = digit as usize - '1' as usize;
+                if let Some(view) = self.views().get(index) {
+                    self.view = *view;
+                }
+            }
+            return true;
+        };
+        match action {
+            Action::Cancel if self.pending.is_some() => self.cancel_scan(),
+            Action::Cancel | Action::Quit => return false,
+            Action::Open if self.view == View::Files => self.edit_selected(),
+            Action::Rescan | Action::Open if self.view == View::Repos => {
+                if let Some(dashboard) = &mut self.dashboard {
+                    dashboard.rescan_all();
+                }
+            }
+            Action::Rescan | Action::Open => self.refresh(true),
+            Action::ExportCsv => self.export(ExportFormat::Csv),
+            Action::ExportMarkdown => self.export(ExportFormat::Md),
+            Action::CopyText => self.copy_summary(false),
+            Action::CopyJson => self.copy_summary(true),
+            Action::SavePng => self.export_image(ImageFormat::Png),
+            Action::SaveSvg => self.export_image(ImageFormat::Svg),
+            Action::Chart => self.language_chart = self.language_chart.next(),
+            Action::Estimate => self.show_estimate = !self.show_estimate,
+            Action::Errors => self.show_errors = !self.show_errors,
+            Action::Compact => self.compact_number = !self.compact_number,
+            Action::AllCategories => self.all_categories = !self.all_categories,
+            Action::Font => self.font = (self.font + 1) % self.settings.fonts.len(),
+            Action::Help => self.show_help = true,
+            Action::NextView => self.step_view(1),
+            Action::PreviousView => self.step_view(-1),
+            Action::EnterDirectory if self.view == View::Directories => self.enter_dir(),
+            Action::LeaveDirectory if self.view == View::Directories => self.leave_dir(),
+            Action::EnterDirectory | Action::LeaveDirectory => {}
+            Action::Sort | Action::ReverseSort => self.change_sort(action == Action::ReverseSort),
+            Action::Up | Action::Down | Action::PageUp | Action::PageDown | Action::Top | Action::Bottom => {
+                self.scroll(action)
+            }
+        }
+        true
     }
-}
 
-fn draw_ui(frame: &mut Frame, app: &App) {
-    let area = frame.area();
-
-    let timestamp = app.scan.scanned_at.format("%Y-%m-%d %H:%M:%S %z");
-    let headline = Paragraph::new(Line::from(format!(
-        "As of {} the number of lines of code in this repo is:",
-        timestamp
-    )))
-    .alignment(Alignment::Center)
-    .block(Block::default().borders(Borders::ALL));
-
-    let ascii_lines = ascii_art_number(app.scan.lines);
-    let time_line = format!(
-        "Time since last scan: {}",
-        format_duration(app.last_scan.elapsed())
-    );
-    let ascii_width = ascii_lines
-        .iter()
-        .map(|line| line.chars().count())
-        .chain(std::iter::once(time_line.chars().count()))
-        .max()
-        .unwrap_or(0) as u16;
-    let ascii_height = ascii_lines.len() as u16 + 2;
-    let mut ascii_text = ascii_lines
-        .iter()
-        .map(|line| Line::styled(line.clone(), Style::default().fg(Color::Cyan)))
-        .collect::<Vec<_>>();
-    ascii_text.push(Line::from(""));
-    ascii_text.push(Line::from(time_line));
-
-    let ascii = Paragraph::new(ascii_text)
-        .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL).title("Lines of Code"));
-
-    let info = Paragraph::new(vec![
-        Line::from(vec![
-            Span::styled("Directory: ", Style::default().fg(Color::Yellow)),
-            Span::raw(app.scan.dir.display().to_string()),
-        ]),
-        Line::from(vec![
-            Span::styled("Files scanned: ", Style::default().fg(Color::Yellow)),
-            Span::raw(app.scan.files.to_string()),
-        ]),
-        Line::from("Keys: r/R/Enter = rescan, q/Q/Esc = quit."),
-    ])
-    .block(Block::default().borders(Borders::ALL))
-    .wrap(Wrap { trim: true });
-
-    let header_height = 3u16.min(area.height);
-    let info_height = 5u16.min(area.height);
-    let header_rect = Rect {
-        x: area.x,
-        y: area.y,
-        width: area.width,
-        height: header_height,
-    };
-    let info_rect = Rect {
-        x: area.x,
-        y: area.y + area.height.saturating_sub(info_height),
-        width: area.width,
-        height: info_height,
-    };
-    let ascii_rect = centered_rect(
-        ascii_width.saturating_add(2),
-        ascii_height.saturating_add(2),
-        area,
-    );
-
-    frame.render_widget(headline, header_rect);
-    frame.render_widget(info, info_rect);
-    frame.render_widget(ascii, ascii_rect);
-}
+    /// The views that have something to show; the git history and author tabs only exist
+    /// with `--history` and `--authors`.
+    fn views(&self) -> Vec<View> {
+        View::ALL
+            .iter()
+            .copied()
+            .filter(|view| match view {
+                View::Trend => !self.trend.is_empty(),
+                View::Repos => self.dashboard.is_some(),
+                View::Authors => !self.authors.is_empty(),
+                View::Packages => self.scan.as_ref().is_some_and(|scan| scan.packages.len() > 1),
+                View::LongLines => self.scan.as_ref().is_some_and(|scan| !scan.long_line_files().is_empty()),
+                View::Duplicates => self.scan.as_ref().is_some_and(|scan| !scan.duplicate_groups().is_empty()),
+                _ => true,
+            })
+            .collect()
+    }
 
-fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
-    let width = width.min(area.width);
-    let height = height.min(area.height);
-    let x = area.x + area.width.saturating_sub(width) / 2;
-    let y = area.y + area.height.saturating_sub(height) / 2;
-    Rect {
-        x,
-        y,
-        width,
-        height,
+    fn step_view(&mut self, step: isize) {
+        let views = self.views();
+        let index = views.iter().position(|view| *view == self.view).unwrap_or(0) as isize;
+        self.view = views[(index + step).rem_euclid(views.len() as isize) as usize];
     }
-}
 
-fn ascii_art_number(value: u64) -> Vec<String> {
-    let chars = format_with_commas(value).chars().collect::<Vec<_>>();
-    let mut lines = Vec::with_capacity(DIGIT_HEIGHT);
-    for row in 0..DIGIT_HEIGHT {
-        let mut line = String::new();
-        for (idx, ch) in chars.iter().enumerate() {
-            if idx > 0 {
-                let prev = chars[idx - 1];
-                if *ch != ',' && prev != ',' {
-                    line.push_str("  ");
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        match mouse.kind {
+            MouseEventKind::ScrollUp => self.scroll(Action::Up),
+            MouseEventKind::ScrollDown => self.scroll(Action::Down),
+            MouseEventKind::Down(MouseButton::Left) if self.show_help => self.show_help = false,
+            MouseEventKind::Down(MouseButton::Left) => {
+                let position = Position::new(mouse.column, mouse.row);
+                let Some(&(_, hit)) = self.hits.iter().find(|(area, _)| area.contains(position)) else {
+                    return;
+                };
+                match hit {
+                    Hit::View(view) => self.view = view,
+                    Hit::Sort(key) => self.sort_by(key),
+                    Hit::Row(index) => self.click_row(index),
                 }
             }
-            let pattern = match ch {
-                '0'..='9' => DIGITS[ch.to_digit(10).unwrap() as usize][row],
-                ',' => COMMA[row],
-                _ => "     ",
-            };
-            line.push_str(&expand_scaled_row(pattern));
-        }
-        for _ in 0..SCALE_Y {
-            lines.push(line.clone());
+            _ => {}
         }
     }
-    lines
-}
 
-fn format_with_commas(value: u64) -> String {
-    let raw = value.to_string();
-    let mut out = String::with_capacity(raw.len() + raw.len() / 3);
-    let mut count = 0;
-    for ch in raw.chars().rev() {
-        if count == 3 {
-            out.push(',');
-            count = 0;
+    /// Selects a clicked row; clicking the selected directory again opens it.
+    fn click_row(&mut self, index: usize) {
+        let table = match self.view {
+            View::Summary | View::Trend => return,
+            View::Languages => &mut self.language_table,
+            View::Files => &mut self.file_table,
+            View::Todos => &mut self.todo_table,
+            View::LongLines => &mut self.long_line_table,
+            View::Duplicates => &mut self.duplicate_table,
+            View::Directories => &mut self.dir_table,
+            View::Packages => &mut self.package_table,
+            View::Authors => &mut self.author_table,
+            View::Repos => &mut self.repo_table,
+        };
+        if self.view == View::Directories && table.selected() == Some(index) {
+            self.enter_dir();
+        } else {
+            table.select(Some(index));
         }
-        out.push(ch);
-        count += 1;
     }
-    out.chars().rev().collect()
-}
 
-fn format_duration(duration: Duration) -> String {
-    let total_ms = duration.as_millis();
-    let ms_in_day = 86_400_000u128;
-    let ms_in_hour = 3_600_000u128;
-    let ms_in_minute = 60_000u128;
-    let ms_in_second = 1_000u128;
+    fn sort_by(&mut self, key: SortKey) {
+        let (sort, table) = match self.view {
+            View::Summary
+            | View::Directories
+            | View::Packages
+            | View::Todos
+            | View::LongLines
+            | View::Duplicates
+            | View::Trend
+            | View::Authors
+            | View::Repos => return,
+            View::Languages => (&mut self.language_sort, &mut self.language_table),
+            View::Files => (&mut self.file_sort, &mut self.file_table),
+        };
+        if sort.key == key {
+            sort.descending = !sort.descending;
+        } else {
+            sort.key = key;
+            sort.descending = key != SortKey::Name;
+        }
+        table.select(Some(0));
+        self.sort_tables();
+    }
 
-    let days = total_ms / ms_in_day;
-    let hours = (total_ms % ms_in_day) / ms_in_hour;
-    let minutes = (total_ms % ms_in_hour) / ms_in_minute;
-    let seconds = (total_ms % ms_in_minute) / ms_in_second;
-    let millis = total_ms % ms_in_second;
+    fn change_sort(&mut self, reverse: bool) {
+        let (sort, keys, table) = match self.view {
+            View::Summary
+            | View::Directories
+            | View::Packages
+            | View::Todos
+            | View::LongLines
+            | View::Duplicates
+            | View::Trend
+            | View::Authors
+            | View::Repos => return,
+            View::Languages => (&mut self.language_sort, Sort::LANGUAGE_KEYS, &mut self.language_table),
+            View::Files => (&mut self.file_sort, Sort::FILE_KEYS, &mut self.file_table),
+        };
+        if reverse {
+            sort.descending = !sort.descending;
+        } else {
+            sort.cycle(keys);
+        }
+        table.select(Some(0));
+        self.sort_tables();
+    }
 
-    let mut parts = Vec::new();
-    if days > 0 {
-        parts.push(format!("{}d", days));
+    fn scroll(&mut self, action: Action) {
+        let Some(scan) = &self.scan else {
+            return;
+        };
+        let (table, count) = match self.view {
+            View::Summary | View::Trend => return,
+            View::Languages => (&mut self.language_table, scan.languages.len()),
+            View::Files => (&mut self.file_table, scan.file_stats.len()),
+            View::Todos => (&mut self.todo_table, scan.todos.len()),
+            View::LongLines => (&mut self.long_line_table, scan.long_line_files().len()),
+            View::Duplicates => (&mut self.duplicate_table, scan.duplicate_groups().len()),
+            View::Packages => (&mut self.package_table, scan.packages.len()),
+            View::Authors => (&mut self.author_table, self.authors.len()),
+            View::Repos => (&mut self.repo_table, self.dashboard.as_ref().map_or(0, |dashboard| dashboard.repos.len())),
+            View::Directories => (
+                &mut self.dir_table,
+                scan.tree.find(&self.dir_path).map_or(0, |dir| dir.children.len()),
+            ),
+        };
+        if count == 0 {
+            return;
+        }
+        let selected = table.selected().unwrap_or(0);
+        let selected = match action {
+            Action::Up => selected.saturating_sub(1),
+            Action::Down => selected + 1,
+            Action::PageUp => selected.saturating_sub(self.page),
+            Action::PageDown => selected + self.page,
+            Action::Top => 0,
+            Action::Bottom => count - 1,
+            _ => return,
+        };
+        table.select(Some(selected.min(count - 1)));
     }
-    if hours > 0 {
-        parts.push(format!("{}h", hours));
+
+    fn edit_selected(&mut self) {
+        let Some(scan) = &self.scan else {
+            return;
+        };
+        if let Some(file) = self.file_table.selected().and_then(|index| scan.file_stats.get(index)) {
+            self.edit = Some(file.path.clone());
+        }
     }
-    if minutes > 0 {
-        parts.push(format!("{}m", minutes));
+
+    fn current_dir(&self) -> Option<&DirNode> {
+        self.scan.as_ref()?.tree.find(&self.dir_path)
     }
-    parts.push(format!("{}s", seconds));
-    if millis > 0 || parts.is_empty() {
-        parts.push(format!("{}ms", millis));
+
+    fn enter_dir(&mut self) {
+        let Some(dir) = self.current_dir() else {
+            return;
+        };
+        if let Some(child) = self.dir_table.selected().and_then(|index| dir.children.get(index))
+            && child.is_dir
+        {
+            self.dir_path.push(child.name.clone());
+            self.dir_table.select(Some(0));
+        }
     }
 
-    parts.join(" ")
-}
+    fn leave_dir(&mut self) {
+        let Some(name) = self.dir_path.pop() else {
+            return;
+        };
+        let index = self
+            .current_dir()
+            .and_then(|dir| dir.children.iter().position(|child| child.is_dir && child.name == name));
+        self.dir_table.select(Some(index.unwrap_or(0)));
+    }
 
-fn expand_scaled_row(row: &str) -> String {
-    let mut out = String::with_capacity(row.len() * SCALE_X);
-    for ch in row.chars() {
-        match ch {
-            '█' => {
-                for _ in 0..SCALE_X {
-                    out.push('█');
+    fn sort_tables(&mut self) {
+        let Some(scan) = &mut self.scan else {
+            return;
+        };
+        let sort = self.file_sort;
+        scan.file_stats.sort_by(|a, b| {
+            sort.apply(match sort.key {
+                SortKey::Lines | SortKey::Files | SortKey::Bytes | SortKey::Words => {
+                    a.lines.cmp(&b.lines).then_with(|| b.path.cmp(&a.path))
                 }
+                SortKey::Name => a.path.cmp(&b.path),
+            })
+        });
+        let sort = self.language_sort;
+        scan.languages.sort_by(|a, b| {
+            sort.apply(match sort.key {
+                SortKey::Lines => a.lines.cmp(&b.lines).then_with(|| b.name.cmp(&a.name)),
+                SortKey::Files => a.files.cmp(&b.files).then_with(|| b.name.cmp(&a.name)),
+                SortKey::Bytes => a.size.bytes.cmp(&b.size.bytes).then_with(|| b.name.cmp(&a.name)),
+                SortKey::Words => a.size.words.cmp(&b.size.words).then_with(|| b.name.cmp(&a.name)),
+                SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            })
+        });
+    }
+
+    fn export(&mut self, format: ExportFormat) {
+        let Some(scan) = &self.scan else {
+            return;
+        };
+        let path = default_export_path(&self.base, format, scan);
+        let message = match export_to_file(format, scan, &path) {
+            Ok(()) => {
+                tracing::info!(path = %path.display(), "exported");
+                format!("Exported to {}", path.display())
             }
-            _ => {
-                for _ in 0..SCALE_X {
-                    out.push(' ');
-                }
+            Err(err) => {
+                tracing::warn!(path = %path.display(), error = %err, "export failed");
+                format!("Export failed: {}: {}", path.display(), err)
             }
-        }
+        };
+        self.status = Some((message, Instant::now()));
     }
-    out
-}
-
-fn scan_directory(dir: PathBuf) -> Result<ScanResult, Box<dyn Error>> {
-    let mut lines = 0u64;
-    let mut files = 0u64;
 
-    let walker = WalkDir::new(&dir).into_iter().filter_entry(|entry| !is_ignored(entry.path()));
+    /// Saves the summary as an image the size of the terminal.
+    fn export_image(&mut self, format: ImageFormat) {
+        let Some(scan) = &self.scan else {
+            return;
+        };
+        let path = default_image_path(&self.base, format, scan);
+        let size = crossterm::terminal::size().unwrap_or(snapshot::DEFAULT_SIZE);
+        let message = match snapshot::export_image(self, format, size, &path) {
+            Ok(()) => {
+                tracing::info!(path = %path.display(), "saved the summary image");
+                format!("Saved the summary to {}", path.display())
+            }
+            Err(err) => {
+                tracing::warn!(path = %path.display(), error = %err, "image export failed");
+                format!("Image export failed: {}: {}", path.display(), err)
+            }
+        };
+        self.status = Some((message, Instant::now()));
+    }
 
-    for entry in walker {
-        let entry = match entry {
-            Ok(entry) => entry,
-            Err(_) => continue,
+    /// Copies the plain-text summary, or its JSON form, to the clipboard.
+    fn copy_summary(&mut self, json: bool) {
+        let Some(scan) = &self.scan else {
+            return;
+        };
+        let mut text = Vec::new();
+        let written = if json {
+            write_summary_json(scan, &mut text)
+        } else {
+            export(ExportFormat::Text, scan, &mut text)
         };
+        let kind = if json { "JSON" } else { "text" };
+        let message = match written.and_then(|()| clipboard::copy(&String::from_utf8_lossy(&text))) {
+            Ok(via) => {
+                tracing::info!(kind, via = %via, "copied the summary");
+                format!("Copied the summary as {} to the clipboard (via {})", kind, via)
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, "copy failed");
+                format!("Copy failed: {}", err)
+            }
+        };
+        self.status = Some((message, Instant::now()));
+    }
 
-        if entry.file_type().is_file() && is_code_file(entry.path()) {
-            files += 1;
-            lines += count_lines(entry.path()).unwrap_or(0);
+    fn status_message(&self) -> Option<&str> {
+        self.status
+            .as_ref()
+            .filter(|(_, shown_at)| shown_at.elapsed() < STATUS_TIMEOUT)
+            .map(|(message, _)| message.as_str())
+    }
+
+    /// The total to draw while the big number rolls to a new count, eased to slow down at the end.
+    fn rolling_total(&self) -> Option<f64> {
+        // The odometer rolls between totals, so it sits still while every category is shown.
+        if self.all_categories {
+            return None;
+        }
+        let (from, started) = self.odometer?;
+        let to = self.scan.as_ref()?.lines;
+        let progress = started.elapsed().as_secs_f64() / ODOMETER_TIME.as_secs_f64();
+        if progress >= 1.0 {
+            return None;
         }
+        let eased = 1.0 - (1.0 - progress).powi(3);
+        Some(from as f64 + (to as f64 - from as f64) * eased)
     }
 
-    Ok(ScanResult {
-        lines,
-        files,
-        dir,
-        scanned_at: Local::now(),
-    })
-}
+    fn animating(&self) -> bool {
+        self.rolling_total().is_some() || self.celebrating().is_some()
+    }
 
-fn is_ignored(path: &Path) -> bool {
-    for component in path.components() {
-        let name = component.as_os_str().to_string_lossy();
-        if name == ".git" || name == "target" || name == "node_modules" {
-            return true;
-        }
+    /// The milestone being celebrated and how long the banner has been up.
+    fn celebrating(&self) -> Option<(u64, Duration)> {
+        self.celebration
+            .map(|(milestone, since)| (milestone, since.elapsed()))
+            .filter(|(_, elapsed)| *elapsed < CELEBRATION_TIME)
     }
-    false
-}
 
-fn count_lines(path: &Path) -> io::Result<u64> {
-    let mut file = File::open(path)?;
-    let mut buf = Vec::new();
-    file.read_to_end(&mut buf)?;
+    /// Time left until the next automatic rescan, rounded up to whole seconds.
+    fn next_refresh(&self) -> Option<Duration> {
+        let interval = self.settings.refresh?;
+        let remaining = interval.saturating_sub(self.last_scan.elapsed());
+        Some(Duration::from_secs(remaining.as_secs_f64().ceil() as u64))
+    }
 
-    if buf.is_empty() {
-        return Ok(0);
+    fn refresh(&mut self, requested: bool) {
+        if self.pending.is_none() {
+            self.requested = requested;
+            self.cancel = Arc::new(AtomicBool::new(false));
+            let from_daemon = self
+                .settings
+                .daemon
+                .then(|| daemon::spawn_request(self.roots.clone(), self.base.clone(), Arc::clone(&self.cancel)))
+                .flatten();
+            self.pending = Some(from_daemon.unwrap_or_else(|| {
+                spawn_scan(
+                    self.roots.clone(),
+                    Arc::clone(&self.settings),
+                    Arc::clone(&self.cache),
+                    Arc::clone(&self.cancel),
+                )
+            }));
+            self.progress = Some((Progress::default(), Instant::now()));
+        }
     }
 
-    let mut count = buf.iter().filter(|b| **b == b'\n').count() as u64;
-    if *buf.last().unwrap() != b'\n' {
-        count += 1;
+    fn cancel_scan(&mut self) {
+        self.cancel.store(true, atomic::Ordering::Relaxed);
+        self.status = Some(("Cancelling the scan…".to_string(), Instant::now()));
     }
-    Ok(count)
-}
 
-fn is_code_file(path: &Path) -> bool {
-    let ext = match path.extension() {
-        Some(ext) => ext.to_string_lossy().to_lowercase(),
-        None => return false,
-    };
-    CODE_EXTENSIONS.iter().any(|allowed| *allowed == ext)
+    fn poll_scan(&mut self) {
+        let Some(pending) = &self.pending else {
+            return;
+        };
+        loop {
+            match pending.try_recv() {
+                Ok(ScanMessage::Progress(progress)) => {
+                    if let Some((current, _)) = &mut self.progress {
+                        *current = progress;
+                    }
+                }
+                Ok(ScanMessage::Done(result)) => {
+                    self.pending = None;
+                    self.progress = None;
+                    let scan = match result {
+                        Ok(scan) => *scan,
+                        Err(err) => {
+                            self.scan_failed(&err);
+                            return;
+                        }
+                    };
+                    if let Some(previous) = &self.scan {
+                        if previous.lines != scan.lines {
+                            self.odometer = Some((previous.lines, Instant::now()));
+                        }
+                        if !self.restored {
+                            self.session_change = Some(scan.lines as i64 - previous.lines as i64);
+                        }
+                    }
+                    self.restored = false;
+                    // A history that can't be written is no reason to lose the scan.
+                    match self.history.record(&scan, &self.settings.milestones, self.requested) {
+                        Ok(Some(milestone)) => self.celebration = Some((milestone, Instant::now())),
+                        Ok(None) => {}
+                        Err(err) => {
+                            tracing::warn!(error = %err, "could not record the scan in the history");
+                            self.status = Some((format!("Could not record the scan in the history: {}", err), Instant::now()));
+                        }
+                    }
+                    self.scan = Some(scan);
+                    self.last_scan = Instant::now();
+                    while self.current_dir().is_none() && self.dir_path.pop().is_some() {}
+                    self.sort_tables();
+                    return;
+                }
+                Ok(ScanMessage::Cancelled) => {
+                    self.pending = None;
+                    self.progress = None;
+                    // Restart the auto-refresh interval rather than rescanning straight away.
+                    self.last_scan = Instant::now();
+                    let message = if self.scan.is_some() {
+                        "Scan cancelled, showing the previous result"
+                    } else {
+                        "Scan cancelled"
+                    };
+                    self.status = Some((message.to_string(), Instant::now()));
+                    return;
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.pending = None;
+                    self.progress = None;
+                    self.scan_failed("the scanner thread exited without a result");
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Keeps the previous result on screen when a rescan fails, and says why in the status line.
+    fn scan_failed(&mut self, error: &str) {
+        tracing::warn!(error, "scan failed");
+        // Wait a whole interval before trying again, rather than failing on every tick.
+        self.last_scan = Instant::now();
+        let message = if self.scan.is_some() {
+            format!("Scan failed, showing the previous result: {}", error)
+        } else {
+            format!("Scan failed: {}", error)
+        };
+        self.status = Some((message, Instant::now()));
+    }
 }
@@ -0,0 +1,109 @@
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::scan::{count_reader, FileCount, Probes};
+
+/// The part of an `.ipynb` file that's counted: its cells, without their outputs or the
+/// notebook's metadata.
+#[derive(Deserialize)]
+struct Notebook {
+    cells: Vec<Cell>,
+}
+
+#[derive(Deserialize)]
+struct Cell {
+    cell_type: String,
+    #[serde(default)]
+    source: Source,
+}
+
+/// A cell's text, which nbformat allows as one string or as a list of lines.
+#[derive(Default, Deserialize)]
+#[serde(untagged)]
+enum Source {
+    #[default]
+    Empty,
+    Text(String),
+    Lines(Vec<String>),
+}
+
+impl Source {
+    fn text(&self) -> String {
+        match self {
+            Source::Empty => String::new(),
+            Source::Text(text) => text.clone(),
+            Source::Lines(lines) => lines.concat(),
+        }
+    }
+}
+
+pub fn is_notebook(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+}
+
+/// Counts a Jupyter notebook by its cells: `lines`, the todos and the rest come from the
+/// code cells, one after another, and the markdown cells' lines go in `docs_lines`.
+/// Outputs and metadata aren't counted. A file that isn't a notebook after all is counted
+/// as it is.
+pub fn count_notebook(raw: &[u8], probes: &Probes) -> io::Result<Option<FileCount>> {
+    let Ok(notebook) = serde_json::from_slice::<Notebook>(raw) else {
+        return count_reader(&mut &*raw, probes);
+    };
+    let mut code = String::new();
+    let mut docs_lines = 0;
+    for cell in &notebook.cells {
+        let text = cell.source.text();
+        match cell.cell_type.as_str() {
+            "code" => {
+                code.push_str(&text);
+                if !text.is_empty() && !text.ends_with('\n') {
+                    code.push('\n');
+                }
+            }
+            "markdown" => docs_lines += text.lines().count() as u64,
+            _ => {}
+        }
+    }
+    let count = count_reader(&mut code.as_bytes(), probes)?;
+    Ok(count.map(|count| FileCount { docs_lines, ..count }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+        "metadata": {"kernelspec": {"name": "python3"}},
+        "cells": [
+            {"cell_type": "markdown", "source": ["# Title\n", "\n", "Some prose. TODO: not code\n"]},
+            {"cell_type": "code", "source": ["import os\n", "# TODO: tidy up\n", "print(os.getcwd())"],
+             "outputs": [{"output_type": "stream", "text": ["/home\n", "/tmp\n"]}]},
+            {"cell_type": "raw", "source": "left out\n"},
+            {"cell_type": "code", "source": "x = 1\n"},
+            {"cell_type": "code"},
+            {"cell_type": "markdown", "source": "One more line"}
+        ]
+    }"##;
+
+    #[test]
+    fn splits_code_cells_from_markdown() {
+        let markers = ["TODO".to_string()];
+        let probes = Probes {
+            todo_markers: &markers,
+            ..Probes::default()
+        };
+        let count = count_notebook(NOTEBOOK.as_bytes(), &probes).unwrap().unwrap();
+        assert_eq!(count.lines, 4);
+        assert_eq!(count.docs_lines, 4);
+        assert_eq!(count.todos.len(), 1);
+        assert_eq!(count.todos[0].line, 2);
+    }
+
+    #[test]
+    fn counts_anything_else_as_it_is() {
+        let count = count_notebook(b"not json\nat all\n", &Probes::default()).unwrap().unwrap();
+        assert_eq!((count.lines, count.docs_lines), (2, 0));
+    }
+}
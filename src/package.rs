@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Settings;
+use crate::scan::FileStat;
+
+/// The manifests that mark the top of a package in a monorepo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageKind {
+    Npm,
+    Go,
+    Python,
+}
+
+impl PackageKind {
+    /// The kind of package a file named `file_name` is the manifest of, if any.
+    pub fn of_manifest(file_name: &str) -> Option<Self> {
+        match file_name {
+            "package.json" => Some(PackageKind::Npm),
+            "go.mod" => Some(PackageKind::Go),
+            "pyproject.toml" => Some(PackageKind::Python),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PackageKind::Npm => "npm",
+            PackageKind::Go => "go",
+            PackageKind::Python => "python",
+        }
+    }
+
+    fn manifest(self) -> &'static str {
+        match self {
+            PackageKind::Npm => "package.json",
+            PackageKind::Go => "go.mod",
+            PackageKind::Python => "pyproject.toml",
+        }
+    }
+
+    /// The name the manifest gives the package: `name` in `package.json`, the `module` path
+    /// in `go.mod`, and `[project]` or `[tool.poetry]` `name` in `pyproject.toml`.
+    fn package_name(self, manifest: &Path) -> Option<String> {
+        let raw = fs::read_to_string(manifest).ok()?;
+        match self {
+            PackageKind::Npm => {
+                let json = serde_json::from_str::<serde_json::Value>(&raw).ok()?;
+                json.get("name")?.as_str().map(str::to_string)
+            }
+            PackageKind::Go => raw
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("module "))
+                .map(|module| module.trim().trim_matches('"').to_string()),
+            PackageKind::Python => {
+                let toml = toml::from_str::<toml::Value>(&raw).ok()?;
+                let name = toml
+                    .get("project")
+                    .and_then(|project| project.get("name"))
+                    .or_else(|| toml.get("tool")?.get("poetry")?.get("name"))?;
+                name.as_str().map(str::to_string)
+            }
+        }
+    }
+}
+
+/// A manifest the walk came across, before the files are grouped under it.
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub root: usize,
+    pub path: PathBuf,
+    pub kind: PackageKind,
+}
+
+/// A package of a monorepo and the files under its directory that no package deeper down
+/// claims.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageStat {
+    pub root: usize,
+    pub name: String,
+    pub kind: PackageKind,
+    /// The package's directory, relative to its root; empty for the root itself.
+    pub path: PathBuf,
+    pub lines: u64,
+    pub files: u64,
+}
+
+/// Groups every counted file under the nearest directory above it that has a manifest. A
+/// directory with several manifests is one package, of the kind listed first in
+/// [`PackageKind`]. Files outside every package are left out. Sorted largest first.
+pub fn package_stats(
+    roots: &[PathBuf],
+    manifests: &[Manifest],
+    file_stats: &[FileStat],
+    settings: &Settings,
+) -> Vec<PackageStat> {
+    let mut by_dir = BTreeMap::<(usize, PathBuf), PackageKind>::new();
+    for manifest in manifests {
+        let Some(dir) = manifest.path.parent() else {
+            continue;
+        };
+        let relative = dir.strip_prefix(&roots[manifest.root]).unwrap_or(dir).to_path_buf();
+        by_dir
+            .entry((manifest.root, relative))
+            .and_modify(|kind| *kind = (*kind).min(manifest.kind))
+            .or_insert(manifest.kind);
+    }
+    let mut packages = by_dir
+        .into_iter()
+        .map(|((root, path), kind)| {
+            let dir = roots[root].join(&path);
+            let name = kind.package_name(&dir.join(kind.manifest())).unwrap_or_else(|| {
+                dir.file_name().map_or_else(|| dir.display().to_string(), |name| name.to_string_lossy().into_owned())
+            });
+            PackageStat {
+                root,
+                name,
+                kind,
+                path,
+                lines: 0,
+                files: 0,
+            }
+        })
+        .collect::<Vec<_>>();
+    for file in file_stats {
+        let relative = file.path.strip_prefix(&roots[file.root]).unwrap_or(&file.path);
+        let package = packages
+            .iter_mut()
+            .filter(|package| package.root == file.root && relative.starts_with(&package.path))
+            .max_by_key(|package| package.path.components().count());
+        if let Some(package) = package {
+            package.lines += file.weighted_lines(&settings.categories);
+            package.files += 1;
+        }
+    }
+    packages.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| (a.root, &a.path).cmp(&(b.root, &b.path))));
+    packages
+}
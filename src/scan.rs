@@ -0,0 +1,1812 @@
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::ops::AddAssign;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use memchr::{memchr_iter, memmem};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+use crate::analyzer::{Metrics, PatternAnalyzer};
+use crate::archive::{self, ArchiveKind};
+use crate::cache::LineCache;
+use crate::complexity::{Complexity, ComplexityCounter};
+use crate::config::Settings;
+use crate::encoding::{self, Decoder};
+use crate::git::{self, LineChanges};
+use crate::language::{Categories, Category, Language, LanguageDb};
+use crate::license::{find_license, LicenseStat};
+use crate::notebook::{count_notebook, is_notebook};
+use crate::package::{package_stats, Manifest, PackageKind, PackageStat};
+use crate::todo::{find_todos, Todo, TodoComment};
+use crate::workspace::{crate_stats, CrateStat};
+
+const LARGEST_FILES: usize = 10;
+/// How often a running scan reports progress.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// How much of a file is inspected when deciding whether it is text or generated.
+const SNIFF_LEN: usize = 8 * 1024;
+/// Files are streamed through a buffer this size instead of being read whole.
+const CHUNK_LEN: usize = 64 * 1024;
+/// Only this much of a single line is kept when looking for TODO markers, so a
+/// minified one-line file doesn't get buffered in full.
+const MAX_TODO_LINE: usize = 4 * 1024;
+const GENERATED_MARKERS: &[&str] = &[
+    "@generated",
+    "DO NOT EDIT",
+    "Code generated by",
+    "Generated by the protocol buffer compiler",
+    "OpenAPI Generator",
+    "<auto-generated",
+    "This file is automatically generated",
+];
+/// Line comments, and block comments with their ends, that can make up the comment block at
+/// the top of a file in any language; shebangs and XML prologs go with them.
+const LEADING_COMMENTS: &[&str] = &["//", "#", "--", ";", "%", "*", "<?xml"];
+const LEADING_BLOCK_COMMENTS: &[(&str, &str)] =
+    &[("/*", "*/"), ("<!--", "-->"), ("\"\"\"", "\"\"\""), ("'''", "'''"), ("{-", "-}"), ("(*", "*)")];
+/// A line at least this many characters long marks a file as likely minified or data.
+pub const LONG_LINE: u64 = 1000;
+/// Code averaging more characters a line than this is taken for minified.
+const MINIFIED_MEAN_LINE: f64 = 300.0;
+const VENDOR_DIRS: &[&str] = &["vendor", "third_party", "third-party"];
+/// FNV-1a, for the content hash that finds duplicate files. It only has to be stable
+/// between runs, since the hashes are cached.
+pub const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+pub const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScanResult {
+    /// The headline total: each file's lines times its category's weight, so only code by default.
+    pub lines: u64,
+    pub files: u64,
+    pub size: TextSize,
+    pub binary_files: u64,
+    /// Files left unread for being over `--max-file-size`.
+    #[serde(default)]
+    pub oversized_files: u64,
+    /// Files left unread because `--max-bytes` had been read by the time the scan reached them.
+    #[serde(default)]
+    pub over_budget_files: u64,
+    pub generated: Bucket,
+    /// Minified files, such as `bundle.min.js`, set aside like generated ones.
+    #[serde(default)]
+    pub minified: Bucket,
+    /// The part of the total in test code.
+    #[serde(default)]
+    pub tests: Bucket,
+    /// Every counted file by category, whatever the category's weight.
+    #[serde(default)]
+    pub categories: Vec<CategoryStat>,
+    /// The member crates of Cargo workspaces among the roots.
+    #[serde(default)]
+    pub crates: Vec<CrateStat>,
+    /// Directories with a `package.json`, `go.mod` or `pyproject.toml`, and the files under each.
+    #[serde(default)]
+    pub packages: Vec<PackageStat>,
+    /// The walk stopped early because `--max-files` was reached.
+    pub truncated: bool,
+    /// Files and directories that couldn't be read, so the totals may be short.
+    #[serde(default)]
+    pub errors: Vec<ScanError>,
+    pub roots: Vec<RootStat>,
+    pub languages: Vec<LanguageStat>,
+    pub file_stats: Vec<FileStat>,
+    pub largest: Vec<FileStat>,
+    pub tree: DirNode,
+    pub todos: Vec<Todo>,
+    /// The analyzers' metrics summed over every file.
+    #[serde(default)]
+    pub metrics: Metrics,
+    pub since: Option<LineChanges>,
+    pub scanned_at: DateTime<Local>,
+    #[serde(default)]
+    pub throughput: Throughput,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RootStat {
+    pub path: PathBuf,
+    pub lines: u64,
+    pub files: u64,
+    pub size: TextSize,
+    pub binary_files: u64,
+    #[serde(default)]
+    pub oversized_files: u64,
+    pub generated: Bucket,
+    #[serde(default)]
+    pub minified: Bucket,
+    /// Checked-out git submodules left out of the count, relative to the root.
+    #[serde(default)]
+    pub submodules: Vec<PathBuf>,
+}
+
+/// A path the walk couldn't descend into, or a file that couldn't be read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanError {
+    pub root: usize,
+    pub path: PathBuf,
+    pub message: String,
+}
+
+/// Lines and files set aside from the headline total.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Bucket {
+    pub lines: u64,
+    pub files: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryStat {
+    pub category: Category,
+    pub lines: u64,
+    pub files: u64,
+    /// What a line of the category added to the headline total in this scan.
+    pub weight: f64,
+}
+
+/// How long a scan took and how much reading the cache saved it.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Throughput {
+    pub elapsed: Duration,
+    /// Bytes in the files that had to be read, the cache misses.
+    pub bytes_read: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+}
+
+impl Throughput {
+    pub fn files_per_second(&self) -> f64 {
+        (self.cache_hits + self.cache_misses) as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn bytes_per_second(&self) -> f64 {
+        self.bytes_read as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageStat {
+    pub name: String,
+    #[serde(default)]
+    pub category: Category,
+    pub lines: u64,
+    pub files: u64,
+    pub size: TextSize,
+    /// Lines per file: the average, and the median and 95th percentile by nearest rank.
+    #[serde(default)]
+    pub mean_lines: f64,
+    #[serde(default)]
+    pub median_lines: u64,
+    #[serde(default)]
+    pub p95_lines: u64,
+    #[serde(default)]
+    pub longest_line: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileStat {
+    pub root: usize,
+    pub path: PathBuf,
+    pub language: String,
+    #[serde(default)]
+    pub category: Category,
+    pub lines: u64,
+    /// Lines in the docs category on top of `lines`: a notebook's markdown cells.
+    #[serde(default)]
+    pub docs_lines: u64,
+    pub size: TextSize,
+    #[serde(default)]
+    pub longest_line: u64,
+    /// Matches one of the test patterns.
+    #[serde(default)]
+    pub test: bool,
+    /// Of the file's text, so copies of it have the same one.
+    #[serde(default)]
+    pub hash: u64,
+    /// Measured with `--complexity`, for languages with branch keywords.
+    #[serde(default)]
+    pub complexity: Option<Complexity>,
+    /// From the file's SPDX tag or license header.
+    #[serde(default)]
+    pub license: Option<String>,
+    /// What the `[[analyzers]]` found in the file.
+    #[serde(default, skip_serializing_if = "Metrics::is_empty")]
+    pub metrics: Metrics,
+}
+
+impl FileStat {
+    /// What the file adds to the headline total.
+    pub fn weighted_lines(&self, categories: &Categories) -> u64 {
+        categories.weighted(self.category, self.lines) + categories.weighted(Category::Docs, self.docs_lines)
+    }
+}
+
+/// Files with exactly the same contents. Every copy after the first is duplication.
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup<'a> {
+    pub lines: u64,
+    pub files: Vec<&'a FileStat>,
+}
+
+impl DuplicateGroup<'_> {
+    pub fn duplicated_lines(&self) -> u64 {
+        self.lines * (self.files.len() as u64 - 1)
+    }
+}
+
+/// Line totals per directory, nested the same way as `ScanResult::display_path`.
+/// Files are leaves; children are sorted by lines, largest first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DirNode {
+    pub name: String,
+    pub is_dir: bool,
+    pub lines: u64,
+    pub files: u64,
+    pub children: Vec<DirNode>,
+}
+
+impl DirNode {
+    fn directory(name: String) -> Self {
+        Self {
+            name,
+            is_dir: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn find(&self, path: &[String]) -> Option<&DirNode> {
+        path.iter().try_fold(self, |node, name| {
+            node.children.iter().find(|child| child.is_dir && &child.name == name)
+        })
+    }
+
+    fn insert(&mut self, components: &[String], lines: u64) {
+        self.lines += lines;
+        self.files += 1;
+        match components {
+            [] => {}
+            [file] => self.children.push(DirNode {
+                name: file.clone(),
+                is_dir: false,
+                lines,
+                files: 1,
+                children: Vec::new(),
+            }),
+            [dir, rest @ ..] => {
+                let index = match self.children.iter().position(|child| child.is_dir && &child.name == dir) {
+                    Some(index) => index,
+                    None => {
+                        self.children.push(DirNode::directory(dir.clone()));
+                        self.children.len() - 1
+                    }
+                };
+                self.children[index].insert(rest, lines);
+            }
+        }
+    }
+
+    fn sort(&mut self) {
+        self.children
+            .sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.name.cmp(&b.name)));
+        for child in &mut self.children {
+            child.sort();
+        }
+    }
+}
+
+impl ScanResult {
+    /// Sets of identical files, most duplicated lines first. Empty files are all alike and
+    /// don't count.
+    pub fn duplicate_groups(&self) -> Vec<DuplicateGroup<'_>> {
+        let mut by_content = BTreeMap::<(u64, u64), Vec<&FileStat>>::new();
+        for file in self.file_stats.iter().filter(|file| file.lines > 0) {
+            by_content.entry((file.hash, file.size.bytes)).or_default().push(file);
+        }
+        let mut groups = by_content
+            .into_values()
+            .filter(|files| files.len() > 1)
+            .map(|mut files| {
+                files.sort_by(|a, b| (a.root, &a.path).cmp(&(b.root, &b.path)));
+                DuplicateGroup {
+                    lines: files[0].lines,
+                    files,
+                }
+            })
+            .collect::<Vec<_>>();
+        groups.sort_by(|a, b| {
+            b.duplicated_lines()
+                .cmp(&a.duplicated_lines())
+                .then_with(|| a.files[0].path.cmp(&b.files[0].path))
+        });
+        groups
+    }
+
+    /// Lines in every copy of a file but the first.
+    pub fn duplicated_lines(&self) -> u64 {
+        self.duplicate_groups().iter().map(DuplicateGroup::duplicated_lines).sum()
+    }
+
+    /// Files with a line of at least `LONG_LINE` characters, longest first.
+    pub fn long_line_files(&self) -> Vec<&FileStat> {
+        let mut files = self
+            .file_stats
+            .iter()
+            .filter(|file| file.longest_line >= LONG_LINE)
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| b.longest_line.cmp(&a.longest_line).then_with(|| a.path.cmp(&b.path)));
+        files
+    }
+
+    /// The files with the most branches, then the deepest nesting; only measured with `--complexity`.
+    pub fn most_complex(&self) -> Vec<&FileStat> {
+        let mut files = self
+            .file_stats
+            .iter()
+            .filter(|file| file.complexity.is_some_and(|complexity| complexity.branches > 0))
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| {
+            let (a_complexity, b_complexity) = (a.complexity.unwrap_or_default(), b.complexity.unwrap_or_default());
+            (b_complexity.branches, b_complexity.max_depth)
+                .cmp(&(a_complexity.branches, a_complexity.max_depth))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        files.truncate(LARGEST_FILES);
+        files
+    }
+
+    /// The licenses named in file headers, most files first.
+    pub fn license_stats(&self) -> Vec<LicenseStat> {
+        let mut by_name = BTreeMap::<&str, LicenseStat>::new();
+        for file in &self.file_stats {
+            let Some(name) = &file.license else {
+                continue;
+            };
+            let stat = by_name.entry(name).or_insert_with(|| LicenseStat {
+                name: name.clone(),
+                files: 0,
+                lines: 0,
+            });
+            stat.files += 1;
+            stat.lines += file.lines;
+        }
+        let mut licenses = by_name.into_values().collect::<Vec<_>>();
+        licenses.sort_by(|a, b| b.files.cmp(&a.files).then_with(|| a.name.cmp(&b.name)));
+        licenses
+    }
+
+    /// Files without a license header in a language where other files have one, so a tree
+    /// that doesn't use headers at all (or only in its code) isn't flagged wholesale.
+    pub fn unlicensed_files(&self) -> Vec<&FileStat> {
+        let licensed = self
+            .file_stats
+            .iter()
+            .filter(|file| file.license.is_some())
+            .map(|file| file.language.as_str())
+            .collect::<HashSet<_>>();
+        let mut files = self
+            .file_stats
+            .iter()
+            .filter(|file| file.license.is_none() && licensed.contains(file.language.as_str()))
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| (a.root, &a.path).cmp(&(b.root, &b.path)));
+        files
+    }
+
+    /// The submodules left out of every root, as display paths.
+    pub fn skipped_submodules(&self) -> Vec<String> {
+        self.roots
+            .iter()
+            .enumerate()
+            .flat_map(|(index, root)| {
+                root.submodules
+                    .iter()
+                    .map(move |submodule| self.display(index, &root.path.join(submodule)))
+            })
+            .collect()
+    }
+
+    /// Lines in every counted file, whether or not its category is in the headline total.
+    pub fn counted_lines(&self) -> u64 {
+        self.categories.iter().map(|category| category.lines).sum()
+    }
+
+    /// Files in the categories that add to the headline total, the ones `lines` comes from.
+    pub fn total_files(&self) -> u64 {
+        self.categories
+            .iter()
+            .filter(|category| category.weight > 0.0)
+            .map(|category| category.files)
+            .sum()
+    }
+
+    /// Test lines per line of production code, once there is some of each.
+    pub fn test_ratio(&self) -> Option<f64> {
+        let production = self.lines.saturating_sub(self.tests.lines);
+        (self.tests.lines > 0 && production > 0).then(|| self.tests.lines as f64 / production as f64)
+    }
+
+    pub fn display_path(&self, file: &FileStat) -> String {
+        self.display(file.root, &file.path)
+    }
+
+    /// `path` relative to root number `root`, prefixed with the root's name when there are several.
+    pub fn display(&self, root: usize, path: &Path) -> String {
+        let root = &self.roots[root].path;
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        if self.roots.len() == 1 && relative.as_os_str().is_empty() {
+            root_label(root).display().to_string()
+        } else if self.roots.len() == 1 {
+            relative.display().to_string()
+        } else {
+            root_label(root).join(relative).display().to_string()
+        }
+    }
+}
+
+pub fn root_label(root: &Path) -> &Path {
+    root.file_name().map(Path::new).unwrap_or(root)
+}
+
+/// How far a running scan has got.
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    /// Files the walk has reached, whether or not they turn out to be counted.
+    pub discovered: u64,
+    pub counted: u64,
+    /// The file being looked at, relative to its root.
+    pub current: PathBuf,
+}
+
+pub enum ScanMessage {
+    Progress(Progress),
+    Done(Result<Box<ScanResult>, String>),
+    Cancelled,
+}
+
+/// Scans on a background thread. Setting `cancel` stops the walk at the next file.
+pub fn spawn_scan(
+    roots: Vec<PathBuf>,
+    settings: Arc<Settings>,
+    cache: Arc<Mutex<LineCache>>,
+    cancel: Arc<AtomicBool>,
+) -> Receiver<ScanMessage> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut cache = cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let report = |progress: &Progress| {
+            let _ = tx.send(ScanMessage::Progress(progress.clone()));
+        };
+        let result = scan(&roots, &settings, &mut cache, &report, &cancel);
+        let message = if cancel.load(Ordering::Relaxed) {
+            ScanMessage::Cancelled
+        } else {
+            ScanMessage::Done(result.map(Box::new).map_err(|err| err.to_string()))
+        };
+        let _ = tx.send(message);
+    });
+    rx
+}
+
+/// State shared by the walks over every root of one scan.
+struct WalkState<'a> {
+    /// Canonical paths of every file counted so far, so a file reachable through
+    /// several symlinks (or several roots) is only counted once.
+    seen: HashSet<PathBuf>,
+    progress: Progress,
+    last_report: Instant,
+    report: &'a dyn Fn(&Progress),
+    cancel: &'a AtomicBool,
+    /// Set once `--max-files` cut the walk short.
+    truncated: bool,
+    errors: Vec<ScanError>,
+    throughput: Throughput,
+    /// Bytes in the files handed to the readers so far, against `--max-bytes`.
+    bytes_planned: u64,
+    over_budget_files: u64,
+    /// Package manifests passed on the way, counted or not.
+    manifests: Vec<Manifest>,
+}
+
+impl WalkState<'_> {
+    fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    fn visit(&mut self, path: &Path) {
+        self.progress.discovered += 1;
+        self.maybe_report(path);
+    }
+
+    /// Remembers `path` if it's a package manifest, unless it belongs to a vendored package
+    /// that the scan sets aside.
+    fn note_manifest(&mut self, root: usize, path: &Path, relative: &Path, settings: &Settings) {
+        let kind = path.file_name().and_then(|name| name.to_str()).and_then(PackageKind::of_manifest);
+        if let Some(kind) = kind
+            && (settings.count_generated || !is_vendored(relative))
+        {
+            self.manifests.push(Manifest {
+                root,
+                path: path.to_path_buf(),
+                kind,
+            });
+        }
+    }
+
+    fn counted(&mut self, path: &Path) {
+        self.progress.counted += 1;
+        self.maybe_report(path);
+    }
+
+    /// Whether reading a file this size would go past `--max-bytes`; if not, its bytes are
+    /// set aside. Once one file is over, every file after it is too, so the files a budget
+    /// leaves out don't depend on how big the ones after them happen to be.
+    fn over_budget(&mut self, len: u64, settings: &Settings) -> bool {
+        let Some(budget) = settings.max_bytes else {
+            return false;
+        };
+        if self.over_budget_files == 0 && self.bytes_planned + len <= budget {
+            self.bytes_planned += len;
+            return false;
+        }
+        if self.over_budget_files == 0 {
+            warn!(bytes = self.bytes_planned, budget, "read budget used up");
+        }
+        true
+    }
+
+    fn error(&mut self, root: usize, path: &Path, err: impl ToString) {
+        let message = err.to_string();
+        warn!(path = %path.display(), error = %message, "unreadable");
+        self.errors.push(ScanError {
+            root,
+            path: path.to_path_buf(),
+            message,
+        });
+    }
+
+    fn maybe_report(&mut self, path: &Path) {
+        if self.last_report.elapsed() >= PROGRESS_INTERVAL {
+            self.progress.current = path.to_path_buf();
+            (self.report)(&self.progress);
+            self.last_report = Instant::now();
+        }
+    }
+}
+
+pub fn scan(
+    roots: &[PathBuf],
+    settings: &Settings,
+    cache: &mut LineCache,
+    report: &dyn Fn(&Progress),
+    cancel: &AtomicBool,
+) -> Result<ScanResult, Box<dyn Error>> {
+    let started = Instant::now();
+    info!(roots = roots.len(), threads = settings.threads, "scan started");
+    let mut result = ScanResult {
+        lines: 0,
+        files: 0,
+        size: TextSize::default(),
+        binary_files: 0,
+        oversized_files: 0,
+        over_budget_files: 0,
+        generated: Bucket::default(),
+        minified: Bucket::default(),
+        tests: Bucket::default(),
+        categories: Vec::new(),
+        crates: Vec::new(),
+        packages: Vec::new(),
+        truncated: false,
+        errors: Vec::new(),
+        roots: Vec::with_capacity(roots.len()),
+        languages: Vec::new(),
+        file_stats: Vec::new(),
+        largest: Vec::new(),
+        tree: DirNode::default(),
+        todos: Vec::new(),
+        metrics: Metrics::new(),
+        since: None,
+        scanned_at: Local::now(),
+        throughput: Throughput::default(),
+    };
+
+    let mut state = WalkState {
+        seen: HashSet::new(),
+        progress: Progress::default(),
+        last_report: Instant::now(),
+        report,
+        cancel,
+        truncated: false,
+        errors: Vec::new(),
+        throughput: Throughput::default(),
+        bytes_planned: 0,
+        over_budget_files: 0,
+        manifests: Vec::new(),
+    };
+    for (index, root) in roots.iter().enumerate() {
+        let root_stat = match ArchiveKind::of(root).filter(|_| root.is_file()) {
+            Some(kind) => scan_archive(index, root, kind, settings, &mut state, &mut result.file_stats, &mut result.todos),
+            None => scan_directory(
+                index,
+                root,
+                settings,
+                cache,
+                &mut state,
+                &mut result.file_stats,
+                &mut result.todos,
+            ),
+        };
+        result.lines += root_stat.lines;
+        result.files += root_stat.files;
+        result.size += root_stat.size;
+        result.binary_files += root_stat.binary_files;
+        result.oversized_files += root_stat.oversized_files;
+        result.generated.lines += root_stat.generated.lines;
+        result.generated.files += root_stat.generated.files;
+        result.minified.lines += root_stat.minified.lines;
+        result.minified.files += root_stat.minified.files;
+        result.roots.push(root_stat);
+
+        if let Some(reference) = &settings.since {
+            let changes = git::changes_since(root, reference, settings)?;
+            let total = result.since.get_or_insert_with(|| LineChanges {
+                reference: reference.clone(),
+                added: 0,
+                removed: 0,
+            });
+            total.added += changes.added;
+            total.removed += changes.removed;
+        }
+        if state.cancelled() {
+            settings.analyzers.finish();
+            info!(elapsed = ?started.elapsed(), "scan cancelled");
+            return Err("scan cancelled".into());
+        }
+    }
+    cache.finish_scan()?;
+    settings.analyzers.finish();
+
+    result.truncated = state.truncated;
+    result.over_budget_files = state.over_budget_files;
+    result.errors = state.errors;
+    result
+        .todos
+        .sort_by(|a, b| (a.root, &a.path, a.comment.line).cmp(&(b.root, &b.path, b.comment.line)));
+    result.languages = language_stats(&result.file_stats);
+    for file in result.file_stats.iter().filter(|file| file.test) {
+        result.tests.lines += file.weighted_lines(&settings.categories);
+        result.tests.files += 1;
+    }
+    result.categories = category_stats(&result.file_stats, settings);
+    result.crates = crate_stats(roots, &result.file_stats, settings);
+    result.packages = package_stats(roots, &state.manifests, &result.file_stats, settings);
+    for (name, count) in result.file_stats.iter().flat_map(|file| &file.metrics) {
+        *result.metrics.entry(name.clone()).or_default() += count;
+    }
+    result.largest = result.file_stats.clone();
+    result
+        .largest
+        .sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.path.cmp(&b.path)));
+    result.largest.truncate(LARGEST_FILES);
+    result.tree = directory_tree(&result);
+    result.scanned_at = Local::now();
+    result.throughput = Throughput {
+        elapsed: started.elapsed(),
+        ..state.throughput
+    };
+    info!(
+        lines = result.lines,
+        files = result.files,
+        binary = result.binary_files,
+        generated = result.generated.files,
+        minified = result.minified.files,
+        errors = result.errors.len(),
+        truncated = result.truncated,
+        elapsed = ?started.elapsed(),
+        "scan finished"
+    );
+
+    Ok(result)
+}
+
+fn scan_directory(
+    root: usize,
+    dir: &Path,
+    settings: &Settings,
+    cache: &mut LineCache,
+    state: &mut WalkState,
+    file_stats: &mut Vec<FileStat>,
+    todos: &mut Vec<Todo>,
+) -> RootStat {
+    let skips = RootSkips::new(dir, settings);
+    let mut candidates = match &settings.files_from {
+        Some(files) => listed_files(root, dir, files, &skips, settings, state, file_stats.len()),
+        None => walk_directory(root, dir, &skips, settings, state, file_stats.len()),
+    };
+    let mut oversized_files = 0u64;
+    if let Some(limit) = settings.max_file_size {
+        candidates.retain(|(path, _)| {
+            let oversized = fs::metadata(path).is_ok_and(|metadata| metadata.len() > limit);
+            if oversized {
+                debug!(path = %path.display(), "skipped oversized");
+                oversized_files += 1;
+            }
+            !oversized
+        });
+    }
+    let counts = count_files(root, dir, &candidates, settings, cache, state);
+    // Files left uncounted by a cancelled scan are simply dropped.
+    let counted = candidates
+        .into_iter()
+        .zip(counts)
+        .filter_map(|((path, language), count)| Some((path, language, count?)))
+        .collect();
+
+    let mut stat = tally(root, dir, counted, true, settings, file_stats, todos);
+    stat.oversized_files = oversized_files;
+    // Ones that were never initialized are empty directories, so nothing was left out.
+    stat.submodules = skips
+        .submodules
+        .into_iter()
+        .filter(|submodule| dir.join(submodule).join(".git").exists())
+        .collect();
+    stat
+}
+
+/// Counts the files in the archive at `path` as they're read out of it, with the filters
+/// and limits of a walk. Nothing is unpacked, so there's no line cache for them and only the
+/// pattern analyzers run; the others need a file on disk.
+fn scan_archive(
+    root: usize,
+    path: &Path,
+    kind: ArchiveKind,
+    settings: &Settings,
+    state: &mut WalkState,
+    file_stats: &mut Vec<FileStat>,
+    todos: &mut Vec<Todo>,
+) -> RootStat {
+    let already = file_stats.len();
+    let mut counted = Vec::new();
+    let mut oversized_files = 0u64;
+    let mut unreadable = Vec::new();
+    let result = archive::for_each_entry(path, kind, &mut |relative, len, reader| {
+        if state.cancelled() {
+            return false;
+        }
+        // A walk doesn't go into left-out directories, so nothing below one counts either.
+        let skipped = relative.ancestors().filter(|dir| !dir.as_os_str().is_empty()).any(|dir| {
+            is_ignored(dir, &settings.ignore) || is_excluded(dir, dir != relative, settings)
+        });
+        if skipped || settings.max_depth.is_some_and(|depth| relative.components().count() > depth) {
+            debug!(path = %relative.display(), "skipped");
+            return true;
+        }
+        state.visit(relative);
+        let mut reader = BufReader::new(reader);
+        let language = if is_code_file(relative, settings) {
+            Some(settings.languages.language_name(relative))
+        } else if relative.extension().is_none() {
+            let head = reader.fill_buf().unwrap_or_default();
+            shebang_language(head, settings).map(|language| language.name.clone())
+        } else {
+            None
+        };
+        let Some(language) = language else {
+            return true;
+        };
+        if settings.max_files.is_some_and(|max| already + counted.len() >= max) {
+            info!(path = %relative.display(), "stopped at --max-files");
+            state.truncated = true;
+            return false;
+        }
+        if settings.max_file_size.is_some_and(|limit| len > limit) {
+            debug!(path = %relative.display(), "skipped oversized");
+            oversized_files += 1;
+            return true;
+        }
+        if state.over_budget(len, settings) {
+            debug!(path = %relative.display(), "skipped over the read budget");
+            state.over_budget_files += 1;
+            return true;
+        }
+        let count = match count_named(relative, &mut reader, &Probes::new(settings, &language)) {
+            Ok(count) => count,
+            Err(err) => {
+                unreadable.push((relative.to_path_buf(), err));
+                Some(FileCount::default())
+            }
+        };
+        state.throughput.cache_misses += 1;
+        state.throughput.bytes_read += len;
+        state.counted(relative);
+        counted.push((path.join(relative), language, count));
+        true
+    });
+    for (relative, err) in unreadable {
+        state.error(root, &path.join(relative), err);
+    }
+    if let Err(err) = result {
+        state.error(root, path, err);
+    }
+
+    let mut stat = tally(root, path, counted, false, settings, file_stats, todos);
+    stat.oversized_files = oversized_files;
+    stat
+}
+
+/// The category a file read out of git is counted under, placed like a scanned file, or
+/// `None` when it's set aside as generated, vendored or minified.
+pub fn counted_category(relative: &Path, count: &FileCount, settings: &Settings) -> Option<Category> {
+    let language = settings.languages.language_name(relative);
+    match place(relative, relative, &language, count, settings) {
+        Placement::Counted(category) => Some(category),
+        Placement::Generated | Placement::Minified => None,
+    }
+}
+
+/// Where a counted file goes in the totals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    /// Set aside as generated or vendored.
+    Generated,
+    /// Set aside as minified.
+    Minified,
+    Counted(Category),
+}
+
+/// Sorts a counted file the way every count does, from a walk or out of git: generated,
+/// vendored and minified files are set aside unless the settings count them, and the rest
+/// go under their category.
+pub fn place(path: &Path, relative: &Path, language: &str, count: &FileCount, settings: &Settings) -> Placement {
+    if !settings.count_generated && (count.generated || is_vendored(relative)) {
+        return Placement::Generated;
+    }
+    let category = settings.categories.of(path, language, count.size.bytes, &settings.languages);
+    if !settings.count_minified && is_minified(relative, category, count) {
+        return Placement::Minified;
+    }
+    Placement::Counted(category)
+}
+
+/// Totals the counted files of one root, `None` for binary ones, and adds them to
+/// `file_stats` and their comments to `todos`. `analyze` runs the registered analyzers on them.
+fn tally(
+    root: usize,
+    dir: &Path,
+    counted: Vec<(PathBuf, String, Option<FileCount>)>,
+    analyze: bool,
+    settings: &Settings,
+    file_stats: &mut Vec<FileStat>,
+    todos: &mut Vec<Todo>,
+) -> RootStat {
+    let mut stat = RootStat {
+        path: dir.to_path_buf(),
+        lines: 0,
+        files: 0,
+        size: TextSize::default(),
+        binary_files: 0,
+        oversized_files: 0,
+        generated: Bucket::default(),
+        minified: Bucket::default(),
+        submodules: Vec::new(),
+    };
+    for (path, language, count) in counted {
+        let Some(count) = count else {
+            debug!(path = %path.display(), "skipped binary");
+            stat.binary_files += 1;
+            continue;
+        };
+        let relative = path.strip_prefix(dir).unwrap_or(&path);
+        let test = settings.tests.is_match(relative);
+        let category = match place(&path, relative, &language, &count, settings) {
+            Placement::Generated => {
+                debug!(path = %path.display(), lines = count.lines, "set aside generated");
+                stat.generated.lines += count.lines;
+                stat.generated.files += 1;
+                continue;
+            }
+            Placement::Minified => {
+                debug!(path = %path.display(), lines = count.lines, "set aside minified");
+                stat.minified.lines += count.lines;
+                stat.minified.files += 1;
+                continue;
+            }
+            Placement::Counted(category) => category,
+        };
+        stat.files += 1;
+        stat.size += count.size;
+        todos.extend(count.todos.into_iter().map(|comment| Todo {
+            root,
+            path: path.clone(),
+            comment,
+        }));
+        let mut metrics = count.metrics;
+        if analyze {
+            metrics.extend(settings.analyzers.analyze(&path, &language));
+        }
+        let file = FileStat {
+            root,
+            language,
+            category,
+            path,
+            lines: count.lines,
+            docs_lines: count.docs_lines,
+            size: count.size,
+            longest_line: count.longest_line,
+            test,
+            hash: count.hash,
+            complexity: count.complexity,
+            license: count.license,
+            metrics,
+        };
+        stat.lines += file.weighted_lines(&settings.categories);
+        file_stats.push(file);
+    }
+    stat
+}
+
+/// What one root leaves out on top of the settings: its git submodules and the paths git
+/// ignores, both relative to the root.
+#[derive(Debug, Default)]
+struct RootSkips {
+    submodules: Vec<PathBuf>,
+    git_ignored: HashSet<PathBuf>,
+}
+
+impl RootSkips {
+    fn new(dir: &Path, settings: &Settings) -> Self {
+        Self {
+            submodules: if settings.include_submodules { Vec::new() } else { git::submodule_paths(dir) },
+            git_ignored: if settings.gitignore { git::ignored_paths(dir) } else { HashSet::new() },
+        }
+    }
+
+    /// Whether `relative`, or a directory it is in, is left out.
+    fn skips(&self, relative: &Path) -> bool {
+        relative
+            .ancestors()
+            .filter(|path| !path.as_os_str().is_empty())
+            .any(|path| self.git_ignored.contains(path) || self.submodules.iter().any(|submodule| submodule == path))
+    }
+}
+
+/// Walks `dir` for the files to count, with the language each is counted under.
+/// `already` is how many files earlier roots contributed towards `--max-files`.
+fn walk_directory(
+    root: usize,
+    dir: &Path,
+    skips: &RootSkips,
+    settings: &Settings,
+    state: &mut WalkState,
+    already: usize,
+) -> Vec<(PathBuf, String)> {
+    let mut candidates = Vec::new();
+
+    // With `follow_links`, walkdir reports symlink loops as errors, which are skipped below.
+    let mut walker = WalkDir::new(dir).follow_links(settings.follow_symlinks);
+    if let Some(depth) = settings.max_depth {
+        walker = walker.max_depth(depth);
+    }
+    let walker = walker.into_iter().filter_entry(|entry| {
+        let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        let skipped = is_ignored(entry.path(), &settings.ignore)
+            || is_excluded(relative, entry.file_type().is_dir(), settings)
+            || skips.skips(relative);
+        if skipped {
+            debug!(path = %entry.path().display(), "skipped");
+        }
+        !skipped
+    });
+
+    for entry in walker {
+        if state.cancelled() {
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) if err.loop_ancestor().is_some() => continue,
+            Err(err) => {
+                let path = err.path().unwrap_or(dir);
+                match err.io_error() {
+                    Some(io_err) => state.error(root, path, io_err),
+                    None => state.error(root, path, &err),
+                }
+                continue;
+            }
+        };
+
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        state.visit(relative);
+        state.note_manifest(root, entry.path(), relative, settings);
+        if let Some(language) = candidate_language(entry.path(), settings) {
+            if settings.max_files.is_some_and(|max| already + candidates.len() >= max) {
+                info!(path = %entry.path().display(), "stopped at --max-files");
+                state.truncated = true;
+                break;
+            }
+            if settings.follow_symlinks
+                && let Ok(canonical) = fs::canonicalize(entry.path())
+                && !state.seen.insert(canonical)
+            {
+                continue;
+            }
+            candidates.push((entry.into_path(), language));
+        }
+    }
+    candidates
+}
+
+/// The files of a `--files-from` list that lie below `dir`, filtered like a walk would be.
+fn listed_files(
+    root: usize,
+    dir: &Path,
+    files: &[PathBuf],
+    skips: &RootSkips,
+    settings: &Settings,
+    state: &mut WalkState,
+    already: usize,
+) -> Vec<(PathBuf, String)> {
+    let mut candidates = Vec::new();
+    for path in files {
+        if state.cancelled() {
+            break;
+        }
+        let Ok(relative) = path.strip_prefix(dir) else {
+            continue;
+        };
+        if is_ignored(relative, &settings.ignore)
+            || is_excluded(relative, false, settings)
+            || skips.skips(relative)
+        {
+            continue;
+        }
+        state.visit(relative);
+        state.note_manifest(root, path, relative, settings);
+        let Some(language) = candidate_language(path, settings) else {
+            continue;
+        };
+        if settings.max_files.is_some_and(|max| already + candidates.len() >= max) {
+            info!(path = %path.display(), "stopped at --max-files");
+            state.truncated = true;
+            break;
+        }
+        // A list may name a file twice, or name it under several roots.
+        if state.seen.insert(path.clone()) {
+            candidates.push((path.clone(), language));
+        }
+    }
+    candidates
+}
+
+/// The language a file is counted under, or `None` when it isn't code.
+fn candidate_language(path: &Path, settings: &Settings) -> Option<String> {
+    if is_code_file(path, settings) {
+        Some(settings.languages.language_name(path))
+    } else {
+        script_language(path, settings).map(|language| language.name.clone())
+    }
+}
+
+/// Counts `candidates` in walk order: cache hits directly, the rest on
+/// `settings.threads` worker threads. Unreadable files count as empty and are
+/// recorded in `state.errors`; `None` marks a file a cancelled scan never got to.
+fn count_files(
+    root: usize,
+    dir: &Path,
+    candidates: &[(PathBuf, String)],
+    settings: &Settings,
+    cache: &mut LineCache,
+    state: &mut WalkState,
+) -> Vec<Option<Option<FileCount>>> {
+    let mut counts = vec![None; candidates.len()];
+    let mut missing = Vec::new();
+    for (index, (path, _)) in candidates.iter().enumerate() {
+        match fs::metadata(path) {
+            Ok(metadata) => match cache.get(path, &metadata) {
+                Some(count) => {
+                    counts[index] = Some(count);
+                    state.throughput.cache_hits += 1;
+                    state.counted(path.strip_prefix(dir).unwrap_or(path));
+                }
+                None if state.over_budget(metadata.len(), settings) => {
+                    debug!(path = %path.display(), "skipped over the read budget");
+                    state.over_budget_files += 1;
+                }
+                None => missing.push((index, metadata)),
+            },
+            Err(err) => {
+                state.error(root, path, err);
+                counts[index] = Some(Some(FileCount::default()));
+            }
+        }
+    }
+    debug!(
+        root = %dir.display(),
+        hits = candidates.len() - missing.len(),
+        misses = missing.len(),
+        "cache"
+    );
+
+    let next = AtomicUsize::new(0);
+    let cancel = state.cancel;
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..settings.threads.min(missing.len()) {
+            let (tx, next, missing) = (tx.clone(), &next, &missing);
+            scope.spawn(move || {
+                while !cancel.load(Ordering::Relaxed) {
+                    let job = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((index, _)) = missing.get(job) else {
+                        break;
+                    };
+                    let (path, language) = &candidates[*index];
+                    let count = count_file(path, &Probes::new(settings, language));
+                    if tx.send((job, count)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+        for (job, count) in rx {
+            let (index, metadata) = &missing[job];
+            let path = &candidates[*index].0;
+            let count = match count {
+                Ok(count) => {
+                    cache.insert(path, metadata, count.clone());
+                    state.throughput.cache_misses += 1;
+                    state.throughput.bytes_read += metadata.len();
+                    count
+                }
+                // Not cached, so the next scan tries the file again.
+                Err(err) => {
+                    state.error(root, path, err);
+                    Some(FileCount::default())
+                }
+            };
+            state.counted(path.strip_prefix(dir).unwrap_or(path));
+            counts[*index] = Some(count);
+        }
+    });
+    counts
+}
+
+fn directory_tree(scan: &ScanResult) -> DirNode {
+    let mut tree = DirNode::directory(".".to_string());
+    for file in &scan.file_stats {
+        let components = Path::new(&scan.display_path(file))
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        tree.insert(&components, file.lines);
+    }
+    tree.sort();
+    tree
+}
+
+fn language_stats(file_stats: &[FileStat]) -> Vec<LanguageStat> {
+    let mut by_name = BTreeMap::<&str, (LanguageStat, Vec<u64>)>::new();
+    for file in file_stats {
+        let (stat, file_lines) = by_name.entry(&file.language).or_insert_with(|| {
+            let stat = LanguageStat {
+                name: file.language.clone(),
+                category: file.category,
+                lines: 0,
+                files: 0,
+                size: TextSize::default(),
+                mean_lines: 0.0,
+                median_lines: 0,
+                p95_lines: 0,
+                longest_line: 0,
+            };
+            (stat, Vec::new())
+        });
+        stat.lines += file.lines;
+        stat.files += 1;
+        stat.size += file.size;
+        stat.longest_line = stat.longest_line.max(file.longest_line);
+        file_lines.push(file.lines);
+    }
+    let mut languages = by_name
+        .into_values()
+        .map(|(mut stat, mut file_lines)| {
+            file_lines.sort_unstable();
+            stat.mean_lines = stat.lines as f64 / stat.files as f64;
+            stat.median_lines = percentile(&file_lines, 50);
+            stat.p95_lines = percentile(&file_lines, 95);
+            stat
+        })
+        .collect::<Vec<_>>();
+    languages.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.name.cmp(&b.name)));
+    languages
+}
+
+/// Lines and files in each category, in `Category::ALL` order, leaving out empty ones.
+fn category_stats(file_stats: &[FileStat], settings: &Settings) -> Vec<CategoryStat> {
+    Category::ALL
+        .iter()
+        .map(|category| {
+            let files = file_stats.iter().filter(|file| file.category == *category);
+            // A notebook's markdown cells are docs, whatever the notebook's own category.
+            let docs_lines = match category {
+                Category::Docs => file_stats.iter().map(|file| file.docs_lines).sum(),
+                _ => 0,
+            };
+            CategoryStat {
+                category: *category,
+                lines: files.clone().map(|file| file.lines).sum::<u64>() + docs_lines,
+                files: files.count() as u64,
+                weight: settings.categories.weight(*category),
+            }
+        })
+        .filter(|stat| stat.lines > 0 || stat.files > 0)
+        .collect()
+}
+
+/// The nearest-rank `percent`th percentile of `sorted`, which mustn't be empty.
+fn percentile(sorted: &[u64], percent: usize) -> u64 {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Whether a path out of git gets counted at all, by its name; see [`counted_category`] for
+/// what's set aside once its contents are known.
+pub fn is_counted(relative: &Path, settings: &Settings) -> bool {
+    !is_ignored(relative, &settings.ignore)
+        && !is_excluded(relative, false, settings)
+        && is_code_file(relative, settings)
+}
+
+/// Dot-directories and dotfiles counted by default: CI, tooling and editor configuration
+/// that is part of the project rather than someone's local state.
+pub const WELL_KNOWN_HIDDEN: &[&str] = &[
+    ".github",
+    ".gitlab",
+    ".circleci",
+    ".cargo",
+    ".devcontainer",
+    ".husky",
+    ".storybook",
+    ".gitlab-ci.yml",
+    ".pre-commit-config.yaml",
+    ".rustfmt.toml",
+    ".clippy.toml",
+    ".eslintrc.js",
+    ".eslintrc.cjs",
+    ".eslintrc.json",
+    ".prettierrc.js",
+    ".babelrc.js",
+    ".stylelintrc.js",
+    ".mocharc.js",
+];
+
+fn is_excluded(relative: &Path, is_dir: bool, settings: &Settings) -> bool {
+    !relative.as_os_str().is_empty()
+        && (settings.exclude.is_match(relative)
+            // Directories are always walked, since files below them may match `--include`.
+            || (!is_dir && !settings.include.is_empty() && !settings.include.is_match(relative))
+            || settings.ccignore.is_ignored(relative, is_dir)
+            || is_hidden(relative, settings.hidden))
+}
+
+/// Whether `relative` is, or lies inside, a dotfile or dot-directory that `hidden` skips.
+fn is_hidden(relative: &Path, hidden: Option<bool>) -> bool {
+    if hidden == Some(true) {
+        return false;
+    }
+    relative.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        name.starts_with('.')
+            && name != "."
+            && name != ".."
+            && (hidden == Some(false) || !WELL_KNOWN_HIDDEN.contains(&name.as_ref()))
+    })
+}
+
+fn is_ignored(path: &Path, ignore: &[String]) -> bool {
+    for component in path.components() {
+        let name = component.as_os_str().to_string_lossy();
+        if ignore.iter().any(|ignored| *ignored == name) {
+            return true;
+        }
+    }
+    false
+}
+
+/// `None` for files that look binary.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileCount {
+    pub lines: u64,
+    pub size: TextSize,
+    /// Characters in the longest line, not counting the line break.
+    pub longest_line: u64,
+    /// FNV-1a of the text, to find files that are copies of each other.
+    pub hash: u64,
+    /// Branches and nesting, with `--complexity`.
+    pub complexity: Option<Complexity>,
+    /// Named by an SPDX tag or a standard license header near the top.
+    pub license: Option<String>,
+    /// Carries a "generated, do not edit" style marker near the top.
+    pub generated: bool,
+    pub todos: Vec<TodoComment>,
+    /// A notebook's markdown cells, which are docs; `lines` only has its code cells.
+    #[serde(default)]
+    pub docs_lines: u64,
+    /// Lines matching each pattern analyzer, by its name.
+    #[serde(default)]
+    pub metrics: Metrics,
+}
+
+impl FileCount {
+    /// What the file adds to the headline total when counted under `category`.
+    pub fn weighted_lines(&self, category: Category, categories: &Categories) -> u64 {
+        categories.weighted(category, self.lines) + categories.weighted(Category::Docs, self.docs_lines)
+    }
+}
+
+/// Size of text content beyond its line count. Words are runs of non-whitespace.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextSize {
+    pub bytes: u64,
+    pub chars: u64,
+    pub words: u64,
+}
+
+impl TextSize {
+    /// Adds one chunk of a file; `in_word` carries a word split across chunks.
+    fn add_chunk(&mut self, buf: &[u8], in_word: &mut bool) {
+        for byte in buf {
+            let space = byte.is_ascii_whitespace();
+            if !space && !*in_word {
+                self.words += 1;
+            }
+            *in_word = !space;
+        }
+        self.bytes += buf.len() as u64;
+        self.chars += count_chars(buf);
+    }
+
+    /// Average characters per line over `lines` lines, line breaks left out.
+    pub fn mean_line(&self, lines: u64) -> f64 {
+        self.chars.saturating_sub(lines) as f64 / lines.max(1) as f64
+    }
+}
+
+/// Every byte that doesn't continue a UTF-8 sequence starts a character.
+fn count_chars(bytes: &[u8]) -> u64 {
+    bytes.iter().filter(|byte| **byte & 0xC0 != 0x80).count() as u64
+}
+
+impl AddAssign for TextSize {
+    fn add_assign(&mut self, other: Self) {
+        self.bytes += other.bytes;
+        self.chars += other.chars;
+        self.words += other.words;
+    }
+}
+
+/// Counts the lines in a file, or returns `None` if it looks binary. The file is
+/// streamed in `CHUNK_LEN` pieces, so memory use doesn't grow with its size. UTF-16
+/// files are decoded on the way, so they're counted like their UTF-8 equivalent.
+/// What else is looked for is up to `probes`. Jupyter notebooks are counted by their cells
+/// instead.
+pub fn count_file(path: &Path, probes: &Probes) -> io::Result<Option<FileCount>> {
+    count_named(path, &mut File::open(path)?, probes)
+}
+
+/// [`count_file`] for the contents of a file named `path` that come from elsewhere, such
+/// as an archive entry or a blob coming out of git.
+pub fn count_named(path: &Path, reader: &mut impl Read, probes: &Probes) -> io::Result<Option<FileCount>> {
+    if is_notebook(path) {
+        let mut raw = Vec::new();
+        reader.read_to_end(&mut raw)?;
+        return count_notebook(&raw, probes);
+    }
+    count_reader(reader, probes)
+}
+
+/// [`count_file`] for any stream, without the notebook handling.
+pub fn count_reader(reader: &mut impl Read, probes: &Probes) -> io::Result<Option<FileCount>> {
+    let mut buf = vec![0; CHUNK_LEN];
+    let len = read_chunk(reader, &mut buf)?;
+    let (encoding, bom) = encoding::sniff(&buf[..len]);
+    let mut decoder = Decoder::new(encoding);
+    let mut text = Vec::new();
+    let head = decoder.decode(&buf[bom..len], &mut text);
+    if is_binary(head) {
+        return Ok(None);
+    }
+    let generated = has_generated_marker(head);
+    let license = find_license(head);
+    let mut counter = LineCounter::new(probes);
+    counter.feed(head);
+    loop {
+        let len = read_chunk(reader, &mut buf)?;
+        if len == 0 {
+            break;
+        }
+        counter.feed(decoder.decode(&buf[..len], &mut text));
+    }
+    counter.feed(decoder.finish(&mut text));
+    Ok(Some(counter.finish(generated, license)))
+}
+
+/// Fills `buf` as far as the file allows; short only at the end of the file.
+fn read_chunk(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(len)
+}
+
+/// What a count looks for in a file besides its lines and size.
+#[derive(Debug, Default)]
+pub struct Probes<'a> {
+    pub todo_markers: &'a [String],
+    /// The file's language, when its complexity is to be measured.
+    pub complexity: Option<&'a Language>,
+    /// The pattern analyzers that apply to the file.
+    pub patterns: Vec<&'a PatternAnalyzer>,
+}
+
+impl<'a> Probes<'a> {
+    /// Everything `settings` asks for in a file counted as `language`.
+    pub fn new(settings: &'a Settings, language: &str) -> Self {
+        Self {
+            todo_markers: &settings.todo_markers,
+            complexity: settings.complexity.then(|| settings.languages.by_name(language)).flatten(),
+            patterns: settings
+                .analyzers
+                .patterns()
+                .iter()
+                .filter(|analyzer| analyzer.applies(language))
+                .collect(),
+        }
+    }
+}
+
+/// Running totals for a file fed in chunks.
+struct LineCounter<'a> {
+    markers: &'a [String],
+    newlines: u64,
+    last: Option<u8>,
+    size: TextSize,
+    in_word: bool,
+    /// Characters so far in a line that continues into the next chunk.
+    line_chars: u64,
+    longest_line: u64,
+    hash: u64,
+    /// The start of a line that continues into the next chunk.
+    partial: Vec<u8>,
+    todo_line: u64,
+    todos: Vec<TodoComment>,
+    complexity: Option<ComplexityCounter<'a>>,
+    /// Each pattern analyzer's name, patterns and lines matched.
+    patterns: Vec<(&'a str, Vec<memmem::Finder<'a>>, u64)>,
+}
+
+impl<'a> LineCounter<'a> {
+    fn new(probes: &Probes<'a>) -> Self {
+        Self {
+            markers: probes.todo_markers,
+            newlines: 0,
+            last: None,
+            size: TextSize::default(),
+            in_word: false,
+            line_chars: 0,
+            longest_line: 0,
+            hash: FNV_OFFSET,
+            partial: Vec::new(),
+            todo_line: 0,
+            todos: Vec::new(),
+            complexity: probes.complexity.and_then(ComplexityCounter::new),
+            patterns: probes
+                .patterns
+                .iter()
+                .map(|analyzer| (analyzer.name(), analyzer.finders(), 0))
+                .collect(),
+        }
+    }
+
+    fn feed(&mut self, chunk: &[u8]) {
+        self.newlines += memchr_iter(b'\n', chunk).count() as u64;
+        self.last = chunk.last().copied().or(self.last);
+        self.size.add_chunk(chunk, &mut self.in_word);
+        self.measure_lines(chunk);
+        self.hash = chunk
+            .iter()
+            .fold(self.hash, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME));
+        if self.markers.is_empty() && self.complexity.is_none() && self.patterns.is_empty() {
+            return;
+        }
+        let mut start = 0;
+        for end in memchr_iter(b'\n', chunk) {
+            if self.partial.is_empty() {
+                self.scan_line(&chunk[start..end]);
+            } else {
+                self.extend_partial(&chunk[start..end]);
+                let line = std::mem::take(&mut self.partial);
+                self.scan_line(&line);
+            }
+            start = end + 1;
+        }
+        self.extend_partial(&chunk[start..]);
+    }
+
+    fn measure_lines(&mut self, chunk: &[u8]) {
+        let mut start = 0;
+        for end in memchr_iter(b'\n', chunk) {
+            let line = &chunk[start..end];
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            self.longest_line = self.longest_line.max(self.line_chars + count_chars(line));
+            self.line_chars = 0;
+            start = end + 1;
+        }
+        self.line_chars += count_chars(&chunk[start..]);
+    }
+
+    fn extend_partial(&mut self, bytes: &[u8]) {
+        let room = MAX_TODO_LINE.saturating_sub(self.partial.len());
+        self.partial.extend_from_slice(&bytes[..bytes.len().min(room)]);
+    }
+
+    fn scan_line(&mut self, line: &[u8]) {
+        self.todo_line += 1;
+        if let Some(complexity) = &mut self.complexity {
+            complexity.line(line);
+        }
+        for (_, finders, matched) in &mut self.patterns {
+            if finders.iter().any(|finder| finder.find(line).is_some()) {
+                *matched += 1;
+            }
+        }
+        let number = self.todo_line;
+        self.todos.extend(find_todos(line, self.markers).into_iter().map(|mut todo| {
+            todo.line = number;
+            todo
+        }));
+    }
+
+    fn finish(mut self, generated: bool, license: Option<String>) -> FileCount {
+        if !self.partial.is_empty() {
+            let line = std::mem::take(&mut self.partial);
+            self.scan_line(&line);
+        }
+        let lines = match self.last {
+            None => 0,
+            Some(b'\n') => self.newlines,
+            Some(_) => self.newlines + 1,
+        };
+        FileCount {
+            lines,
+            size: self.size,
+            longest_line: self.longest_line.max(self.line_chars),
+            hash: self.hash,
+            complexity: self.complexity.map(ComplexityCounter::finish),
+            license,
+            generated,
+            todos: self.todos,
+            docs_lines: 0,
+            metrics: self
+                .patterns
+                .into_iter()
+                .map(|(name, _, matched)| (name.to_string(), matched))
+                .collect(),
+        }
+    }
+}
+
+/// Generators put their marker in the comments at the top of the file, which may open with
+/// a license of any length first (Go only asks for it before the first line of code). Only
+/// that leading comment block is searched, so docs that merely mention "DO NOT EDIT" further
+/// down stay hand-written.
+fn has_generated_marker(buf: &[u8]) -> bool {
+    leading_comments(buf)
+        .iter()
+        .any(|line| GENERATED_MARKERS.iter().any(|marker| contains(line, marker)))
+}
+
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle.as_bytes())
+}
+
+/// The lines of the comment block at the top of a file, blank ones included, up to the
+/// first line of anything else.
+pub fn leading_comments(buf: &[u8]) -> Vec<&[u8]> {
+    let mut lines = Vec::new();
+    let mut block_end = None;
+    for line in buf[..buf.len().min(SNIFF_LEN)].split(|byte| *byte == b'\n') {
+        let trimmed = line.trim_ascii();
+        let in_comments = match block_end {
+            Some(end) => {
+                if contains(trimmed, end) {
+                    block_end = None;
+                }
+                true
+            }
+            None => match LEADING_BLOCK_COMMENTS.iter().find(|(start, _)| trimmed.starts_with(start.as_bytes())) {
+                Some((start, end)) => {
+                    if !contains(&trimmed[start.len()..], end) {
+                        block_end = Some(*end);
+                    }
+                    true
+                }
+                None => {
+                    trimmed.is_empty() || LEADING_COMMENTS.iter().any(|comment| trimmed.starts_with(comment.as_bytes()))
+                }
+            },
+        };
+        if !in_comments {
+            break;
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Named like `app.min.js` or `app-min.css`, or code with lines long enough on average that
+/// no one wrote them by hand.
+fn is_minified(relative: &Path, category: Category, count: &FileCount) -> bool {
+    let stem = relative.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    stem.ends_with(".min")
+        || stem.ends_with("-min")
+        || (category == Category::Code && count.size.mean_line(count.lines) > MINIFIED_MEAN_LINE)
+}
+
+fn is_vendored(relative: &Path) -> bool {
+    relative.parent().is_some_and(|dir| {
+        dir.components()
+            .any(|component| VENDOR_DIRS.contains(&&*component.as_os_str().to_string_lossy()))
+    })
+}
+
+/// Treats content as binary if its head has a NUL byte or is mostly invalid UTF-8.
+/// Legacy 8-bit encodings only have the odd invalid byte, so they still count as text.
+fn is_binary(buf: &[u8]) -> bool {
+    let head = &buf[..buf.len().min(SNIFF_LEN)];
+    if head.contains(&0) {
+        return true;
+    }
+    let control = head
+        .iter()
+        .filter(|byte| (**byte < 0x20 && !b"\t\n\r\x0c\x1b".contains(byte)) || **byte == 0x7f)
+        .count();
+    let invalid = head.utf8_chunks().map(|chunk| chunk.invalid().len()).sum::<usize>();
+    // Legacy 8-bit and CJK encodings aren't valid UTF-8 either, but they're free of control bytes.
+    control * 20 > head.len() || (invalid * 10 > head.len() * 3 && control * 100 > head.len())
+}
+
+fn is_code_file(path: &Path, settings: &Settings) -> bool {
+    let languages = &settings.languages;
+    if let Some(ext) = languages.by_filename(path).and_then(Language::extension) {
+        return settings.extensions.matches(ext, languages);
+    }
+    let ext = match path.extension() {
+        Some(ext) => ext.to_string_lossy().to_lowercase(),
+        None => return false,
+    };
+    settings.extensions.matches(&ext, languages)
+}
+
+/// Detects extensionless scripts such as `bin/deploy` from their `#!` line.
+fn script_language<'a>(path: &Path, settings: &'a Settings) -> Option<&'a Language> {
+    if path.extension().is_some() {
+        return None;
+    }
+    let mut head = [0; 128];
+    let len = File::open(path).and_then(|mut file| file.read(&mut head)).ok()?;
+    shebang_language(&head[..len], settings)
+}
+
+/// The language a `#!` line at the start of `head` names, if it's one being counted.
+fn shebang_language<'a>(head: &[u8], settings: &'a Settings) -> Option<&'a Language> {
+    let language = settings.languages.by_shebang(head)?;
+    settings
+        .extensions
+        .matches(language.extension()?, &settings.languages)
+        .then_some(language)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ExtensionFilter {
+    pub extra: Vec<String>,
+    pub only: Vec<String>,
+    pub excluded: Vec<String>,
+}
+
+impl ExtensionFilter {
+    fn matches(&self, ext: &str, languages: &LanguageDb) -> bool {
+        if self.excluded.iter().any(|excluded| excluded == ext) {
+            return false;
+        }
+        if !self.only.is_empty() {
+            return self.only.iter().any(|allowed| allowed == ext);
+        }
+        languages.by_extension(ext).is_some() || self.extra.iter().any(|allowed| allowed == ext)
+    }
+
+    pub fn describe(&self) -> String {
+        let mut out = if self.only.is_empty() {
+            let mut out = String::from("all known");
+            if !self.extra.is_empty() {
+                out.push_str(&format!(" + {}", self.extra.join(", ")));
+            }
+            out
+        } else {
+            format!("only {}", self.only.join(", "))
+        };
+        if !self.excluded.is_empty() {
+            out.push_str(&format!(", without {}", self.excluded.join(", ")));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_a_generated_marker_after_a_license() {
+        let text = b"// Copyright 2024 The Authors.\n// Licensed under the Apache License.\n\n// Code generated by protoc-gen-go. DO NOT EDIT.\n\npackage api\n";
+        assert!(has_generated_marker(text));
+        assert!(has_generated_marker(b"#!/bin/sh\n# @generated by tools/gen.sh\necho hi\n"));
+    }
+
+    #[test]
+    fn finds_a_generated_marker_in_a_block_comment() {
+        assert!(has_generated_marker(b"/*\n * Licensed under MIT.\n *\n * @generated\n */\nint x;\n"));
+        assert!(has_generated_marker(b"<?xml version=\"1.0\"?>\n<!-- <auto-generated>\n     by a tool\n</auto-generated> -->\n<root/>\n"));
+        assert!(has_generated_marker(b"\"\"\"\nThis file is automatically generated.\n\"\"\"\nimport os\n"));
+    }
+
+    #[test]
+    fn ignores_markers_below_the_first_line_of_code() {
+        assert!(!has_generated_marker(b"// Hand written.\nfn main() {}\n// DO NOT EDIT\n"));
+        assert!(!has_generated_marker(b"/* one line */\nconst NOTE: &str = \"@generated\";\n"));
+        assert!(!has_generated_marker(b"# Generated files\n\nFiles marked DO NOT EDIT are skipped.\n"));
+    }
+
+    #[test]
+    fn leading_comments_stop_at_code() {
+        let text = b"/* a\n   b */\n\n// c\nlet d = 1;\n// e\n";
+        let lines: Vec<&[u8]> = vec![b"/* a", b"   b */", b"", b"// c"];
+        assert_eq!(leading_comments(text), lines);
+    }
+
+    #[test]
+    fn nul_or_control_bytes_make_a_file_binary() {
+        assert!(is_binary(b"\x7fELF\x02\x01\x01\0\0\0"));
+        assert!(is_binary(b"PK\x03\x04\x14\x00\x08\x00"));
+        assert!(is_binary(&[0x01, 0x02, 0x03, b'a', 0x04, 0x05, 0x06, 0x07]));
+    }
+
+    #[test]
+    fn legacy_8_bit_text_is_not_binary() {
+        assert!(!is_binary(b""));
+        assert!(!is_binary(b"fn main() {\n\tprintln!(\"caf\xC3\xA9\");\r\n}\n"));
+        // Latin-1 and Shift_JIS: invalid UTF-8, but no control bytes.
+        assert!(!is_binary(b"/* r\xE9sum\xE9 du caf\xE9 */\nint x;\n"));
+        assert!(!is_binary(b"// \x93\xfa\x96\x7b\x8c\xea\nint y;\n"));
+        // Form feeds and ANSI colours turn up in plain text too.
+        assert!(!is_binary(b"page one\x0cpage two \x1b[1mbold\x1b[0m\n"));
+    }
+}
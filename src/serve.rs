@@ -0,0 +1,530 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use codecounter::cache::LineCache;
+use codecounter::cli::{Cli, ServeArgs};
+use codecounter::config::Settings;
+use codecounter::export::{export, ExportFormat};
+use codecounter::history::History;
+use codecounter::scan::{scan, ScanResult};
+
+/// Rescan interval when neither `--refresh` nor `refresh-interval` is set.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+const MAX_REQUEST_BODY: usize = 64 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Where `POST /api/roots` takes its bearer token from; without it one is made up at start.
+const TOKEN_VAR: &str = "CODECOUNTER_TOKEN";
+
+struct Snapshot {
+    scan: ScanResult,
+    took: Duration,
+}
+
+/// What the scanner works on. `POST /api/roots` swaps in new roots, with the settings
+/// resolved for them, and bumps `generation` so the scanner starts a fresh line cache.
+struct Target {
+    roots: Vec<PathBuf>,
+    base: PathBuf,
+    settings: Arc<Settings>,
+    generation: u64,
+}
+
+struct ServerState {
+    /// The command line the server was started with, for resolving the settings of new roots.
+    cli: Cli,
+    target: Mutex<Target>,
+    latest: Mutex<Option<Snapshot>>,
+    history: Mutex<History>,
+    scanning: AtomicBool,
+    /// Stops the running scan when the roots change under it.
+    cancel: AtomicBool,
+    /// Wakes the scanner before its interval is up.
+    wake: Sender<()>,
+    /// Names the server answers to in `Host` and `Origin`, so a web page can't reach it
+    /// through a domain of its own that resolves here (DNS rebinding).
+    hosts: Vec<String>,
+    /// Directories `POST /api/roots` may point the server at, and what's under them.
+    allowed_roots: Vec<PathBuf>,
+    /// The bearer token `POST /api/roots` needs.
+    token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RootsRequest {
+    roots: Vec<PathBuf>,
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn text(status: &'static str, body: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    fn json(status: &'static str, value: &impl serde::Serialize) -> Self {
+        match serde_json::to_vec_pretty(value) {
+            Ok(mut body) => {
+                body.push(b'\n');
+                Self {
+                    status,
+                    content_type: "application/json",
+                    body,
+                }
+            }
+            Err(err) => Self::text("500 Internal Server Error", &format!("{}\n", err)),
+        }
+    }
+}
+
+/// Rescans `roots` on a timer and serves the latest result over HTTP until killed. The
+/// `/api` endpoints also let a client rescan now, point the server at other directories and
+/// read the history of the ones scanned.
+pub fn run(
+    cli: &Cli,
+    args: &ServeArgs,
+    roots: Vec<PathBuf>,
+    base: &Path,
+    settings: Settings,
+) -> Result<(), Box<dyn Error>> {
+    let address = format!("{}:{}", args.host, args.port);
+    let listener = TcpListener::bind(&address).map_err(|err| format!("{}: {}", address, err))?;
+    let interval = settings.refresh.unwrap_or(DEFAULT_INTERVAL);
+    eprintln!(
+        "Serving http://{}/metrics and /api/scan, rescanning every {}",
+        address,
+        codecounter::format::format_duration(interval)
+    );
+
+    let mut hosts = vec!["localhost".to_string(), "127.0.0.1".to_string(), "[::1]".to_string()];
+    // An IPv6 address is bracketed in a `Host` header.
+    hosts.extend(args.allow_host.iter().chain([&args.host]).map(|host| {
+        if host.contains(':') {
+            format!("[{}]", host.trim_matches(['[', ']']))
+        } else {
+            host.to_ascii_lowercase()
+        }
+    }));
+    let mut allowed_roots = roots.clone();
+    for dir in &args.allow_root {
+        allowed_roots.push(canonical_dir(dir)?);
+    }
+    let token = match std::env::var(TOKEN_VAR) {
+        Ok(token) if !token.is_empty() => token,
+        _ => {
+            let token = new_token().map_err(|err| format!("could not make a token for POST /api/roots: {}", err))?;
+            eprintln!("POST /api/roots needs the header `Authorization: Bearer {}` ({} sets another)", token, TOKEN_VAR);
+            token
+        }
+    };
+
+    let (wake, woken) = mpsc::channel();
+    let state = Arc::new(ServerState {
+        cli: cli.clone(),
+        history: Mutex::new(History::load(&roots, &settings)?),
+        target: Mutex::new(Target {
+            roots,
+            base: base.to_path_buf(),
+            settings: Arc::new(settings),
+            generation: 0,
+        }),
+        latest: Mutex::new(None),
+        scanning: AtomicBool::new(false),
+        cancel: AtomicBool::new(false),
+        wake,
+        hosts,
+        allowed_roots,
+        token,
+    });
+    let scanner = Arc::clone(&state);
+    thread::spawn(move || scan_loop(&scanner, &woken, interval));
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let state = Arc::clone(&state);
+        thread::spawn(move || {
+            if let Err(err) = handle(stream, &state) {
+                eprintln!("Request failed: {}", err);
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Scans whatever the target is now, then waits out the interval or a wake-up from the API.
+fn scan_loop(state: &ServerState, woken: &Receiver<()>, interval: Duration) {
+    let (mut cache_generation, mut cache) = {
+        let target = lock(&state.target);
+        (target.generation, LineCache::new(&target.base, &target.settings))
+    };
+    // Scans on start-up and on a wake-up from the API are asked for; timed ones aren't.
+    let mut requested = true;
+    loop {
+        let (roots, settings, generation) = {
+            let target = lock(&state.target);
+            if target.generation != cache_generation {
+                cache = LineCache::new(&target.base, &target.settings);
+                cache_generation = target.generation;
+            }
+            // Under the lock, so a change of roots can't slip in between and cancel this scan.
+            state.cancel.store(false, Ordering::Relaxed);
+            (target.roots.clone(), Arc::clone(&target.settings), target.generation)
+        };
+        state.scanning.store(true, Ordering::Relaxed);
+        let started = Instant::now();
+        let result = scan(&roots, &settings, &mut cache, &|_| {}, &state.cancel);
+        state.scanning.store(false, Ordering::Relaxed);
+        // A scan the roots changed under is thrown away, finished or not. The target stays
+        // locked so they can't change while the scan is recorded.
+        let target = lock(&state.target);
+        if target.generation == generation {
+            match result {
+                Ok(scan) => {
+                    if let Err(err) = lock(&state.history).record(&scan, &settings.milestones, requested) {
+                        eprintln!("Could not record the scan in the history: {}", err);
+                    }
+                    *lock(&state.latest) = Some(Snapshot {
+                        scan,
+                        took: started.elapsed(),
+                    });
+                }
+                Err(err) => eprintln!("Scan failed: {}", err),
+            }
+        }
+        drop(target);
+        match woken.recv_timeout(interval) {
+            Ok(()) => requested = true,
+            Err(RecvTimeoutError::Timeout) => requested = false,
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        // Requests that came in during the scan are all answered by the next one.
+        while woken.try_recv().is_ok() {}
+    }
+}
+
+fn handle(mut stream: TcpStream, state: &ServerState) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let (head, body) = read_request(&mut stream)?;
+    let request_line = head.lines().next().unwrap_or_default();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or("/");
+    let path = target.split('?').next().unwrap_or(target);
+
+    let response = match refuse_host(&head, &state.hosts) {
+        Some(refused) => refused,
+        None => respond(method, path, &head, &body, state),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    )?;
+    if method != "HEAD" {
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()
+}
+
+/// Reads the request headers, up to the blank line that ends them, and the body after them
+/// as long as `Content-Length` says.
+fn read_request(stream: &mut TcpStream) -> io::Result<(String, Vec<u8>)> {
+    let mut data = Vec::new();
+    let mut buf = [0; 1024];
+    let end = loop {
+        if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+        let read = stream.read(&mut buf)?;
+        if read == 0 || data.len() >= MAX_REQUEST_HEAD {
+            break data.len();
+        }
+        data.extend_from_slice(&buf[..read]);
+    };
+    let head = String::from_utf8_lossy(&data[..end]).into_owned();
+    let length = header(&head, "content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0)
+        .min(MAX_REQUEST_BODY);
+    let mut body = data.split_off(end);
+    while body.len() < length {
+        let read = stream.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&buf[..read]);
+    }
+    body.truncate(length);
+    Ok((head, body))
+}
+
+/// The value of the header `name` in the request head, if it has one.
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.trim())
+}
+
+/// The host name of a `Host` header or an origin's authority, without the port.
+fn host_name(authority: &str) -> String {
+    let name = match authority.rsplit_once(':') {
+        Some((name, port)) if !port.contains(']') => name,
+        _ => authority,
+    };
+    name.to_ascii_lowercase()
+}
+
+/// Refuses requests addressed to a name the server doesn't go by, and ones a web page on
+/// another site sent. A browser always sends the `Host` it resolved, so a page on a domain
+/// that rebinds to this address is caught by it even when it's same-origin to the browser.
+fn refuse_host(head: &str, hosts: &[String]) -> Option<Response> {
+    let known = |authority: &str| hosts.contains(&host_name(authority));
+    match header(head, "host") {
+        Some(host) if known(host) => {}
+        Some(_) => return Some(Response::text("403 Forbidden", "unexpected Host header\n")),
+        None => return Some(Response::text("400 Bad Request", "the Host header is missing\n")),
+    }
+    match header(head, "origin") {
+        Some(origin) if !origin.split_once("://").is_some_and(|(_, authority)| known(authority)) => {
+            Some(Response::text("403 Forbidden", "requests from other sites are refused\n"))
+        }
+        _ => None,
+    }
+}
+
+fn respond(method: &str, path: &str, head: &str, body: &[u8], state: &ServerState) -> Response {
+    let format = match (method, path) {
+        ("GET" | "HEAD", "/metrics") => ExportFormat::Prometheus,
+        ("GET" | "HEAD", "/api/scan") => ExportFormat::Json,
+        ("GET" | "HEAD", "/api/status") => return status(state),
+        ("GET" | "HEAD", "/api/history") => return Response::json("200 OK", &lock(&state.history).entries()),
+        ("POST", "/api/rescan") => {
+            let _ = state.wake.send(());
+            return Response::json("202 Accepted", &serde_json::json!({ "rescan": "queued" }));
+        }
+        ("POST", "/api/roots") => return change_roots(head, body, state),
+        ("GET" | "HEAD", "/") => {
+            return Response::text(
+                "200 OK",
+                "codecounter: GET /metrics, /api/scan, /api/status and /api/history; POST /api/rescan and /api/roots\n",
+            );
+        }
+        (_, "/" | "/metrics" | "/api/scan" | "/api/status" | "/api/history" | "/api/rescan" | "/api/roots") => {
+            return Response::text("405 Method Not Allowed", "method not allowed here\n");
+        }
+        _ => return Response::text("404 Not Found", "not found\n"),
+    };
+
+    let latest = lock(&state.latest);
+    let Some(snapshot) = latest.as_ref() else {
+        return Response::text("503 Service Unavailable", "the first scan is still running\n");
+    };
+    let mut body = Vec::new();
+    if let Err(err) = export(format, &snapshot.scan, &mut body) {
+        return Response::text("500 Internal Server Error", &format!("{}\n", err));
+    }
+    if format == ExportFormat::Prometheus {
+        body.extend_from_slice(
+            format!(
+                "# HELP codecounter_scan_duration_seconds How long the last scan took.\n\
+                 # TYPE codecounter_scan_duration_seconds gauge\n\
+                 codecounter_scan_duration_seconds {:.3}\n",
+                snapshot.took.as_secs_f64()
+            )
+            .as_bytes(),
+        );
+    }
+    Response {
+        status: "200 OK",
+        content_type: match format {
+            ExportFormat::Json => "application/json",
+            _ => "text/plain; version=0.0.4; charset=utf-8",
+        },
+        body,
+    }
+}
+
+/// What's being scanned, whether a scan is running, and when the last one finished.
+fn status(state: &ServerState) -> Response {
+    let roots = lock(&state.target).roots.clone();
+    let latest = lock(&state.latest);
+    Response::json(
+        "200 OK",
+        &serde_json::json!({
+            "roots": roots,
+            "scanning": state.scanning.load(Ordering::Relaxed),
+            "last_scan": latest.as_ref().map(|snapshot| serde_json::json!({
+                "scanned_at": snapshot.scan.scanned_at,
+                "seconds": snapshot.took.as_secs_f64(),
+                "lines": snapshot.scan.lines,
+                "files": snapshot.scan.files,
+            })),
+        }),
+    )
+}
+
+/// Points the server at the directories in a `{"roots": [...]}` body, with the settings
+/// their config files give, cancelling any scan of the old ones and starting one of these.
+/// Only a client with the token may, only with a JSON body (which a web form can't send
+/// without asking first), and only at the directories the server was allowed.
+fn change_roots(head: &str, body: &[u8], state: &ServerState) -> Response {
+    let authorized = header(head, "authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| same_secret(token.trim().as_bytes(), state.token.as_bytes()));
+    if !authorized {
+        return Response::text("403 Forbidden", "a valid `Authorization: Bearer` token is needed\n");
+    }
+    let json = header(head, "content-type")
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("application/json"));
+    if !json {
+        return Response::text("415 Unsupported Media Type", "the body must be application/json\n");
+    }
+    let request = match serde_json::from_slice::<RootsRequest>(body) {
+        Ok(request) if !request.roots.is_empty() => request,
+        Ok(_) => return Response::text("400 Bad Request", "roots: at least one directory is needed\n"),
+        Err(err) => return Response::text("400 Bad Request", &format!("{}\n", err)),
+    };
+    let roots = match request.roots.iter().map(|root| canonical_dir(root)).collect::<Result<Vec<_>, _>>() {
+        Ok(roots) => roots,
+        Err(err) => return Response::text("400 Bad Request", &format!("{}\n", err)),
+    };
+    if let Some(root) = roots
+        .iter()
+        .find(|root| !state.allowed_roots.iter().any(|allowed| root.starts_with(allowed)))
+    {
+        return Response::text(
+            "403 Forbidden",
+            &format!("{}: not under a directory the server was started with or --allow-root\n", root.display()),
+        );
+    }
+    let base = match roots.as_slice() {
+        [root] => root.clone(),
+        _ => lock(&state.target).base.clone(),
+    };
+    let settings = match Settings::resolve(&state.cli, &base) {
+        Ok(settings) => settings,
+        Err(err) => return Response::text("400 Bad Request", &format!("{}\n", err)),
+    };
+    let history = match History::load(&roots, &settings) {
+        Ok(history) => history,
+        Err(err) => return Response::text("500 Internal Server Error", &format!("{}\n", err)),
+    };
+    {
+        let mut target = lock(&state.target);
+        target.roots.clone_from(&roots);
+        target.base = base;
+        target.settings = Arc::new(settings);
+        target.generation += 1;
+        *lock(&state.history) = history;
+        *lock(&state.latest) = None;
+        state.cancel.store(true, Ordering::Relaxed);
+    }
+    let _ = state.wake.send(());
+    Response::json("202 Accepted", &serde_json::json!({ "roots": roots, "rescan": "queued" }))
+}
+
+fn canonical_dir(path: &Path) -> Result<PathBuf, String> {
+    let dir = fs::canonicalize(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    if !dir.is_dir() {
+        return Err(format!("{}: not a directory", path.display()));
+    }
+    Ok(dir)
+}
+
+/// Compares a token without stopping at the first difference, which would tell by the time
+/// it took how much of it was right.
+fn same_secret(given: &[u8], expected: &[u8]) -> bool {
+    given.len() == expected.len() && given.iter().zip(expected).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// 128 random bits from the operating system, in hex.
+fn new_token() -> Result<String, getrandom::Error> {
+    let mut bytes = [0; 16];
+    getrandom::fill(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosts() -> Vec<String> {
+        ["localhost", "127.0.0.1", "[::1]", "counter.lan"].map(String::from).to_vec()
+    }
+
+    fn refused(head: &str) -> Option<&'static str> {
+        refuse_host(head, &hosts()).map(|response| response.status)
+    }
+
+    #[test]
+    fn host_names_drop_the_port() {
+        assert_eq!(host_name("localhost:8080"), "localhost");
+        assert_eq!(host_name("Counter.LAN"), "counter.lan");
+        assert_eq!(host_name("[::1]:8080"), "[::1]");
+        assert_eq!(host_name("[::1]"), "[::1]");
+    }
+
+    #[test]
+    fn accepts_known_hosts_and_origins() {
+        assert_eq!(refused("GET /metrics HTTP/1.1\r\nHost: localhost:8080\r\n"), None);
+        assert_eq!(refused("GET / HTTP/1.1\r\nhost: [::1]:8080\r\n"), None);
+        assert_eq!(refused("POST /api/rescan HTTP/1.1\r\nHost: counter.lan\r\nOrigin: http://counter.lan:8080\r\n"), None);
+    }
+
+    #[test]
+    fn refuses_unknown_hosts_and_origins() {
+        assert_eq!(refused("GET / HTTP/1.1\r\n"), Some("400 Bad Request"));
+        assert_eq!(refused("GET / HTTP/1.1\r\nHost: rebound.example:8080\r\n"), Some("403 Forbidden"));
+        assert_eq!(
+            refused("POST /api/rescan HTTP/1.1\r\nHost: localhost:8080\r\nOrigin: https://evil.example\r\n"),
+            Some("403 Forbidden")
+        );
+        assert_eq!(refused("GET / HTTP/1.1\r\nHost: localhost\r\nOrigin: null\r\n"), Some("403 Forbidden"));
+    }
+
+    #[test]
+    fn compares_secrets_in_full() {
+        assert!(same_secret(b"0123456789abcdef", b"0123456789abcdef"));
+        assert!(!same_secret(b"0123456789abcdee", b"0123456789abcdef"));
+        assert!(!same_secret(b"0123456789abcde", b"0123456789abcdef"));
+        assert!(!same_secret(b"", b"0123456789abcdef"));
+    }
+
+    #[test]
+    fn tokens_are_random_hex() {
+        let token = new_token().unwrap();
+        assert_eq!(token.len(), 32);
+        assert!(token.bytes().all(|byte| byte.is_ascii_hexdigit()));
+        assert_ne!(token, new_token().unwrap());
+    }
+}
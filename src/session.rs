@@ -0,0 +1,62 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use codecounter::config::data_home;
+use codecounter::scan::{ScanResult, FNV_OFFSET, FNV_PRIME};
+
+use crate::{LanguageChart, Sort, View};
+
+const SESSION_DIR: &str = "sessions";
+
+/// What the TUI was showing when it was last closed on a set of roots, so the next launch
+/// can put the old count up straight away while the new scan runs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub roots: Vec<PathBuf>,
+    pub scan: ScanResult,
+    pub view: View,
+    pub file_sort: Sort,
+    pub language_sort: Sort,
+    pub language_chart: LanguageChart,
+    pub show_estimate: bool,
+    pub show_errors: bool,
+    pub compact_number: bool,
+    #[serde(default)]
+    pub all_categories: bool,
+    /// By name, since the fonts on offer can change between runs.
+    pub font: String,
+}
+
+impl Session {
+    /// The session last saved for exactly `roots`. A missing file, or one written by a version
+    /// with a different scan format, is no session at all.
+    pub fn load(roots: &[PathBuf]) -> Option<Self> {
+        let raw = fs::read(session_path(roots)?).ok()?;
+        serde_json::from_slice::<Self>(&raw).ok().filter(|session| session.roots == roots)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = session_path(&self.roots) else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Written aside and renamed, so quitting twice at once can't leave half a file.
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, serde_json::to_vec(self)?)?;
+        fs::rename(&partial, &path)
+    }
+}
+
+/// One file per set of roots, named after a hash of them.
+fn session_path(roots: &[PathBuf]) -> Option<PathBuf> {
+    let key = serde_json::to_string(roots).ok()?;
+    let hash = key
+        .bytes()
+        .fold(FNV_OFFSET, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME));
+    Some(data_home()?.join("codecounter").join(SESSION_DIR).join(format!("{:016x}.json", hash)))
+}
@@ -0,0 +1,532 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use ratatui::backend::TestBackend;
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::style::{Color, Modifier};
+use ratatui::Terminal;
+
+use codecounter::scan::ScanResult;
+
+use crate::App;
+use crate::ui::draw_snapshot;
+
+/// Pixels per terminal cell in a PNG, and user units per cell in an SVG.
+const CELL_WIDTH: usize = 12;
+const CELL_HEIGHT: usize = 24;
+/// Each dot of the pixel font is drawn as a square this many pixels wide, this far into the cell.
+const FONT_SCALE: usize = 2;
+const FONT_LEFT: usize = 1;
+const FONT_TOP: usize = 4;
+/// Monospace fonts an SVG asks for, most likely to be installed last.
+const SVG_FONTS: &str = "ui-monospace, Menlo, Consolas, 'DejaVu Sans Mono', monospace";
+/// Columns and rows drawn when the terminal's size can't be read; `--image-size` defaults to the same.
+pub const DEFAULT_SIZE: (u16, u16) = (100, 32);
+/// Thickness of the box-drawing lines.
+const LINE: usize = 2;
+/// What the terminal's own colors are drawn as, where the theme leaves them to it.
+const DEFAULT_FG: Rgb = [0xd0, 0xd0, 0xd0];
+const DEFAULT_BG: Rgb = [0x1c, 0x1c, 0x1c];
+/// The 16 ANSI colors as xterm draws them.
+const ANSI: [Rgb; 16] = [
+    [0x00, 0x00, 0x00],
+    [0xcd, 0x00, 0x00],
+    [0x00, 0xcd, 0x00],
+    [0xcd, 0xcd, 0x00],
+    [0x00, 0x00, 0xee],
+    [0xcd, 0x00, 0xcd],
+    [0x00, 0xcd, 0xcd],
+    [0xe5, 0xe5, 0xe5],
+    [0x7f, 0x7f, 0x7f],
+    [0xff, 0x00, 0x00],
+    [0x00, 0xff, 0x00],
+    [0xff, 0xff, 0x00],
+    [0x5c, 0x5c, 0xff],
+    [0xff, 0x00, 0xff],
+    [0x00, 0xff, 0xff],
+    [0xff, 0xff, 0xff],
+];
+
+type Rgb = [u8; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+}
+
+impl ImageFormat {
+    /// SVG for a `.svg` file name, PNG for anything else.
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("svg") => ImageFormat::Svg,
+            _ => ImageFormat::Png,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Svg => "svg",
+        }
+    }
+}
+
+pub fn default_image_path(dir: &Path, format: ImageFormat, scan: &ScanResult) -> PathBuf {
+    dir.join(format!(
+        "codecounter-{}.{}",
+        scan.scanned_at.format("%Y%m%d-%H%M%S"),
+        format.extension()
+    ))
+}
+
+/// Draws the summary into an offscreen buffer of `columns × rows` cells and saves it as an
+/// image: every cell of the text drawn with its colors, the big number included.
+pub fn export_image(app: &mut App, format: ImageFormat, (columns, rows): (u16, u16), path: &Path) -> io::Result<()> {
+    let mut terminal = Terminal::new(TestBackend::new(columns, rows))?;
+    terminal.draw(|frame| draw_snapshot(frame, app))?;
+    let buffer = terminal.backend().buffer();
+    let mut out = BufWriter::new(File::create(path)?);
+    match format {
+        ImageFormat::Png => write_png(buffer, &mut out)?,
+        ImageFormat::Svg => write_svg(buffer, &mut out)?,
+    }
+    out.flush()
+}
+
+/// A cell's foreground and background, swapped for reversed text.
+fn colors(cell: &Cell) -> (Rgb, Rgb) {
+    let fg = rgb(cell.fg, DEFAULT_FG);
+    let bg = rgb(cell.bg, DEFAULT_BG);
+    if cell.modifier.contains(Modifier::REVERSED) {
+        (bg, fg)
+    } else {
+        (fg, bg)
+    }
+}
+
+fn rgb(color: Color, default: Rgb) -> Rgb {
+    match color {
+        Color::Reset => default,
+        Color::Black => ANSI[0],
+        Color::Red => ANSI[1],
+        Color::Green => ANSI[2],
+        Color::Yellow => ANSI[3],
+        Color::Blue => ANSI[4],
+        Color::Magenta => ANSI[5],
+        Color::Cyan => ANSI[6],
+        Color::Gray => ANSI[7],
+        Color::DarkGray => ANSI[8],
+        Color::LightRed => ANSI[9],
+        Color::LightGreen => ANSI[10],
+        Color::LightYellow => ANSI[11],
+        Color::LightBlue => ANSI[12],
+        Color::LightMagenta => ANSI[13],
+        Color::LightCyan => ANSI[14],
+        Color::White => ANSI[15],
+        Color::Rgb(red, green, blue) => [red, green, blue],
+        Color::Indexed(index @ 0..=15) => ANSI[usize::from(index)],
+        // The 6×6×6 color cube, then 24 grays.
+        Color::Indexed(index @ 16..=231) => {
+            let level = |step: u8| if step == 0 { 0 } else { 55 + step * 40 };
+            let index = index - 16;
+            [level(index / 36), level(index / 6 % 6), level(index % 6)]
+        }
+        Color::Indexed(index) => [8 + (index - 232) * 10; 3],
+    }
+}
+
+/// Part of a cell filled with the foreground color, in pixels from its top left. `alpha`
+/// (out of 255) is below 255 only for the shade characters.
+#[derive(Debug, Clone, Copy)]
+struct Fill {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    alpha: u8,
+}
+
+impl Fill {
+    fn solid(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+            alpha: 255,
+        }
+    }
+}
+
+/// How the block, braille, line and other drawing characters the summary is made of fill
+/// their cell, so they join up into solid shapes whatever font the image is viewed with.
+/// Anything else is text.
+fn fills(ch: char) -> Option<Vec<Fill>> {
+    let (w, h) = (CELL_WIDTH, CELL_HEIGHT);
+    let fills = match ch {
+        '█' => vec![Fill::solid(0, 0, w, h)],
+        '▀' => vec![Fill::solid(0, 0, w, h / 2)],
+        '▔' => vec![Fill::solid(0, 0, w, h / 8)],
+        '▐' => vec![Fill::solid(w / 2, 0, w - w / 2, h)],
+        '▕' => vec![Fill::solid(w - w / 8, 0, w / 8, h)],
+        // ▁ to ▇: the lower eighths, ▄ among them.
+        '\u{2581}'..='\u{2587}' => {
+            let height = h * (ch as usize - 0x2580) / 8;
+            vec![Fill::solid(0, h - height, w, height)]
+        }
+        // ▉ down to ▏: the left eighths, ▌ among them.
+        '\u{2589}'..='\u{258f}' => vec![Fill::solid(0, 0, w * (0x2590 - ch as usize) / 8, h)],
+        '░' | '▒' | '▓' => {
+            let alpha = [64, 128, 192][ch as usize - 0x2591];
+            vec![Fill { alpha, ..Fill::solid(0, 0, w, h) }]
+        }
+        // The quadrants ▖ to ▟, as upper left, upper right, lower left and lower right.
+        '\u{2596}'..='\u{259f}' => {
+            let quadrants = [0b0010, 0b0001, 0b1000, 0b1011, 0b1001, 0b1110, 0b1101, 0b0100, 0b0110, 0b0111];
+            let set = quadrants[ch as usize - 0x2596];
+            [(0, 0), (w / 2, 0), (0, h / 2), (w / 2, h / 2)]
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| set & (0b1000 >> index) != 0)
+                .map(|(_, (x, y))| Fill::solid(*x, *y, w / 2, h / 2))
+                .collect()
+        }
+        // Braille: bits 0-2 and 6 are the left column's dots from the top, 3-5 and 7 the right's.
+        '\u{2800}'..='\u{28ff}' => {
+            let bits = ch as usize - 0x2800;
+            let dot = w / 3;
+            [(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2), (0, 3), (1, 3)]
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| bits & (1 << bit) != 0)
+                .map(|(_, (column, row))| {
+                    Fill::solid(column * w / 2 + (w / 2 - dot) / 2, row * h / 4 + (h / 4 - dot) / 2, dot, dot)
+                })
+                .collect()
+        }
+        '·' => vec![Fill::solid((w - 4) / 2, (h - 4) / 2, 4, 4)],
+        '•' | '●' => vec![Fill::solid(w / 4, h / 2 - w / 4, w / 2, w / 2)],
+        '…' => (0..3).map(|dot| Fill::solid(1 + dot * 4, h - 8, 2, 2)).collect(),
+        '▲' | '▼' => (0..w / 2)
+            .map(|step| {
+                let row = if ch == '▲' { h / 4 + step } else { h * 3 / 4 - step };
+                Fill::solid(w / 2 - step, row, step * 2, 1)
+            })
+            .collect(),
+        _ => return box_line(ch),
+    };
+    Some(fills)
+}
+
+/// The box-drawing characters as lines from the middle of the cell out to its edges; the
+/// rounded, heavy and double ones are drawn like the plain ones.
+fn box_line(ch: char) -> Option<Vec<Fill>> {
+    let (up, down, left, right) = match ch {
+        '─' | '━' | '═' => (false, false, true, true),
+        '│' | '┃' | '║' => (true, true, false, false),
+        '┌' | '╭' | '┏' | '╔' => (false, true, false, true),
+        '┐' | '╮' | '┓' | '╗' => (false, true, true, false),
+        '└' | '╰' | '┗' | '╚' => (true, false, false, true),
+        '┘' | '╯' | '┛' | '╝' => (true, false, true, false),
+        '├' | '┣' | '╠' => (true, true, false, true),
+        '┤' | '┫' | '╣' => (true, true, true, false),
+        '┬' | '┳' | '╦' => (false, true, true, true),
+        '┴' | '┻' | '╩' => (true, false, true, true),
+        '┼' | '╋' | '╬' => (true, true, true, true),
+        _ => return None,
+    };
+    let (x, y) = ((CELL_WIDTH - LINE) / 2, (CELL_HEIGHT - LINE) / 2);
+    let mut fills = Vec::new();
+    if up {
+        fills.push(Fill::solid(x, 0, LINE, y + LINE));
+    }
+    if down {
+        fills.push(Fill::solid(x, y, LINE, CELL_HEIGHT - y));
+    }
+    if left {
+        fills.push(Fill::solid(0, y, x + LINE, LINE));
+    }
+    if right {
+        fills.push(Fill::solid(x, y, CELL_WIDTH - x, LINE));
+    }
+    Some(fills)
+}
+
+/// Runs of neighbouring cells in a row that share `key`, as `(first column, length, key)`.
+fn runs<K: PartialEq>(row: &[Cell], key: impl Fn(&Cell) -> K) -> Vec<(usize, usize, K)> {
+    let mut runs: Vec<(usize, usize, K)> = Vec::new();
+    for (column, cell) in row.iter().enumerate() {
+        let key = key(cell);
+        match runs.last_mut() {
+            Some((_, length, last)) if *last == key => *length += 1,
+            _ => runs.push((column, 1, key)),
+        }
+    }
+    runs
+}
+
+fn hex([red, green, blue]: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", red, green, blue)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// An SVG of the cells: backgrounds and drawing characters as rectangles, text as text in
+/// a monospace font, each run stretched to the width of its cells.
+fn write_svg(buffer: &Buffer, out: &mut dyn Write) -> io::Result<()> {
+    let columns = usize::from(buffer.area.width);
+    let (width, height) = (columns * CELL_WIDTH, usize::from(buffer.area.height) * CELL_HEIGHT);
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        width, height, width, height
+    )?;
+    writeln!(out, "<rect width=\"{}\" height=\"{}\" fill=\"{}\"/>", width, height, hex(DEFAULT_BG))?;
+    writeln!(
+        out,
+        "<g font-family=\"{}\" font-size=\"{}\" xml:space=\"preserve\">",
+        SVG_FONTS,
+        CELL_HEIGHT * 5 / 6
+    )?;
+    for (row, cells) in buffer.content.chunks(columns).enumerate() {
+        let top = row * CELL_HEIGHT;
+        for (column, length, bg) in runs(cells, |cell| colors(cell).1) {
+            if bg != DEFAULT_BG {
+                writeln!(
+                    out,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+                    column * CELL_WIDTH,
+                    top,
+                    length * CELL_WIDTH,
+                    CELL_HEIGHT,
+                    hex(bg)
+                )?;
+            }
+        }
+        // Shapes that line up along the row, like a border or a bar, go out as one rectangle.
+        let mut shapes = cells
+            .iter()
+            .enumerate()
+            .filter_map(|(column, cell)| {
+                let fills = cell.symbol().chars().next().and_then(fills)?;
+                let fg = colors(cell).0;
+                Some(fills.into_iter().map(move |fill| {
+                    let fill = Fill {
+                        x: column * CELL_WIDTH + fill.x,
+                        y: top + fill.y,
+                        ..fill
+                    };
+                    (fill, fg)
+                }))
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        shapes.sort_by_key(|(fill, fg)| (fill.y, fill.height, fill.alpha, *fg, fill.x));
+        let mut merged: Vec<(Fill, Rgb)> = Vec::new();
+        for (fill, fg) in shapes {
+            match merged.last_mut() {
+                Some((last, last_fg))
+                    if (last.y, last.height, last.alpha, *last_fg) == (fill.y, fill.height, fill.alpha, fg)
+                        && fill.x <= last.x + last.width =>
+                {
+                    last.width = last.width.max(fill.x + fill.width - last.x);
+                }
+                _ => merged.push((fill, fg)),
+            }
+        }
+        for (fill, fg) in merged {
+            write!(
+                out,
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"",
+                fill.x,
+                fill.y,
+                fill.width,
+                fill.height,
+                hex(fg)
+            )?;
+            if fill.alpha < 255 {
+                write!(out, " fill-opacity=\"{:.2}\"", f64::from(fill.alpha) / 255.0)?;
+            }
+            writeln!(out, "/>")?;
+        }
+        let text_runs = runs(cells, |cell| {
+            let text = cell.symbol().chars().next().is_none_or(|ch| fills(ch).is_none());
+            (text, colors(cell).0, cell.modifier.contains(Modifier::BOLD), cell.modifier.contains(Modifier::UNDERLINED))
+        });
+        for (column, length, (text, fg, bold, underlined)) in text_runs {
+            let run = &cells[column..column + length];
+            let content = run.iter().map(Cell::symbol).collect::<String>();
+            if !text || content.trim().is_empty() {
+                continue;
+            }
+            write!(
+                out,
+                "<text x=\"{}\" y=\"{}\" textLength=\"{}\" lengthAdjust=\"spacingAndGlyphs\" fill=\"{}\"",
+                column * CELL_WIDTH,
+                top + CELL_HEIGHT * 3 / 4,
+                length * CELL_WIDTH,
+                hex(fg)
+            )?;
+            if bold {
+                write!(out, " font-weight=\"bold\"")?;
+            }
+            if underlined {
+                write!(out, " text-decoration=\"underline\"")?;
+            }
+            writeln!(out, ">{}</text>", escape_xml(&content))?;
+        }
+    }
+    writeln!(out, "</g>")?;
+    writeln!(out, "</svg>")
+}
+
+/// A strip of the image being drawn for a PNG, three bytes to a pixel.
+struct Canvas {
+    width: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize, color: Rgb, alpha: u8) {
+        for row in y..y + height {
+            let start = (row * self.width + x) * 3;
+            for pixel in self.pixels[start..start + width * 3].chunks_mut(3) {
+                for (channel, value) in pixel.iter_mut().zip(color) {
+                    *channel = ((u16::from(value) * u16::from(alpha) + u16::from(*channel) * (255 - u16::from(alpha)))
+                        / 255) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// A PNG of the cells, with text in a built-in 5×7 pixel font. Characters outside ASCII
+/// that aren't drawing characters come out as `?`. It's drawn and compressed one row of
+/// cells at a time, so memory use doesn't grow with the height of the image.
+fn write_png(buffer: &Buffer, out: &mut dyn Write) -> io::Result<()> {
+    let columns = usize::from(buffer.area.width);
+    let (width, height) = (columns * CELL_WIDTH, usize::from(buffer.area.height) * CELL_HEIGHT);
+    let mut encoder = png::Encoder::new(out, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    let mut png = writer.stream_writer()?;
+    let mut strip = Canvas {
+        width,
+        pixels: vec![0; width * CELL_HEIGHT * 3],
+    };
+    for row in buffer.content.chunks(columns) {
+        for (column, cell) in row.iter().enumerate() {
+            draw_cell(&mut strip, column * CELL_WIDTH, cell);
+        }
+        png.write_all(&strip.pixels)?;
+    }
+    png.finish()?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// Draws `cell` into the strip of pixels for its row, `left` pixels in.
+fn draw_cell(strip: &mut Canvas, left: usize, cell: &Cell) {
+    let (fg, bg) = colors(cell);
+    strip.fill(left, 0, CELL_WIDTH, CELL_HEIGHT, bg, 255);
+    let Some(ch) = cell.symbol().chars().next() else {
+        return;
+    };
+    if let Some(fills) = fills(ch) {
+        for fill in fills {
+            strip.fill(left + fill.x, fill.y, fill.width, fill.height, fg, fill.alpha);
+        }
+        return;
+    }
+    let glyph = match ascii(ch) {
+        ch @ ' '..='~' => &FONT[ch as usize - 0x20],
+        _ if ch.is_whitespace() => return,
+        _ => &FONT[usize::from(b'?') - 0x20],
+    };
+    // Bold text is drawn a pixel wider.
+    let dot_width = FONT_SCALE + usize::from(cell.modifier.contains(Modifier::BOLD));
+    for (row, bits) in glyph.iter().enumerate() {
+        for column in (0..5).filter(|column| bits & (0x10 >> column) != 0) {
+            strip.fill(
+                left + FONT_LEFT + column * FONT_SCALE,
+                FONT_TOP + row * FONT_SCALE,
+                dot_width,
+                FONT_SCALE,
+                fg,
+                255,
+            );
+        }
+    }
+    if cell.modifier.contains(Modifier::UNDERLINED) {
+        strip.fill(left, CELL_HEIGHT - 4, CELL_WIDTH, 1, fg, 255);
+    }
+}
+
+/// The ASCII stand-in for typographic punctuation the pixel font lacks.
+fn ascii(ch: char) -> char {
+    match ch {
+        '–' | '—' | '−' => '-',
+        '‘' | '’' => '\'',
+        '“' | '”' => '"',
+        '×' => 'x',
+        _ => ch,
+    }
+}
+
+/// The printable ASCII characters from the space on, each five dots wide with the leftmost
+/// in the `0x10` bit: seven rows, then two more for the descenders of `g`, `j`, `p`, … .
+const FONT: [[u8; 9]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04, 0x00, 0x00],
+    [0x0a, 0x0a, 0x0a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], [0x0a, 0x0a, 0x1f, 0x0a, 0x1f, 0x0a, 0x0a, 0x00, 0x00],
+    [0x04, 0x0f, 0x14, 0x0e, 0x05, 0x1e, 0x04, 0x00, 0x00], [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03, 0x00, 0x00],
+    [0x0c, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0d, 0x00, 0x00], [0x0c, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02, 0x00, 0x00], [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08, 0x00, 0x00],
+    [0x00, 0x04, 0x15, 0x0e, 0x15, 0x04, 0x00, 0x00, 0x00], [0x00, 0x04, 0x04, 0x1f, 0x04, 0x04, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c, 0x04, 0x08], [0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0c, 0x0c, 0x00, 0x00], [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00, 0x00, 0x00],
+    [0x0e, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0e, 0x00, 0x00], [0x04, 0x0c, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00],
+    [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f, 0x00, 0x00], [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e, 0x00, 0x00],
+    [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02, 0x00, 0x00], [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e, 0x00, 0x00],
+    [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e, 0x00, 0x00], [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08, 0x00, 0x00],
+    [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e, 0x00, 0x00], [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c, 0x00, 0x00],
+    [0x00, 0x0c, 0x0c, 0x00, 0x0c, 0x0c, 0x00, 0x00, 0x00], [0x00, 0x0c, 0x0c, 0x00, 0x00, 0x0c, 0x0c, 0x04, 0x08],
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02, 0x00, 0x00], [0x00, 0x00, 0x1f, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00],
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08, 0x00, 0x00], [0x0e, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04, 0x00, 0x00],
+    [0x0e, 0x11, 0x01, 0x0d, 0x15, 0x15, 0x0e, 0x00, 0x00], [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11, 0x00, 0x00],
+    [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e, 0x00, 0x00], [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e, 0x00, 0x00],
+    [0x1c, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1c, 0x00, 0x00], [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f, 0x00, 0x00],
+    [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10, 0x00, 0x00], [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f, 0x00, 0x00],
+    [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11, 0x00, 0x00], [0x0e, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00],
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c, 0x00, 0x00], [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11, 0x00, 0x00],
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f, 0x00, 0x00], [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11, 0x00, 0x00],
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11, 0x00, 0x00], [0x0e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e, 0x00, 0x00],
+    [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10, 0x00, 0x00], [0x0e, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0d, 0x00, 0x00],
+    [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11, 0x00, 0x00], [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e, 0x00, 0x00],
+    [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x00], [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e, 0x00, 0x00],
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04, 0x00, 0x00], [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a, 0x00, 0x00],
+    [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11, 0x00, 0x00], [0x11, 0x11, 0x11, 0x0a, 0x04, 0x04, 0x04, 0x00, 0x00],
+    [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f, 0x00, 0x00], [0x0e, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0e, 0x00, 0x00],
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00, 0x00, 0x00], [0x0e, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0e, 0x00, 0x00],
+    [0x04, 0x0a, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x00],
+    [0x08, 0x04, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x0e, 0x01, 0x0f, 0x11, 0x0f, 0x00, 0x00],
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x1e, 0x00, 0x00], [0x00, 0x00, 0x0e, 0x10, 0x10, 0x11, 0x0e, 0x00, 0x00],
+    [0x01, 0x01, 0x0d, 0x13, 0x11, 0x11, 0x0f, 0x00, 0x00], [0x00, 0x00, 0x0e, 0x11, 0x1f, 0x10, 0x0e, 0x00, 0x00],
+    [0x06, 0x09, 0x08, 0x1c, 0x08, 0x08, 0x08, 0x00, 0x00], [0x00, 0x00, 0x0f, 0x11, 0x11, 0x11, 0x0f, 0x01, 0x0e],
+    [0x10, 0x10, 0x16, 0x19, 0x11, 0x11, 0x11, 0x00, 0x00], [0x04, 0x00, 0x0c, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00],
+    [0x02, 0x00, 0x06, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c], [0x10, 0x10, 0x12, 0x14, 0x18, 0x14, 0x12, 0x00, 0x00],
+    [0x0c, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0e, 0x00, 0x00], [0x00, 0x00, 0x1a, 0x15, 0x15, 0x11, 0x11, 0x00, 0x00],
+    [0x00, 0x00, 0x16, 0x19, 0x11, 0x11, 0x11, 0x00, 0x00], [0x00, 0x00, 0x0e, 0x11, 0x11, 0x11, 0x0e, 0x00, 0x00],
+    [0x00, 0x00, 0x1e, 0x11, 0x11, 0x11, 0x1e, 0x10, 0x10], [0x00, 0x00, 0x0f, 0x11, 0x11, 0x11, 0x0f, 0x01, 0x01],
+    [0x00, 0x00, 0x16, 0x19, 0x10, 0x10, 0x10, 0x00, 0x00], [0x00, 0x00, 0x0e, 0x10, 0x0e, 0x01, 0x1e, 0x00, 0x00],
+    [0x08, 0x08, 0x1c, 0x08, 0x08, 0x09, 0x06, 0x00, 0x00], [0x00, 0x00, 0x11, 0x11, 0x11, 0x13, 0x0d, 0x00, 0x00],
+    [0x00, 0x00, 0x11, 0x11, 0x11, 0x0a, 0x04, 0x00, 0x00], [0x00, 0x00, 0x11, 0x11, 0x15, 0x15, 0x0a, 0x00, 0x00],
+    [0x00, 0x00, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x00, 0x00], [0x00, 0x00, 0x11, 0x11, 0x11, 0x11, 0x0f, 0x01, 0x0e],
+    [0x00, 0x00, 0x1f, 0x02, 0x04, 0x08, 0x1f, 0x00, 0x00], [0x02, 0x04, 0x04, 0x08, 0x04, 0x04, 0x02, 0x00, 0x00],
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x00], [0x08, 0x04, 0x04, 0x02, 0x04, 0x04, 0x08, 0x00, 0x00],
+    [0x00, 0x00, 0x08, 0x15, 0x02, 0x00, 0x00, 0x00, 0x00],
+];
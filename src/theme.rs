@@ -0,0 +1,84 @@
+use clap::ValueEnum;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+    Dark,
+    Light,
+    HighContrast,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub number: Color,
+    pub label: Color,
+    pub text: Color,
+    pub border: Color,
+    pub background: Color,
+    pub highlight: Option<Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::preset(ThemePreset::Dark)
+    }
+}
+
+impl Theme {
+    pub fn preset(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Dark => Self {
+                number: Color::Cyan,
+                label: Color::Yellow,
+                text: Color::Reset,
+                border: Color::Reset,
+                background: Color::Reset,
+                highlight: None,
+            },
+            ThemePreset::Light => Self {
+                number: Color::Blue,
+                label: Color::Magenta,
+                text: Color::Black,
+                border: Color::Gray,
+                background: Color::White,
+                highlight: Some(Color::Gray),
+            },
+            ThemePreset::HighContrast => Self {
+                number: Color::LightYellow,
+                label: Color::LightCyan,
+                text: Color::White,
+                border: Color::White,
+                background: Color::Black,
+                highlight: None,
+            },
+        }
+    }
+
+    pub fn base_style(&self) -> Style {
+        Style::default().fg(self.text).bg(self.background)
+    }
+
+    pub fn number_style(&self) -> Style {
+        Style::default().fg(self.number)
+    }
+
+    pub fn label_style(&self) -> Style {
+        Style::default().fg(self.label)
+    }
+
+    pub fn highlight_style(&self) -> Style {
+        match self.highlight {
+            Some(color) => Style::default().bg(color).add_modifier(Modifier::BOLD),
+            None => Style::default().add_modifier(Modifier::REVERSED),
+        }
+    }
+
+    pub fn block(&self) -> Block<'static> {
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.border))
+    }
+}
@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_MARKERS: &[&str] = &["TODO", "FIXME", "HACK"];
+const MAX_TEXT_LEN: usize = 120;
+
+/// A marker comment inside one file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TodoComment {
+    pub line: u64,
+    pub marker: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Todo {
+    pub root: usize,
+    pub path: PathBuf,
+    pub comment: TodoComment,
+}
+
+/// Finds `markers` used as whole words, e.g. `TODO:` or `FIXME(alice)` but not `TODOS`.
+pub fn find_todos(buf: &[u8], markers: &[String]) -> Vec<TodoComment> {
+    let mut todos = Vec::new();
+    if markers.is_empty() {
+        return todos;
+    }
+    for (index, line) in buf.split(|byte| *byte == b'\n').enumerate() {
+        let Some((start, marker)) = find_marker(line, markers) else {
+            continue;
+        };
+        let text = String::from_utf8_lossy(&line[start..]);
+        let text = text.trim();
+        todos.push(TodoComment {
+            line: index as u64 + 1,
+            marker: marker.to_string(),
+            text: match text.char_indices().nth(MAX_TEXT_LEN) {
+                Some((cut, _)) => format!("{}…", &text[..cut]),
+                None => text.to_string(),
+            },
+        });
+    }
+    todos
+}
+
+fn find_marker<'a>(line: &[u8], markers: &'a [String]) -> Option<(usize, &'a str)> {
+    let is_word = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'_';
+    for start in 0..line.len() {
+        if start > 0 && is_word(line[start - 1]) {
+            continue;
+        }
+        for marker in markers {
+            let end = start + marker.len();
+            if line[start..].starts_with(marker.as_bytes()) && !line.get(end).is_some_and(|byte| is_word(*byte)) {
+                return Some((start, marker));
+            }
+        }
+    }
+    None
+}
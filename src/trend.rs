@@ -0,0 +1,92 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::{DateTime, FixedOffset};
+
+use codecounter::cache::ObjectCache;
+use codecounter::config::Settings;
+use codecounter::format::format_with_commas;
+use codecounter::git;
+use codecounter::scan::{counted_category, is_counted};
+
+#[derive(Debug, Clone)]
+pub struct TrendPoint {
+    pub commit: String,
+    pub label: String,
+    pub date: DateTime<FixedOffset>,
+    pub lines: u64,
+    pub files: u64,
+}
+
+/// Counts the lines in `dir` as of each of the last `points` commits (or tags),
+/// reading file contents straight from git so the working tree is left alone.
+pub fn line_trend(
+    dir: &Path,
+    settings: &Settings,
+    points: usize,
+    tags: bool,
+) -> Result<Vec<TrendPoint>, Box<dyn Error>> {
+    let commits = if tags {
+        git::recent_tags(dir, points)?
+    } else {
+        git::recent_commits(dir, points)?
+    };
+
+    let mut cache = ObjectCache::load();
+    let mut trend = Vec::with_capacity(commits.len());
+    for commit in commits {
+        let files = git::tree_files(dir, &commit.hash)?
+            .into_iter()
+            .filter(|(_, path)| is_counted(path, settings))
+            .collect::<Vec<_>>();
+
+        let counts = git::blob_counts(dir, &files, &mut cache)?;
+        // Placed like a scan places them, so each point is comparable with the headline total.
+        let counts = files
+            .iter()
+            .zip(counts)
+            .filter_map(|((_, path), count)| {
+                let count = count?;
+                let category = counted_category(path, &count, settings)?;
+                Some(count.weighted_lines(category, &settings.categories))
+            })
+            .collect::<Vec<_>>();
+        trend.push(TrendPoint {
+            commit: commit.hash[..commit.hash.len().min(7)].to_string(),
+            label: commit.label,
+            date: commit.date,
+            lines: counts.iter().sum(),
+            files: counts.len() as u64,
+        });
+    }
+    cache.save()?;
+    Ok(trend)
+}
+
+pub fn write_text(trend: &[TrendPoint], out: &mut dyn Write) -> io::Result<()> {
+    let lines_width = trend
+        .iter()
+        .map(|point| format_with_commas(point.lines).len())
+        .max()
+        .unwrap_or(0);
+    let files_width = trend
+        .iter()
+        .map(|point| format_with_commas(point.files).len())
+        .max()
+        .unwrap_or(0);
+    for point in trend {
+        writeln!(
+            out,
+            "{}  {}  {:>lines_width$} lines  {:>files_width$} files  {}",
+            point.date.format("%Y-%m-%d"),
+            point.commit,
+            format_with_commas(point.lines),
+            format_with_commas(point.files),
+            point.label,
+            lines_width = lines_width,
+            files_width = files_width
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,2059 @@
+use std::time::Duration;
+
+use chrono::Local;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Alignment, Constraint, Direction, Flex, Layout, Margin, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::symbols::Marker;
+use ratatui::widgets::canvas::{Canvas, Points};
+use ratatui::widgets::{
+    Axis, Bar, BarChart, BarGroup, Block, Cell, Chart, Clear, Dataset, GraphType, Paragraph, Row, Sparkline, Table, TableState, Tabs,
+    Widget, Wrap,
+};
+use ratatui::Frame;
+
+use codecounter::ascii::{BigNumber, SMALL_DIGIT_ROWS};
+use codecounter::cocomo::estimate;
+use codecounter::config::Settings;
+use codecounter::export::{duplication_summary, metrics_summary, test_summary};
+use codecounter::format::{format_bytes, format_decimal, format_duration, format_share, format_signed, format_with_commas};
+use codecounter::keymap::Action;
+use codecounter::scan::{root_label, DirNode, ScanResult, LONG_LINE};
+
+use crate::diff::{format_relative, ScanDiff};
+use crate::{App, Hit, LanguageChart, Sort, SortKey, View};
+
+pub fn draw_ui(frame: &mut Frame, app: &mut App) {
+    frame.render_widget(Block::default().style(app.settings.theme.base_style()), frame.area());
+    app.hits.clear();
+    let area = frame.area();
+    let status_height = if area.height >= STATUS_BAR_MIN_HEIGHT { 1 } else { 0 };
+    let [tabs_rect, body, status_rect] =
+        Layout::vertical([Constraint::Length(1), Constraint::Fill(1), Constraint::Length(status_height)]).areas(area);
+    draw_tabs(frame, app, tabs_rect);
+    draw_status_bar(frame, app, status_rect);
+    match app.view {
+        View::Summary => draw_summary(frame, app, body, false),
+        View::Languages => draw_languages(frame, app, body),
+        View::Files => draw_files(frame, app, body),
+        View::Directories => draw_directories(frame, app, body),
+        View::Packages => draw_packages(frame, app, body),
+        View::Todos => draw_todos(frame, app, body),
+        View::LongLines => draw_long_lines(frame, app, body),
+        View::Duplicates => draw_duplicates(frame, app, body),
+        View::Trend => draw_trend(frame, app, body),
+        View::Authors => draw_authors(frame, app, body),
+        View::Repos => draw_repos(frame, app, body),
+    }
+    if let Some((milestone, elapsed)) = app.celebrating() {
+        draw_celebration(frame, app, milestone, elapsed, body);
+    }
+    if app.show_help {
+        draw_help(frame, app);
+    }
+}
+
+/// The summary on its own, without the tab bar or any overlay, for `--image` and `i` / `I`.
+pub fn draw_snapshot(frame: &mut Frame, app: &mut App) {
+    frame.render_widget(Block::default().style(app.settings.theme.base_style()), frame.area());
+    app.hits.clear();
+    draw_summary(frame, app, frame.area(), true);
+}
+
+/// Alternates every `FLASH_PERIOD` while a milestone is being celebrated.
+fn flash_on(elapsed: Duration) -> bool {
+    (elapsed.as_millis() / FLASH_PERIOD.as_millis()).is_multiple_of(2)
+}
+
+/// A flashing banner across the top of the body, with a row of sparkles drifting along it.
+fn draw_celebration(frame: &mut Frame, app: &App, milestone: u64, elapsed: Duration, area: Rect) {
+    let theme = &app.settings.theme;
+    let message = format!("{} lines of code!", format_with_commas(milestone));
+    let width = (message.chars().count() as u16 + 16).max(40);
+    let step = (elapsed.as_millis() / FLASH_PERIOD.as_millis()) as usize;
+    let sparkles = (0..width.saturating_sub(4) as usize)
+        .map(|column| if (column + step).is_multiple_of(4) { '✦' } else { ' ' })
+        .collect::<String>();
+    let (accent, text) = if flash_on(elapsed) {
+        (theme.number_style(), theme.label_style())
+    } else {
+        (theme.label_style(), theme.number_style())
+    };
+    let lines = vec![
+        Line::styled(sparkles.clone(), accent),
+        Line::styled(message, text.add_modifier(Modifier::BOLD)),
+        Line::styled("Milestone reached", theme.label_style()),
+        Line::styled(sparkles.chars().rev().collect::<String>(), accent),
+    ];
+    let rect = centered_rect(
+        width,
+        lines.len() as u16 + 2,
+        Rect {
+            height: (lines.len() as u16 + 2).min(area.height),
+            y: area.y + area.height.saturating_sub(lines.len() as u16 + 2) / 3,
+            ..area
+        },
+    );
+    let banner = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .style(theme.base_style())
+        .block(theme.block().border_style(accent));
+    frame.render_widget(Clear, rect);
+    frame.render_widget(banner, rect);
+}
+
+/// "Scanning… 1,204 counted / 1,530 found, 2s" while a scan is running.
+fn scan_progress(app: &App) -> Option<String> {
+    let (progress, started) = app.progress.as_ref()?;
+    Some(format!(
+        "Scanning… {} counted / {} found, {}",
+        format_with_commas(progress.counted),
+        format_with_commas(progress.discovered),
+        format_duration(started.elapsed())
+    ))
+}
+
+/// "4 of 8 cores", so a `--threads` override is visible next to what the machine offers.
+fn describe_milestones(settings: &Settings) -> String {
+    let milestones = &settings.milestones;
+    let mut parts = milestones
+        .every
+        .map(|every| format!("every {}", format_with_commas(every)))
+        .into_iter()
+        .chain(milestones.at.iter().map(|at| format_with_commas(*at)))
+        .collect::<Vec<_>>();
+    if parts.is_empty() {
+        parts.push("off".to_string());
+    }
+    parts.join(", ")
+}
+
+fn describe_threads(threads: usize) -> String {
+    match std::thread::available_parallelism() {
+        Ok(cores) => format!("{} of {} cores", threads, cores),
+        Err(_) => threads.to_string(),
+    }
+}
+
+fn describe_hidden(hidden: Option<bool>) -> &'static str {
+    match hidden {
+        Some(true) => "all",
+        Some(false) => "none",
+        None => "well-known only",
+    }
+}
+
+fn draw_tabs(frame: &mut Frame, app: &mut App, area: Rect) {
+    let views = app.views();
+    let titles = views
+        .iter()
+        .enumerate()
+        .map(|(index, view)| format!("{} {}", index + 1, view.title()))
+        .collect::<Vec<_>>();
+    // Mirrors how `Tabs` lays titles out: one space of padding either side, then a divider.
+    let mut x = area.x;
+    for (title, view) in titles.iter().zip(&views) {
+        let width = title.chars().count() as u16 + 2;
+        let rect = Rect {
+            x,
+            width: width.min((area.x + area.width).saturating_sub(x)),
+            ..area
+        };
+        app.hits.push((rect, Hit::View(*view)));
+        x = x.saturating_add(width + 1);
+    }
+    let tabs = Tabs::new(titles)
+        .select(views.iter().position(|view| *view == app.view))
+        .style(app.settings.theme.base_style())
+        .highlight_style(app.settings.theme.highlight_style())
+        .divider(Span::styled("│", Style::default().fg(app.settings.theme.border)));
+    frame.render_widget(tabs, area);
+
+    // The running scan is reported on the right of the tab bar so every view shows it.
+    if let Some((progress, _)) = &app.progress
+        && let Some(status) = scan_progress(app)
+    {
+        let tabs_width = x.saturating_sub(area.x);
+        let room = area.width.saturating_sub(tabs_width + 2) as usize;
+        let mut line = status;
+        let current = progress.current.display().to_string();
+        if !current.is_empty() && line.chars().count() + current.chars().count() + 3 <= room {
+            line = format!("{} · {}", line, current);
+        }
+        if line.chars().count() <= room {
+            let status = Paragraph::new(Line::from(line).alignment(Alignment::Right)).style(app.settings.theme.label_style());
+            frame.render_widget(status, area);
+        }
+    }
+}
+
+/// How the last scan went, along the bottom of every view: how long it took, files and
+/// bytes read per second, and how many files came from the cache.
+fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
+    let Some(scan) = &app.scan else {
+        return;
+    };
+    let throughput = scan.throughput;
+    let counted = throughput.cache_hits + throughput.cache_misses;
+    let mut parts = vec![
+        format!(
+            "{} {}",
+            if app.restored { "Previous session's scan" } else { "Last scan" },
+            format_duration(throughput.elapsed)
+        ),
+        format!("{} files/s", format_with_commas(throughput.files_per_second().round() as u64)),
+        format!("{}/s read", format_bytes(throughput.bytes_per_second().round() as u64)),
+    ];
+    if counted > 0 {
+        parts.push(format!(
+            "cache hits {} ({} of {})",
+            format_share(throughput.cache_hits, counted),
+            format_with_commas(throughput.cache_hits),
+            format_with_commas(counted)
+        ));
+    }
+    frame.render_widget(Paragraph::new(parts.join(" · ")).style(app.settings.theme.label_style()), area);
+}
+
+fn draw_help(frame: &mut Frame, app: &App) {
+    let settings = &app.settings;
+    let on_off = |enabled: bool| if enabled { "on" } else { "off" }.to_string();
+    let mut keys = settings.keymap.help();
+    keys.insert(2, ("1 … 9".to_string(), "jump to a view"));
+    keys.push(("mouse".to_string(), "click panels, headers and rows; wheel scrolls"));
+    let mut options = vec![
+        ("Extensions", settings.extensions.describe()),
+        ("Ignored", settings.ignore.join(", ")),
+        ("Exclude globs", settings.exclude.len().to_string()),
+        (
+            "Include globs",
+            if settings.include.is_empty() { "all files".to_string() } else { settings.include.len().to_string() },
+        ),
+        (".ccignore rules", settings.ccignore.len().to_string()),
+        (
+            "Auto-refresh",
+            settings
+                .refresh
+                .map_or_else(|| "off".to_string(), |interval| format!("every {}", format_duration(interval))),
+        ),
+        ("Cache", on_off(settings.persist_cache)),
+        ("Session", on_off(settings.session)),
+        ("History log", on_off(settings.history)),
+        ("Count generated", on_off(settings.count_generated)),
+        ("Count minified", on_off(settings.count_minified)),
+        ("Git-ignored files", if settings.gitignore { "skipped" } else { "counted" }.to_string()),
+        ("Submodules", if settings.include_submodules { "counted" } else { "left out" }.to_string()),
+        ("Complexity", on_off(settings.complexity)),
+        ("Follow symlinks", on_off(settings.follow_symlinks)),
+        ("Hidden files", describe_hidden(settings.hidden).to_string()),
+        ("Threads", describe_threads(settings.threads)),
+        ("Milestones", describe_milestones(settings)),
+        ("TODO markers", settings.todo_markers.join(", ")),
+        (
+            "Analyzers",
+            if settings.analyzers.is_empty() { "none".to_string() } else { settings.analyzers.names().join(", ") },
+        ),
+        ("Languages chart", app.language_chart.name().to_string()),
+        ("Estimate", on_off(app.show_estimate)),
+        ("Errors panel", on_off(app.show_errors)),
+        ("Compact number", on_off(app.compact_number)),
+        ("Big number", if app.all_categories { "every category" } else { "the total" }.to_string()),
+        ("Font", settings.fonts.get(app.font).name.clone()),
+        ("Digits", settings.digit_renderer.name().to_string()),
+    ];
+    if let Some(reference) = &settings.since {
+        options.push(("Since", reference.clone()));
+    }
+    if let Some(depth) = settings.max_depth {
+        options.push(("Max depth", depth.to_string()));
+    }
+    if let Some(files) = settings.max_files {
+        options.push(("Max files", format_with_commas(files as u64)));
+    }
+
+    let label_width = keys.iter().map(|(key, _)| key.chars().count()).max().unwrap_or(0);
+    let option_width = options.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let mut lines = vec![Line::styled("Keys", settings.theme.label_style().add_modifier(Modifier::BOLD))];
+    lines.extend(keys.into_iter().map(|(key, action)| {
+        Line::from(vec![
+            Span::styled(format!("  {:<width$}  ", key, width = label_width), settings.theme.number_style()),
+            Span::raw(action),
+        ])
+    }));
+    lines.push(Line::from(""));
+    lines.push(Line::styled("Options", settings.theme.label_style().add_modifier(Modifier::BOLD)));
+    lines.extend(options.into_iter().map(|(name, value)| {
+        Line::from(vec![
+            Span::styled(format!("  {:<width$}  ", name, width = option_width), settings.theme.label_style()),
+            Span::raw(value),
+        ])
+    }));
+
+    let area = centered_rect(76, lines.len() as u16 + 2, frame.area());
+    let help = Paragraph::new(lines)
+        .style(settings.theme.base_style())
+        .wrap(Wrap { trim: false })
+        .block(settings.theme.block().title("Help (any key closes)"));
+    frame.render_widget(Clear, area);
+    frame.render_widget(help, area);
+}
+
+fn headline(app: &App) -> Paragraph<'static> {
+    let subject = match app.roots.len() {
+        1 => "this repo".to_string(),
+        count => format!("these {} directories", count),
+    };
+    let headline = match &app.scan {
+        Some(scan) => format!(
+            "As of {} the number of {} in {} is:",
+            scan.scanned_at.format("%Y-%m-%d %H:%M:%S %z"),
+            if app.all_categories { "lines" } else { "lines of code" },
+            subject
+        ),
+        None => format!("Counting the lines of code in {}…", subject),
+    };
+    Paragraph::new(Line::from(headline))
+        .alignment(Alignment::Center)
+        .block(app.settings.theme.block())
+}
+
+/// With `snapshot`, what only means something on screen (the time since the scan, the
+/// status line and the keys) is left out.
+fn draw_summary(frame: &mut Frame, app: &mut App, area: Rect, snapshot: bool) {
+
+    let headline = headline(app);
+
+    let time_line = match (&app.scan, &app.pending) {
+        (None, Some(_)) => scan_progress(app).unwrap_or_else(|| "Scanning…".to_string()),
+        (None, None) => "Scan cancelled, press r to start again".to_string(),
+        (Some(_), Some(_)) => format!(
+            "{} (last scan {} ago)",
+            scan_progress(app).unwrap_or_else(|| "Scanning…".to_string()),
+            format_duration(app.last_scan.elapsed())
+        ),
+        (Some(_), None) => format!(
+            "Time since last scan: {}",
+            format_duration(app.last_scan.elapsed())
+        ),
+    };
+    let mut footer = Vec::new();
+    if let Some(scan) = &app.scan {
+        if scan.categories.len() > 1 {
+            footer.push(category_line(app, scan));
+        }
+        if let Some(change) = app.session_change {
+            footer.push(session_change_line(change));
+        }
+        let deltas = app
+            .history
+            .deltas(app.session_change.is_none())
+            .iter()
+            .map(|delta| format!("{} {}", format_signed(delta.change), delta.label))
+            .collect::<Vec<_>>();
+        if !deltas.is_empty() {
+            footer.push(Line::from(deltas.join(" · ")));
+        }
+    }
+    if !snapshot {
+        footer.push(Line::from(time_line));
+    }
+
+    let mut info_lines = vec![
+        Line::from(vec![
+            Span::styled(
+                if app.roots.len() == 1 { "Directory: " } else { "Directories: " },
+                app.settings.theme.label_style(),
+            ),
+            Span::raw(
+                app.roots
+                    .iter()
+                    .map(|root| root.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Files scanned: ", app.settings.theme.label_style()),
+            Span::raw(
+                app.scan
+                    .as_ref()
+                    .map(|scan| {
+                        let mut files = scan.files.to_string();
+                        if scan.binary_files > 0 {
+                            files.push_str(&format!(" (skipped {} binary)", scan.binary_files));
+                        }
+                        if scan.oversized_files > 0 {
+                            files.push_str(&format!(" (skipped {} over --max-file-size)", scan.oversized_files));
+                        }
+                        if scan.over_budget_files > 0 {
+                            files.push_str(&format!(" (skipped {} past --max-bytes)", scan.over_budget_files));
+                        }
+                        if scan.truncated {
+                            files.push_str(" (stopped at --max-files)");
+                        }
+                        if !scan.errors.is_empty() {
+                            files.push_str(&format!(" ({} unreadable)", scan.errors.len()));
+                        }
+                        files
+                    })
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Extensions: ", app.settings.theme.label_style()),
+            Span::raw(app.settings.extensions.describe()),
+        ]),
+        Line::from(vec![
+            Span::styled("Threads: ", app.settings.theme.label_style()),
+            Span::raw(describe_threads(app.settings.threads)),
+        ]),
+    ];
+    if let Some(generated) = app.scan.as_ref().map(|scan| scan.generated).filter(|bucket| bucket.files > 0) {
+        info_lines.push(Line::from(vec![
+            Span::styled("Generated/vendored: ", app.settings.theme.label_style()),
+            Span::raw(format!(
+                "{} lines in {} files (not in the total)",
+                format_with_commas(generated.lines),
+                format_with_commas(generated.files)
+            )),
+        ]));
+    }
+    if let Some(minified) = app.scan.as_ref().map(|scan| scan.minified).filter(|bucket| bucket.files > 0) {
+        info_lines.push(Line::from(vec![
+            Span::styled("Minified: ", app.settings.theme.label_style()),
+            Span::raw(format!(
+                "{} lines in {} files (not in the total, --count-minified counts them)",
+                format_with_commas(minified.lines),
+                format_with_commas(minified.files)
+            )),
+        ]));
+    }
+    let submodules = app.scan.as_ref().map(ScanResult::skipped_submodules).unwrap_or_default();
+    if !submodules.is_empty() {
+        info_lines.push(Line::from(vec![
+            Span::styled("Submodules: ", app.settings.theme.label_style()),
+            Span::raw(format!("{} (not counted, --include-submodules counts them)", submodules.join(", "))),
+        ]));
+    }
+    if let Some(tests) = app.scan.as_ref().and_then(test_summary) {
+        info_lines.push(Line::from(vec![
+            Span::styled("Test code: ", app.settings.theme.label_style()),
+            Span::raw(tests),
+        ]));
+    }
+    if let Some(metrics) = app.scan.as_ref().and_then(metrics_summary) {
+        info_lines.push(Line::from(vec![
+            Span::styled("Analyzers: ", app.settings.theme.label_style()),
+            Span::raw(metrics),
+        ]));
+    }
+    if let Some(duplication) = app.scan.as_ref().and_then(duplication_summary) {
+        info_lines.push(Line::from(vec![
+            Span::styled("Duplication: ", app.settings.theme.label_style()),
+            Span::raw(duplication),
+        ]));
+    }
+    if let Some(changes) = app.scan.as_ref().and_then(|scan| scan.since.as_ref()) {
+        info_lines.push(Line::from(vec![
+            Span::styled(
+                format!("Since {}: ", changes.reference),
+                app.settings.theme.label_style(),
+            ),
+            Span::raw(format!(
+                "{} / {} lines",
+                format_signed(changes.added as i64),
+                format_signed(-(changes.removed as i64))
+            )),
+        ]));
+    }
+    if let Some(entry) = app.history.last_milestone()
+        && let Some(milestone) = entry.milestone
+    {
+        info_lines.push(Line::from(vec![
+            Span::styled("Last milestone: ", app.settings.theme.label_style()),
+            Span::raw(format!(
+                "{} lines, reached {}",
+                format_with_commas(milestone),
+                entry.scanned_at.format("%Y-%m-%d %H:%M")
+            )),
+        ]));
+    }
+    if let (Some(interval), Some(next)) = (app.settings.refresh, app.next_refresh()) {
+        let countdown = if app.pending.is_some() {
+            "scanning now".to_string()
+        } else {
+            format!("next in {}", format_duration(next))
+        };
+        info_lines.push(Line::from(vec![
+            Span::styled("Auto-refresh: ", app.settings.theme.label_style()),
+            Span::raw(format!("every {}, {}", format_duration(interval), countdown)),
+        ]));
+    }
+    if let Some(message) = app.status_message().filter(|_| !snapshot) {
+        info_lines.push(Line::styled(message.to_string(), app.settings.theme.label_style()));
+    }
+    if !snapshot {
+        info_lines.push(Line::from(key_hints(
+            app,
+            &[
+                (&[Action::Rescan], "rescan"),
+                (&[Action::NextView], "views"),
+                (&[Action::ExportCsv, Action::ExportMarkdown], "export CSV/Markdown"),
+                (&[Action::Help], "help"),
+                (&[Action::Quit], "quit"),
+            ],
+        )));
+    }
+    // The number's box comes first, then the headline, then as much of the info box as
+    // leaves room for the small digits (or at least a line of plain text), so on a short
+    // terminal they give way rather than draw over each other.
+    let header_height = if area.height >= 6 { 3 } else { 0 };
+    let info_height = [SMALL_DIGIT_ROWS as u16 + 2, 3]
+        .into_iter()
+        .map(|number| (info_lines.len() as u16 + 2).min(area.height.saturating_sub(header_height + number)))
+        .find(|height| *height >= 3)
+        .unwrap_or(0);
+    let info = Paragraph::new(info_lines)
+        .block(app.settings.theme.block())
+        .wrap(Wrap { trim: true });
+
+    let [header_rect, middle, info_rect] = Layout::vertical([
+        Constraint::Length(header_height),
+        Constraint::Fill(1),
+        Constraint::Length(info_height),
+    ])
+    .areas(area);
+    // A wide terminal has room for the languages beside the number rather than under it.
+    let languages = app.scan.as_ref().map_or(&[][..], |scan| &scan.languages[..]);
+    let [main, side] = if middle.width >= WIDE_SUMMARY && !languages.is_empty() {
+        Layout::horizontal([Constraint::Fill(3), Constraint::Fill(2)]).spacing(1).areas(middle)
+    } else {
+        [middle, Rect::default()]
+    };
+    // The footer wants its own rows plus a blank line under the number, and gives them up
+    // (time line first) rather than squeeze the number below the small digits.
+    let rows = middle.height.saturating_sub(2) as usize;
+    let beside_footer = rows.saturating_sub(footer.len() + 1);
+    let big = BigNumber {
+        font: app.settings.fonts.get(app.font),
+        renderer: app.settings.digit_renderer,
+        max_width: main.width.saturating_sub(2) as usize,
+        max_height: if beside_footer >= SMALL_DIGIT_ROWS { beside_footer } else { rows.max(1) },
+    };
+    let ascii_lines = match (&app.scan, app.rolling_total()) {
+        (None, _) => Vec::new(),
+        (Some(_), Some(value)) if app.compact_number => big.compact(value as u64),
+        (Some(scan), None) if app.compact_number => big.compact(big_total(app, scan)),
+        (Some(_), Some(value)) => big.rolling(value),
+        (Some(scan), None) => big.number(big_total(app, scan)),
+    };
+    let spare = rows.saturating_sub(ascii_lines.len());
+    let blank_line = !ascii_lines.is_empty() && spare > footer.len();
+    footer.truncate(spare);
+    let ascii_width = ascii_lines
+        .iter()
+        .map(|line| line.chars().count())
+        .chain(footer.iter().map(Line::width))
+        .max()
+        .unwrap_or(0) as u16;
+    let number_style = match app.celebrating() {
+        Some((_, elapsed)) if !flash_on(elapsed) => app.settings.theme.number_style().add_modifier(Modifier::REVERSED),
+        _ => app.settings.theme.number_style(),
+    };
+    let mut ascii_text = ascii_lines
+        .iter()
+        .map(|line| Line::styled(line.clone(), number_style))
+        .collect::<Vec<_>>();
+    if blank_line {
+        ascii_text.push(Line::from(""));
+    }
+    ascii_text.extend(footer);
+    let ascii_height = ascii_text.len() as u16;
+
+    let number_title = if app.all_categories { ALL_LINES_TITLE } else { NUMBER_TITLE };
+    let ascii = Paragraph::new(ascii_text)
+        .alignment(Alignment::Center)
+        .block(app.settings.theme.block().title(number_title));
+
+    let ascii_box_width = ascii_width.max(number_title.len() as u16).saturating_add(2);
+    let ascii_box_height = ascii_height.saturating_add(2);
+
+    let panel_width = ascii_box_width.max(60).min(main.width);
+    let mut panels = Vec::new();
+    let mut used_height = ascii_box_height;
+    if let Some(scan) = &app.scan {
+        // Each panel is paired with the view a click on it switches to.
+        let candidates = [
+            ((app.show_errors && !scan.errors.is_empty()).then(|| error_panel(app, scan)), None),
+            (
+                (app.history.entries().len() > 1).then(|| history_sparkline(app, panel_width)),
+                (!app.trend.is_empty()).then_some(View::Trend),
+            ),
+            ((scan.roots.len() > 1).then(|| root_breakdown(app, scan)), Some(View::Directories)),
+            ((!scan.crates.is_empty()).then(|| crate_breakdown(app, scan)), Some(View::Directories)),
+            (
+                (!scan.languages.is_empty() && side.is_empty()).then(|| language_panel(app, scan)).flatten(),
+                Some(View::Languages),
+            ),
+            ((!scan.file_stats.is_empty()).then(|| size_histogram(app, scan)), Some(View::Files)),
+            (app.show_estimate.then(|| estimate_panel(app, scan)), None),
+            ((!app.authors.is_empty()).then(|| top_authors(app)), Some(View::Authors)),
+            ((!scan.todos.is_empty()).then(|| todo_summary(app, scan)), Some(View::Todos)),
+            (license_summary(app, scan), None),
+            ((!scan.largest.is_empty()).then(|| largest_files(app, scan)), Some(View::Files)),
+            (complex_files(app, scan), Some(View::Files)),
+        ];
+        for (panel, target) in candidates {
+            let Some((panel, rows)) = panel else {
+                continue;
+            };
+            let height = rows as u16 + 2;
+            if used_height + height <= main.height {
+                used_height += height;
+                panels.push((panel, height, target));
+            }
+        }
+    }
+
+    // The number and the panels under it go down the middle of their column as one group.
+    let heights = std::iter::once(ascii_box_height).chain(panels.iter().map(|(_, height, _)| *height));
+    let rects = Layout::vertical(heights.map(Constraint::Length)).flex(Flex::Center).split(main);
+    frame.render_widget(ascii, centered_rect(ascii_box_width, ascii_box_height, rects[0]));
+    let mut hits = Vec::new();
+    for ((panel, height, target), rect) in panels.into_iter().zip(rects.iter().skip(1)) {
+        let rect = centered_rect(panel_width, height, *rect);
+        frame.render_widget(panel, rect);
+        if let Some(view) = target {
+            hits.push((rect, Hit::View(view)));
+        }
+    }
+    if let Some(scan) = app.scan.as_ref().filter(|_| !side.is_empty()) {
+        hits.extend(draw_summary_languages(frame, app, scan, side));
+    }
+    app.hits.extend(hits);
+
+    frame.render_widget(headline, header_rect);
+    frame.render_widget(info, info_rect);
+}
+
+/// The languages chart the `b` key has picked, if any.
+fn language_panel(app: &App, scan: &ScanResult) -> Option<(Panel, usize)> {
+    match app.language_chart {
+        LanguageChart::Bars => Some(language_chart(app, scan)),
+        LanguageChart::Donut => Some(language_donut(app, scan)),
+        LanguageChart::Hidden => None,
+    }
+}
+
+/// The column beside the number on a wide summary: a table of the languages, with the
+/// languages chart under it while the table keeps `SIDE_TABLE_ROWS` rows. A click on
+/// either opens the languages view.
+fn draw_summary_languages(frame: &mut Frame, app: &App, scan: &ScanResult, area: Rect) -> Vec<(Rect, Hit)> {
+    let full_table = scan.languages.len() as u16 + 3;
+    let chart = language_panel(app, scan)
+        .map(|(panel, rows)| (panel, rows as u16 + 2))
+        .filter(|(_, height)| full_table.min(SIDE_TABLE_ROWS + 3) + height <= area.height);
+    let chart_height = chart.as_ref().map_or(0, |(_, height)| *height);
+    let table_height = full_table.min(area.height - chart_height);
+    let [table_rect, chart_rect] =
+        Layout::vertical([Constraint::Length(table_height), Constraint::Length(chart_height)])
+            .flex(Flex::Center)
+            .areas(area);
+
+    let rows = scan.languages.iter().take(table_height.saturating_sub(3) as usize).map(|language| {
+        Row::new(vec![
+            Cell::from(language.name.clone()),
+            right(format_with_commas(language.files)),
+            right(format_with_commas(language.lines)),
+            right(format_share(language.lines, scan.counted_lines())),
+        ])
+    });
+    let header = Row::new(vec![
+        Cell::from("Language"),
+        right("Files".to_string()),
+        right("Lines".to_string()),
+        right("Share".to_string()),
+    ])
+    .style(app.settings.theme.label_style());
+    let widths = [Constraint::Min(12), Constraint::Length(8), Constraint::Length(12), Constraint::Length(8)];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .column_spacing(2)
+        .block(app.settings.theme.block().title(format!("Languages ({})", scan.languages.len())));
+    frame.render_widget(table, table_rect);
+    let mut hits = vec![(table_rect, Hit::View(View::Languages))];
+    if let Some((chart, _)) = chart {
+        frame.render_widget(chart, chart_rect);
+        hits.push((chart_rect, Hit::View(View::Languages)));
+    }
+    hits
+}
+
+/// What the big number shows: the total, which is code only unless the config weighs the
+/// other categories in, or with the toggle every line counted.
+fn big_total(app: &App, scan: &ScanResult) -> u64 {
+    if app.all_categories { scan.counted_lines() } else { scan.lines }
+}
+
+/// "Code 12,034 · Docs 1,210 · Config 380", with the categories the big number leaves out dimmed.
+fn category_line(app: &App, scan: &ScanResult) -> Line<'static> {
+    let mut spans = Vec::new();
+    for category in &scan.categories {
+        if !spans.is_empty() {
+            spans.push(Span::raw(" · "));
+        }
+        let name = category.category.name();
+        let text = format!("{}{} {}", name[..1].to_uppercase(), &name[1..], format_with_commas(category.lines));
+        if app.all_categories || category.weight > 0.0 {
+            spans.push(Span::raw(text));
+        } else {
+            spans.push(Span::styled(text, Style::default().add_modifier(Modifier::DIM)));
+        }
+    }
+    Line::from(spans)
+}
+
+/// "▲ 152 lines since the last rescan", green for growth and red for shrinkage.
+fn session_change_line(change: i64) -> Line<'static> {
+    let (text, style) = match change.signum() {
+        1 => (format!("▲ {} lines", format_with_commas(change as u64)), Style::default().fg(Color::Green)),
+        -1 => (format!("▼ {} lines", format_with_commas(change.unsigned_abs())), Style::default().fg(Color::Red)),
+        _ => ("No change".to_string(), Style::default()),
+    };
+    Line::from(vec![Span::styled(text, style), Span::raw(" since the last rescan")])
+}
+
+fn root_breakdown(app: &App, scan: &ScanResult) -> (Panel, usize) {
+    let rows = scan
+        .roots
+        .iter()
+        .map(|root| {
+            (
+                format_with_commas(root.lines),
+                format!(
+                    "{} ({} files)",
+                    root_label(&root.path).display(),
+                    format_with_commas(root.files)
+                ),
+            )
+        })
+        .collect::<Vec<_>>();
+    (Panel::Table(count_table(app, "Per directory".to_string(), rows)), scan.roots.len())
+}
+
+/// The largest crates of a Cargo workspace, with the lines in each one's `src`, `tests` and
+/// `benches`.
+fn crate_breakdown(app: &App, scan: &ScanResult) -> (Panel, usize) {
+    let rows = scan
+        .crates
+        .iter()
+        .take(TOP_CRATES)
+        .map(|member| {
+            let parts = member
+                .parts()
+                .iter()
+                .map(|(part, bucket)| format!("{} {}", part, format_with_commas(bucket.lines)))
+                .collect::<Vec<_>>();
+            (format_with_commas(member.lines), format!("{} ({})", member.name, parts.join(" · ")))
+        })
+        .collect::<Vec<_>>();
+    let count = rows.len();
+    let title = format!("Workspace crates ({})", format_with_commas(scan.crates.len() as u64));
+    (Panel::Table(count_table(app, title, rows)), count)
+}
+
+/// A panel stacked under the big number on the summary screen.
+enum Panel {
+    Table(Table<'static>),
+    Bars(BarChart<'static>),
+    Spark(Sparkline<'static>),
+    Donut(Donut),
+}
+
+impl Widget for Panel {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        match self {
+            Panel::Table(table) => Widget::render(table, area, buf),
+            Panel::Bars(chart) => chart.render(area, buf),
+            Panel::Spark(sparkline) => sparkline.render(area, buf),
+            Panel::Donut(donut) => donut.render(area, buf),
+        }
+    }
+}
+
+fn history_sparkline(app: &App, width: u16) -> (Panel, usize) {
+    let entries = app.history.entries();
+    let shown = &entries[entries.len().saturating_sub(width.saturating_sub(2) as usize)..];
+    let min = shown.iter().map(|entry| entry.lines).min().unwrap_or(0);
+    // Drop the common baseline so growth stays visible on large codebases.
+    let data = shown.iter().map(|entry| entry.lines - min + 1).collect::<Vec<_>>();
+    let first = shown.first().map_or(0, |entry| entry.lines);
+    let last = shown.last().map_or(0, |entry| entry.lines);
+    let sparkline = Sparkline::default()
+        .data(&data)
+        .style(app.settings.theme.number_style())
+        .block(
+            app.settings
+                .theme
+                .block()
+                .title(format!(
+                    "History: {} → {} over {} scans",
+                    format_with_commas(first),
+                    format_with_commas(last),
+                    shown.len()
+                ))
+                .title_style(app.settings.theme.label_style()),
+        );
+    (Panel::Spark(sparkline), HISTORY_SPARKLINE_HEIGHT)
+}
+
+fn language_chart(app: &App, scan: &ScanResult) -> (Panel, usize) {
+    let bars = scan
+        .languages
+        .iter()
+        .take(LANGUAGE_CHART_BARS)
+        .map(|language| {
+            Bar::default()
+                .label(Line::from(language.name.clone()))
+                .value(language.lines)
+                .text_value(format!(
+                    "{} ({})",
+                    format_with_commas(language.lines),
+                    format_share(language.lines, scan.counted_lines())
+                ))
+        })
+        .collect::<Vec<_>>();
+    let count = bars.len();
+    let chart = BarChart::default()
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(app.settings.theme.number_style())
+        .value_style(app.settings.theme.base_style().add_modifier(Modifier::REVERSED))
+        .label_style(app.settings.theme.label_style())
+        .data(BarGroup::default().bars(&bars))
+        .block(
+            app.settings
+                .theme
+                .block()
+                .title("Top languages")
+                .title_style(app.settings.theme.label_style()),
+        );
+    (Panel::Bars(chart), count)
+}
+
+/// How many files fall in each of `FILE_SIZE_BUCKETS`.
+fn size_histogram(app: &App, scan: &ScanResult) -> (Panel, usize) {
+    let mut counts = [0u64; FILE_SIZE_BUCKETS.len()];
+    for file in &scan.file_stats {
+        let bucket = FILE_SIZE_BUCKETS
+            .iter()
+            .rposition(|(from, _)| file.lines >= *from)
+            .unwrap_or(0);
+        counts[bucket] += 1;
+    }
+    let bars = FILE_SIZE_BUCKETS
+        .iter()
+        .zip(counts)
+        .map(|((_, label), count)| {
+            Bar::default()
+                .label(Line::from(*label))
+                .value(count)
+                .text_value(format!("{} ({})", format_with_commas(count), format_share(count, scan.files)))
+        })
+        .collect::<Vec<_>>();
+    let count = bars.len();
+    let chart = BarChart::default()
+        .direction(Direction::Horizontal)
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(app.settings.theme.number_style())
+        .value_style(app.settings.theme.base_style().add_modifier(Modifier::REVERSED))
+        .label_style(app.settings.theme.label_style())
+        .data(BarGroup::default().bars(&bars))
+        .block(
+            app.settings
+                .theme
+                .block()
+                .title("Files by line count")
+                .title_style(app.settings.theme.label_style()),
+        );
+    (Panel::Bars(chart), count)
+}
+
+fn language_donut(app: &App, scan: &ScanResult) -> (Panel, usize) {
+    let mut slices = scan
+        .languages
+        .iter()
+        .take(LANGUAGE_CHART_BARS)
+        .enumerate()
+        .map(|(index, language)| (language.name.clone(), language.lines, TREEMAP_PALETTE[index % TREEMAP_PALETTE.len()]))
+        .collect::<Vec<_>>();
+    let rest = scan.languages.iter().skip(LANGUAGE_CHART_BARS).map(|language| language.lines).sum::<u64>();
+    if rest > 0 {
+        slices.push(("Other".to_string(), rest, Color::DarkGray));
+    }
+    let rows = slices.len().max(DONUT_ROWS);
+    let donut = Donut {
+        slices,
+        total: scan.counted_lines(),
+        block: app
+            .settings
+            .theme
+            .block()
+            .title("Language share")
+            .title_style(app.settings.theme.label_style()),
+        text_style: app.settings.theme.base_style(),
+    };
+    (Panel::Donut(donut), rows)
+}
+
+/// Each language's share of the lines as a slice of a ring, with a legend beside it.
+struct Donut {
+    /// Name, lines and color, clockwise from the top.
+    slices: Vec<(String, u64, Color)>,
+    total: u64,
+    block: Block<'static>,
+    text_style: Style,
+}
+
+impl Widget for Donut {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = self.block.inner(area);
+        self.block.render(area, buf);
+        // Cells are about twice as tall as they are wide, so twice as many columns as rows is round.
+        let ring_width = (inner.height * 2).min(inner.width);
+        let [ring, legend] = Layout::horizontal([Constraint::Length(ring_width), Constraint::Min(0)])
+            .spacing(2)
+            .areas(inner);
+
+        let dots = (ring.width as usize * 2, ring.height as usize * 4);
+        let slices = donut_points(&self.slices, self.total, dots);
+        Canvas::default()
+            .marker(Marker::Braille)
+            .x_bounds([-1.0, 1.0])
+            .y_bounds([-1.0, 1.0])
+            .paint(|ctx| {
+                for (coords, color) in &slices {
+                    ctx.draw(&Points { coords, color: *color });
+                }
+            })
+            .render(ring, buf);
+
+        let lines = self
+            .slices
+            .iter()
+            .map(|(name, lines, color)| {
+                Line::from(vec![
+                    Span::styled("■ ", Style::default().fg(*color)),
+                    Span::styled(format!("{} {}", name, format_share(*lines, self.total)), self.text_style),
+                ])
+            })
+            .collect::<Vec<_>>();
+        Paragraph::new(lines).render(legend, buf);
+    }
+}
+
+/// The dots of a ring `dots` wide and tall, grouped by the slice whose angle they fall in.
+fn donut_points(slices: &[(String, u64, Color)], total: u64, dots: (usize, usize)) -> Vec<(Vec<(f64, f64)>, Color)> {
+    let mut points = slices.iter().map(|(_, _, color)| (Vec::new(), *color)).collect::<Vec<_>>();
+    let total = slices.iter().map(|(_, lines, _)| *lines).sum::<u64>().max(total).max(1) as f64;
+    let (columns, rows) = dots;
+    for row in 0..rows {
+        for column in 0..columns {
+            // The middle of the dot, in the canvas' -1..1 coordinates.
+            let x = (column as f64 + 0.5) / columns as f64 * 2.0 - 1.0;
+            let y = 1.0 - (row as f64 + 0.5) / rows as f64 * 2.0;
+            let radius = x.hypot(y);
+            if !(DONUT_HOLE..=1.0).contains(&radius) {
+                continue;
+            }
+            // Clockwise from twelve o'clock, as a fraction of the full turn.
+            let turn = x.atan2(y).rem_euclid(std::f64::consts::TAU) / std::f64::consts::TAU;
+            let mut start = 0.0;
+            for (index, (_, lines, _)) in slices.iter().enumerate() {
+                let end = start + *lines as f64 / total;
+                if turn < end {
+                    points[index].0.push((x, y));
+                    break;
+                }
+                start = end;
+            }
+        }
+    }
+    points
+}
+
+fn estimate_panel(app: &App, scan: &ScanResult) -> (Panel, usize) {
+    let estimate = estimate(scan.lines, app.settings.cost_per_month);
+    let rows = vec![
+        (format_decimal(estimate.effort_months, 1), "person-months of effort".to_string()),
+        (format_decimal(estimate.schedule_months, 1), "months to develop".to_string()),
+        (format_decimal(estimate.developers, 1), "developers".to_string()),
+        (
+            format!("${}", format_with_commas(estimate.cost.round() as u64)),
+            "estimated cost".to_string(),
+        ),
+    ];
+    let count = rows.len();
+    (Panel::Table(count_table(app, "Estimate (COCOMO)".to_string(), rows)), count)
+}
+
+fn todo_summary(app: &App, scan: &ScanResult) -> (Panel, usize) {
+    let rows = app
+        .settings
+        .todo_markers
+        .iter()
+        .filter_map(|marker| {
+            let count = scan.todos.iter().filter(|todo| todo.comment.marker == *marker).count();
+            (count > 0).then(|| (format_with_commas(count as u64), marker.clone()))
+        })
+        .collect::<Vec<_>>();
+    let count = rows.len();
+    let title = format!("Tech debt ({} markers, Tab for the list)", format_with_commas(scan.todos.len() as u64));
+    (Panel::Table(count_table(app, title, rows)), count)
+}
+
+fn error_panel(app: &App, scan: &ScanResult) -> (Panel, usize) {
+    let mut rows = scan
+        .errors
+        .iter()
+        .take(ERROR_ROWS)
+        .map(|error| {
+            let path = scan.display(error.root, &error.path);
+            (error.message.clone(), if path.is_empty() { ".".to_string() } else { path })
+        })
+        .collect::<Vec<_>>();
+    if scan.errors.len() > ERROR_ROWS {
+        rows.push((String::new(), format!("… and {} more", scan.errors.len() - ERROR_ROWS)));
+    }
+    let count = rows.len();
+    let title = format!("Unreadable paths ({}), the count may be short", format_with_commas(scan.errors.len() as u64));
+    (Panel::Table(count_table(app, title, rows)), count)
+}
+
+fn top_authors(app: &App) -> (Panel, usize) {
+    let total = app.authors.iter().map(|author| author.lines).sum::<u64>();
+    let rows = app
+        .authors
+        .iter()
+        .take(TOP_AUTHORS)
+        .map(|author| {
+            (
+                format_with_commas(author.lines),
+                format!("{} ({})", author.name, format_share(author.lines, total)),
+            )
+        })
+        .collect::<Vec<_>>();
+    let count = rows.len();
+    let title = format!("Top authors by git blame ({} in all)", format_with_commas(app.authors.len() as u64));
+    (Panel::Table(count_table(app, title, rows)), count)
+}
+
+fn largest_files(app: &App, scan: &ScanResult) -> (Panel, usize) {
+    let rows = scan
+        .largest
+        .iter()
+        .map(|file| (format_with_commas(file.lines), scan.display_path(file)))
+        .collect::<Vec<_>>();
+    let title = format!("Top {} largest files", scan.largest.len());
+    (Panel::Table(count_table(app, title, rows)), scan.largest.len())
+}
+
+fn license_summary(app: &App, scan: &ScanResult) -> Option<(Panel, usize)> {
+    let licenses = scan.license_stats();
+    if licenses.is_empty() {
+        return None;
+    }
+    let mut rows = licenses
+        .iter()
+        .map(|license| (format_with_commas(license.files), license.name.clone()))
+        .collect::<Vec<_>>();
+    let unlicensed = scan.unlicensed_files().len();
+    if unlicensed > 0 {
+        rows.push((format_with_commas(unlicensed as u64), "no license header".to_string()));
+    }
+    let count = rows.len();
+    Some((Panel::Table(count_table(app, "Licenses in file headers".to_string(), rows)), count))
+}
+
+fn complex_files(app: &App, scan: &ScanResult) -> Option<(Panel, usize)> {
+    let files = scan.most_complex();
+    if files.is_empty() {
+        return None;
+    }
+    let rows = files
+        .iter()
+        .map(|file| {
+            let complexity = file.complexity.unwrap_or_default();
+            (
+                format_with_commas(complexity.branches),
+                format!("{} (nesting {})", scan.display_path(file), complexity.max_depth),
+            )
+        })
+        .collect::<Vec<_>>();
+    let title = format!("Top {} most complex files, by branches", files.len());
+    Some((Panel::Table(count_table(app, title, rows)), files.len()))
+}
+
+fn count_table(app: &App, title: String, rows: Vec<(String, String)>) -> Table<'static> {
+    let count_width = rows.iter().map(|(count, _)| count.len()).max().unwrap_or(0) as u16;
+    let rows = rows.into_iter().map(|(count, label)| {
+        Row::new(vec![
+            Cell::from(Line::from(count).alignment(Alignment::Right)),
+            Cell::from(label),
+        ])
+    });
+    Table::new(rows, [Constraint::Length(count_width), Constraint::Min(10)])
+        .column_spacing(2)
+        .block(
+            app.settings
+                .theme
+                .block()
+                .title(title)
+                .title_style(app.settings.theme.label_style()),
+        )
+}
+
+struct TableViewLayout {
+    header: Rect,
+    table: Rect,
+    hint: Rect,
+}
+
+fn table_view_layout(area: Rect) -> TableViewLayout {
+    let header_height = 3u16.min(area.height);
+    let hint_height = 1u16.min(area.height.saturating_sub(header_height));
+    TableViewLayout {
+        header: Rect {
+            height: header_height,
+            ..area
+        },
+        table: Rect {
+            y: area.y + header_height,
+            height: area.height.saturating_sub(header_height + hint_height),
+            ..area
+        },
+        hint: Rect {
+            y: area.y + area.height.saturating_sub(hint_height),
+            height: hint_height,
+            ..area
+        },
+    }
+}
+
+fn table_hint(app: &App) -> Paragraph<'static> {
+    const SCROLL: &[Action] = &[Action::Up, Action::Down, Action::PageUp, Action::PageDown];
+    let hints: &[(&[Action], &str)] = match app.view {
+        View::Directories => &[
+            (&[Action::Up, Action::Down], "select"),
+            (&[Action::EnterDirectory], "open directory"),
+            (&[Action::LeaveDirectory], "go up"),
+            (&[Action::NextView], "next view"),
+            (&[Action::Help], "help"),
+            (&[Action::Quit], "quit"),
+        ],
+        View::Trend => &[(&[Action::NextView], "next view"), (&[Action::Help], "help"), (&[Action::Quit], "quit")],
+        View::Repos => &[
+            (SCROLL, "scroll"),
+            (&[Action::Rescan], "rescan all"),
+            (&[Action::NextView], "next view"),
+            (&[Action::Help], "help"),
+            (&[Action::Quit], "quit"),
+        ],
+        View::Packages | View::Todos | View::LongLines | View::Duplicates | View::Authors => &[
+            (SCROLL, "scroll"),
+            (&[Action::NextView], "next view"),
+            (&[Action::Help], "help"),
+            (&[Action::Quit], "quit"),
+        ],
+        View::Files => &[
+            (SCROLL, "scroll"),
+            (&[Action::Open], "open in $EDITOR"),
+            (&[Action::Sort], "sort"),
+            (&[Action::ReverseSort], "reverse"),
+            (&[Action::Help], "help"),
+            (&[Action::Quit], "quit"),
+        ],
+        _ => &[
+            (SCROLL, "scroll"),
+            (&[Action::Sort], "sort column"),
+            (&[Action::ReverseSort], "reverse"),
+            (&[Action::NextView], "next view"),
+            (&[Action::Help], "help"),
+            (&[Action::Quit], "quit"),
+        ],
+    };
+    let hint = match app.status_message() {
+        Some(message) => message.to_string(),
+        None => key_hints(app, hints),
+    };
+    Paragraph::new(Line::from(hint))
+}
+
+/// "Keys: r = rescan, ? = help, …", naming each action by the first key bound to it in the
+/// keymap in effect. Actions left without a key are left out.
+fn key_hints(app: &App, hints: &[(&[Action], &str)]) -> String {
+    let hints = hints
+        .iter()
+        .filter_map(|(actions, what)| {
+            let keys = actions
+                .iter()
+                .filter_map(|action| app.settings.keymap.first_key(*action))
+                .collect::<Vec<_>>();
+            (!keys.is_empty()).then(|| format!("{} = {}", keys.join("/"), what))
+        })
+        .collect::<Vec<_>>();
+    format!("Keys: {}.", hints.join(", "))
+}
+
+fn sort_header(label: &str, key: SortKey, sort: Sort) -> String {
+    if sort.key != key {
+        return label.to_string();
+    }
+    format!("{} {}", label, if sort.descending { "▼" } else { "▲" })
+}
+
+fn right(text: String) -> Cell<'static> {
+    Cell::from(Line::from(text).alignment(Alignment::Right))
+}
+
+/// Click targets for the sortable header cells of a bordered table, laid out the
+/// way `Table` places its columns.
+fn header_hits(area: Rect, widths: &[Constraint], keys: &[Option<SortKey>]) -> Vec<(Rect, Hit)> {
+    let inner = area.inner(Margin::new(1, 1));
+    let header = Rect {
+        height: inner.height.min(1),
+        ..inner
+    };
+    let columns = Layout::horizontal(widths.iter().copied())
+        .flex(Flex::Start)
+        .spacing(2)
+        .split(header);
+    columns
+        .iter()
+        .zip(keys)
+        .filter_map(|(column, key)| key.map(|key| (*column, Hit::Sort(key))))
+        .collect()
+}
+
+/// Click targets for the rows of a bordered table with a one-line header, once it
+/// has been rendered and `offset` reflects the scroll position.
+fn row_hits(area: Rect, offset: usize, count: usize) -> Vec<(Rect, Hit)> {
+    let inner = area.inner(Margin::new(1, 1));
+    let visible = inner.height.saturating_sub(1) as usize;
+    (offset..count)
+        .take(visible)
+        .enumerate()
+        .map(|(line, index)| {
+            let rect = Rect {
+                y: inner.y + 1 + line as u16,
+                height: 1,
+                ..inner
+            };
+            (rect, Hit::Row(index))
+        })
+        .collect()
+}
+
+fn draw_languages(frame: &mut Frame, app: &mut App, area: Rect) {
+    let layout = table_view_layout(area);
+    app.page = layout.table.height.saturating_sub(3).max(1) as usize;
+
+    let sort = app.language_sort;
+    let header = Row::new(vec![
+        Cell::from(sort_header("Language", SortKey::Name, sort)),
+        right(sort_header("Files", SortKey::Files, sort)),
+        right(sort_header("Lines", SortKey::Lines, sort)),
+        right(sort_header("Bytes", SortKey::Bytes, sort)),
+        right("Chars".to_string()),
+        right(sort_header("Words", SortKey::Words, sort)),
+        right("Mean".to_string()),
+        right("Median".to_string()),
+        right("P95".to_string()),
+        right("Share".to_string()),
+    ])
+    .style(app.settings.theme.label_style());
+
+    let languages = app.scan.as_ref().map_or(&[][..], |scan| &scan.languages[..]);
+    let total = app.scan.as_ref().map_or(0, ScanResult::counted_lines);
+    let rows = languages.iter().map(|language| {
+        Row::new(vec![
+            Cell::from(language.name.clone()),
+            right(format_with_commas(language.files)),
+            right(format_with_commas(language.lines)),
+            right(format_bytes(language.size.bytes)),
+            right(format_with_commas(language.size.chars)),
+            right(format_with_commas(language.size.words)),
+            right(format_decimal(language.mean_lines, 0)),
+            right(format_with_commas(language.median_lines)),
+            right(format_with_commas(language.p95_lines)),
+            right(format_share(language.lines, total)),
+        ])
+    });
+    let widths = [
+        Constraint::Min(20),
+        Constraint::Length(10),
+        Constraint::Length(12),
+        Constraint::Length(10),
+        Constraint::Length(13),
+        Constraint::Length(12),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(8),
+    ];
+    let count = languages.len();
+    let table = Table::new(rows, widths)
+        .header(header)
+        .column_spacing(2)
+        .row_highlight_style(app.settings.theme.highlight_style())
+        .block(app.settings.theme.block().title(format!("Languages ({})", count)));
+    if app.language_table.selected().is_none() && count > 0 {
+        app.language_table.select(Some(0));
+    }
+
+    frame.render_widget(headline(app), layout.header);
+    frame.render_widget(table_hint(app), layout.hint);
+    frame.render_stateful_widget(table, layout.table, &mut app.language_table);
+    let keys = [
+        Some(SortKey::Name),
+        Some(SortKey::Files),
+        Some(SortKey::Lines),
+        Some(SortKey::Bytes),
+        None,
+        Some(SortKey::Words),
+        None,
+        None,
+        None,
+        None,
+    ];
+    app.hits.extend(header_hits(layout.table, &widths, &keys));
+    app.hits.extend(row_hits(layout.table, app.language_table.offset(), count));
+}
+
+fn draw_files(frame: &mut Frame, app: &mut App, area: Rect) {
+    let layout = table_view_layout(area);
+    app.page = layout.table.height.saturating_sub(3).max(1) as usize;
+
+    let sort = app.file_sort;
+    let header = Row::new(vec![
+        right(sort_header("Lines", SortKey::Lines, sort)),
+        Cell::from(sort_header("Path", SortKey::Name, sort)),
+    ])
+    .style(app.settings.theme.label_style());
+
+    let files = app.scan.as_ref().map_or(&[][..], |scan| &scan.file_stats[..]);
+    let rows = app.scan.iter().flat_map(|scan| {
+        scan.file_stats
+            .iter()
+            .map(|file| Row::new(vec![right(format_with_commas(file.lines)), Cell::from(scan.display_path(file))]))
+    });
+    let lines_width = files
+        .iter()
+        .map(|file| format_with_commas(file.lines).len())
+        .max()
+        .unwrap_or(0)
+        .max(7) as u16;
+    let widths = [Constraint::Length(lines_width), Constraint::Min(10)];
+    let count = files.len();
+    let table = Table::new(rows, widths)
+        .header(header)
+        .column_spacing(2)
+        .row_highlight_style(app.settings.theme.highlight_style())
+        .block(app.settings.theme.block().title(format!("Files ({})", format_with_commas(count as u64))));
+    if app.file_table.selected().is_none() && count > 0 {
+        app.file_table.select(Some(0));
+    }
+
+    frame.render_widget(headline(app), layout.header);
+    frame.render_widget(table_hint(app), layout.hint);
+    frame.render_stateful_widget(table, layout.table, &mut app.file_table);
+    app.hits.extend(header_hits(layout.table, &widths, &[Some(SortKey::Lines), Some(SortKey::Name)]));
+    app.hits.extend(row_hits(layout.table, app.file_table.offset(), count));
+}
+
+const TREEMAP_PALETTE: &[Color] = &[
+    Color::Blue,
+    Color::Green,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Red,
+    Color::Yellow,
+    Color::LightBlue,
+    Color::LightGreen,
+    Color::LightMagenta,
+    Color::LightRed,
+];
+const FLASH_PERIOD: Duration = Duration::from_millis(400);
+/// Rows below which the status bar gives its row to the view.
+const STATUS_BAR_MIN_HEIGHT: u16 = 10;
+/// The big number's box is never narrower than its title, which says whether the number is the
+/// total or every category's lines.
+const NUMBER_TITLE: &str = "Lines of Code";
+const ALL_LINES_TITLE: &str = "All Lines";
+/// Columns from which the summary puts the languages beside the number.
+const WIDE_SUMMARY: u16 = 140;
+/// Language rows the wide summary's table keeps before it gives up the chart under it.
+const SIDE_TABLE_ROWS: u16 = 5;
+const HISTORY_SPARKLINE_HEIGHT: usize = 3;
+const LANGUAGE_CHART_BARS: usize = 8;
+/// Lower bound and label of each bar in the file size histogram.
+const FILE_SIZE_BUCKETS: &[(u64, &str)] = &[(0, "0–50"), (50, "50–200"), (200, "200–1000"), (1000, "1000+")];
+/// Height of the language donut; its legend can make the panel taller.
+const DONUT_ROWS: usize = 8;
+/// The hole in the middle of the donut, as a fraction of its radius.
+const DONUT_HOLE: f64 = 0.5;
+const TREEMAP_MIN_TILE: u16 = 4;
+const TOP_AUTHORS: usize = 5;
+const TOP_CRATES: usize = 8;
+/// Unreadable paths listed on the summary before the rest are summed up.
+const ERROR_ROWS: usize = 5;
+
+fn draw_todos(frame: &mut Frame, app: &mut App, area: Rect) {
+    let layout = table_view_layout(area);
+    app.page = layout.table.height.saturating_sub(3).max(1) as usize;
+
+    let todos = app.scan.as_ref().map_or(&[][..], |scan| &scan.todos[..]);
+    let rows = app.scan.iter().flat_map(|scan| {
+        scan.todos.iter().map(|todo| {
+            Row::new(vec![
+                Cell::from(format!("{}:{}", scan.display(todo.root, &todo.path), todo.comment.line)),
+                Cell::from(Span::styled(todo.comment.marker.clone(), app.settings.theme.number_style())),
+                Cell::from(todo.comment.text.clone()),
+            ])
+        })
+    });
+    let location_width = app
+        .scan
+        .iter()
+        .flat_map(|scan| {
+            scan.todos
+                .iter()
+                .map(|todo| scan.display(todo.root, &todo.path).len() + todo.comment.line.to_string().len() + 1)
+        })
+        .max()
+        .unwrap_or(0)
+        .clamp(8, 50) as u16;
+    let marker_width = app.settings.todo_markers.iter().map(|marker| marker.len()).max().unwrap_or(4) as u16;
+    let header = Row::new(vec![Cell::from("Location"), Cell::from("Marker"), Cell::from("Comment")])
+        .style(app.settings.theme.label_style());
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(location_width),
+            Constraint::Length(marker_width.max(6)),
+            Constraint::Min(10),
+        ],
+    )
+    .header(header)
+    .column_spacing(2)
+    .row_highlight_style(app.settings.theme.highlight_style())
+    .block(app.settings.theme.block().title(format!("Tech debt ({})", format_with_commas(todos.len() as u64))));
+    if app.todo_table.selected().is_none() && !todos.is_empty() {
+        app.todo_table.select(Some(0));
+    }
+
+    frame.render_widget(headline(app), layout.header);
+    frame.render_widget(table_hint(app), layout.hint);
+    let count = todos.len();
+    frame.render_stateful_widget(table, layout.table, &mut app.todo_table);
+    app.hits.extend(row_hits(layout.table, app.todo_table.offset(), count));
+}
+
+fn draw_long_lines(frame: &mut Frame, app: &mut App, area: Rect) {
+    let layout = table_view_layout(area);
+    app.page = layout.table.height.saturating_sub(3).max(1) as usize;
+
+    let files = app.scan.as_ref().map(ScanResult::long_line_files).unwrap_or_default();
+    let rows = files
+        .iter()
+        .map(|file| {
+            Row::new(vec![
+                right(format_with_commas(file.longest_line)),
+                right(format_with_commas(file.size.mean_line(file.lines).round() as u64)),
+                right(format_with_commas(file.lines)),
+                Cell::from(app.scan.as_ref().map(|scan| scan.display_path(file)).unwrap_or_default()),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let header = Row::new(vec![
+        right("Longest".to_string()),
+        right("Average".to_string()),
+        right("Lines".to_string()),
+        Cell::from("Path"),
+    ])
+    .style(app.settings.theme.label_style());
+    let count = rows.len();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Min(10),
+        ],
+    )
+    .header(header)
+    .column_spacing(2)
+    .row_highlight_style(app.settings.theme.highlight_style())
+    .block(app.settings.theme.block().title(format!(
+        "Files with lines of {}+ characters, likely minified or data ({})",
+        format_with_commas(LONG_LINE),
+        format_with_commas(count as u64)
+    )));
+    if app.long_line_table.selected().is_none() && count > 0 {
+        app.long_line_table.select(Some(0));
+    }
+
+    frame.render_widget(headline(app), layout.header);
+    frame.render_widget(table_hint(app), layout.hint);
+    frame.render_stateful_widget(table, layout.table, &mut app.long_line_table);
+    app.hits.extend(row_hits(layout.table, app.long_line_table.offset(), count));
+}
+
+fn draw_duplicates(frame: &mut Frame, app: &mut App, area: Rect) {
+    let layout = table_view_layout(area);
+    app.page = layout.table.height.saturating_sub(3).max(1) as usize;
+
+    let groups = app.scan.as_ref().map(ScanResult::duplicate_groups).unwrap_or_default();
+    let rows = groups
+        .iter()
+        .map(|group| {
+            let paths = group
+                .files
+                .iter()
+                .map(|file| app.scan.as_ref().map(|scan| scan.display_path(file)).unwrap_or_default())
+                .collect::<Vec<_>>();
+            Row::new(vec![
+                right(format_with_commas(group.duplicated_lines())),
+                right(format_with_commas(group.files.len() as u64)),
+                right(format_with_commas(group.lines)),
+                Cell::from(paths.join(", ")),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let header = Row::new(vec![
+        right("Duplicated".to_string()),
+        right("Files".to_string()),
+        right("Lines".to_string()),
+        Cell::from("Paths"),
+    ])
+    .style(app.settings.theme.label_style());
+    let count = rows.len();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Length(9),
+            Constraint::Min(10),
+        ],
+    )
+    .header(header)
+    .column_spacing(2)
+    .row_highlight_style(app.settings.theme.highlight_style())
+    .block(
+        app.settings
+            .theme
+            .block()
+            .title(format!("Files with identical contents ({} sets)", format_with_commas(count as u64))),
+    );
+    if app.duplicate_table.selected().is_none() && count > 0 {
+        app.duplicate_table.select(Some(0));
+    }
+
+    frame.render_widget(headline(app), layout.header);
+    frame.render_widget(table_hint(app), layout.hint);
+    frame.render_stateful_widget(table, layout.table, &mut app.duplicate_table);
+    app.hits.extend(row_hits(layout.table, app.duplicate_table.offset(), count));
+}
+
+fn draw_authors(frame: &mut Frame, app: &mut App, area: Rect) {
+    let layout = table_view_layout(area);
+    app.page = layout.table.height.saturating_sub(3).max(1) as usize;
+
+    let total = app.authors.iter().map(|author| author.lines).sum::<u64>();
+    let rows = app.authors.iter().map(|author| {
+        Row::new(vec![
+            right(format_with_commas(author.lines)),
+            right(format_share(author.lines, total)),
+            right(format_with_commas(author.files)),
+            Cell::from(author.name.clone()),
+        ])
+    });
+    let lines_width = app
+        .authors
+        .iter()
+        .map(|author| format_with_commas(author.lines).len())
+        .max()
+        .unwrap_or(0)
+        .max(5) as u16;
+    let header = Row::new(vec![
+        right("Lines".to_string()),
+        right("Share".to_string()),
+        right("Files".to_string()),
+        Cell::from("Author"),
+    ])
+    .style(app.settings.theme.label_style());
+    let count = app.authors.len();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(lines_width),
+            Constraint::Length(6),
+            Constraint::Length(7),
+            Constraint::Min(10),
+        ],
+    )
+    .header(header)
+    .column_spacing(2)
+    .row_highlight_style(app.settings.theme.highlight_style())
+    .block(app.settings.theme.block().title(format!(
+        "Lines by author at HEAD, per git blame ({})",
+        format_with_commas(count as u64)
+    )));
+    if app.author_table.selected().is_none() && count > 0 {
+        app.author_table.select(Some(0));
+    }
+
+    frame.render_widget(headline(app), layout.header);
+    frame.render_widget(table_hint(app), layout.hint);
+    frame.render_stateful_widget(table, layout.table, &mut app.author_table);
+    app.hits.extend(row_hits(layout.table, app.author_table.offset(), count));
+}
+
+fn draw_packages(frame: &mut Frame, app: &mut App, area: Rect) {
+    let layout = table_view_layout(area);
+    app.page = layout.table.height.saturating_sub(3).max(1) as usize;
+
+    let Some(scan) = &app.scan else {
+        return;
+    };
+    let rows = scan.packages.iter().map(|package| {
+        let dir = scan.display(package.root, &scan.roots[package.root].path.join(&package.path));
+        Row::new(vec![
+            right(format_with_commas(package.lines)),
+            right(format_share(package.lines, scan.lines)),
+            right(format_with_commas(package.files)),
+            Cell::from(package.kind.name()),
+            Cell::from(package.name.clone()),
+            Cell::from(if dir.is_empty() { ".".to_string() } else { dir }),
+        ])
+    });
+    let lines_width = scan
+        .packages
+        .iter()
+        .map(|package| format_with_commas(package.lines).len())
+        .max()
+        .unwrap_or(0)
+        .max(5) as u16;
+    let name_width = scan.packages.iter().map(|package| package.name.chars().count()).max().unwrap_or(0).max(7) as u16;
+    let header = Row::new(vec![
+        right("Lines".to_string()),
+        right("Share".to_string()),
+        right("Files".to_string()),
+        Cell::from("Kind"),
+        Cell::from("Package"),
+        Cell::from("Directory"),
+    ])
+    .style(app.settings.theme.label_style());
+    let count = scan.packages.len();
+    let outside = scan.lines.saturating_sub(scan.packages.iter().map(|package| package.lines).sum());
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(lines_width),
+            Constraint::Length(6),
+            Constraint::Length(7),
+            Constraint::Length(6),
+            Constraint::Length(name_width.min(40)),
+            Constraint::Min(10),
+        ],
+    )
+    .header(header)
+    .column_spacing(2)
+    .row_highlight_style(app.settings.theme.highlight_style())
+    .block(app.settings.theme.block().title(format!(
+        "Lines by package ({}), {} lines outside them",
+        format_with_commas(count as u64),
+        format_with_commas(outside)
+    )));
+    if app.package_table.selected().is_none() && count > 0 {
+        app.package_table.select(Some(0));
+    }
+
+    frame.render_widget(headline(app), layout.header);
+    frame.render_widget(table_hint(app), layout.hint);
+    frame.render_stateful_widget(table, layout.table, &mut app.package_table);
+    app.hits.extend(row_hits(layout.table, app.package_table.offset(), count));
+}
+
+/// How many of a repository's latest scans its trend column shows.
+const REPO_TREND_SCANS: usize = 16;
+
+fn draw_repos(frame: &mut Frame, app: &mut App, area: Rect) {
+    let layout = table_view_layout(area);
+    app.page = layout.table.height.saturating_sub(3).max(1) as usize;
+
+    let Some(dashboard) = &app.dashboard else {
+        return;
+    };
+    let rows = dashboard.repos.iter().map(|repo| {
+        let entries = repo.history.entries();
+        let recent = &entries[entries.len().saturating_sub(REPO_TREND_SCANS)..];
+        let lines = recent.iter().map(|entry| entry.lines).collect::<Vec<_>>();
+        let change = match (recent.first(), recent.last()) {
+            (Some(first), Some(last)) if recent.len() > 1 => format_signed(last.lines as i64 - first.lines as i64),
+            _ => String::new(),
+        };
+        let status = if repo.scanning() {
+            "scanning…".to_string()
+        } else if let Some(err) = &repo.error {
+            err.clone()
+        } else if let Some(latest) = repo.latest() {
+            let age = (Local::now() - latest.scanned_at).to_std().unwrap_or_default().as_secs();
+            format!("{} ago", format_duration(Duration::from_secs(age)))
+        } else {
+            "not yet".to_string()
+        };
+        let status_style = if repo.error.is_some() && !repo.scanning() {
+            Style::default().fg(Color::Red)
+        } else {
+            Style::default().add_modifier(Modifier::DIM)
+        };
+        Row::new(vec![
+            Cell::from(repo.name.clone()),
+            right(repo.latest().map_or_else(String::new, |latest| format_with_commas(latest.lines))),
+            right(repo.latest().map_or_else(String::new, |latest| format_with_commas(latest.files))),
+            Cell::from(sparkline(&lines)),
+            right(change),
+            Cell::from(status).style(status_style),
+        ])
+    });
+    let name_width = dashboard.repos.iter().map(|repo| repo.name.chars().count()).max().unwrap_or(0).max(4) as u16;
+    let header = Row::new(vec![
+        Cell::from("Repo"),
+        right("Lines".to_string()),
+        right("Files".to_string()),
+        Cell::from("Trend"),
+        right("Change".to_string()),
+        Cell::from("Scanned"),
+    ])
+    .style(app.settings.theme.label_style());
+    let count = dashboard.repos.len();
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(name_width.min(30)),
+            Constraint::Length(11),
+            Constraint::Length(7),
+            Constraint::Length(REPO_TREND_SCANS as u16),
+            Constraint::Length(9),
+            Constraint::Min(10),
+        ],
+    )
+    .header(header)
+    .column_spacing(2)
+    .row_highlight_style(app.settings.theme.highlight_style())
+    .block(app.settings.theme.block().title(format!(
+        "Repositories, rescanned in the background ({})",
+        format_with_commas(count as u64)
+    )));
+    if app.repo_table.selected().is_none() && count > 0 {
+        app.repo_table.select(Some(0));
+    }
+
+    frame.render_widget(headline(app), layout.header);
+    frame.render_widget(table_hint(app), layout.hint);
+    frame.render_stateful_widget(table, layout.table, &mut app.repo_table);
+    app.hits.extend(row_hits(layout.table, app.repo_table.offset(), count));
+}
+
+/// `values` as a row of block characters, the lowest of them at the bottom and the highest
+/// a full block.
+fn sparkline(values: &[u64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let (Some(low), Some(high)) = (values.iter().min(), values.iter().max()) else {
+        return String::new();
+    };
+    values
+        .iter()
+        .map(|value| match high - low {
+            0 => BARS[3],
+            range => BARS[((value - low) * 7 / range) as usize],
+        })
+        .collect()
+}
+
+fn draw_directories(frame: &mut Frame, app: &mut App, area: Rect) {
+    let layout = table_view_layout(area);
+    let treemap_height = (layout.table.height * 2 / 5).clamp(3.min(layout.table.height), 12);
+    let treemap_rect = Rect {
+        height: treemap_height,
+        ..layout.table
+    };
+    let list_rect = Rect {
+        y: layout.table.y + treemap_height,
+        height: layout.table.height - treemap_height,
+        ..layout.table
+    };
+    app.page = list_rect.height.saturating_sub(3).max(1) as usize;
+
+    let empty = DirNode::default();
+    let dir = app.current_dir().unwrap_or(&empty);
+    if app.dir_table.selected().is_none() && !dir.children.is_empty() {
+        app.dir_table.select(Some(0));
+    }
+    let selected = app.dir_table.selected();
+    let dir = app.current_dir().unwrap_or(&empty);
+    let breadcrumb = std::iter::once(".")
+        .chain(app.dir_path.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let treemap = app.settings.theme.block().title(format!(
+        "{} ({} lines)",
+        breadcrumb,
+        format_with_commas(dir.lines)
+    ));
+    let tiles_rect = treemap.inner(treemap_rect);
+    let tiles = treemap_tiles(dir, tiles_rect, selected);
+    let count = dir.children.len();
+
+    let max = dir.children.first().map_or(1, |child| child.lines.max(1));
+    let bar_width = 20u64;
+    let rows = dir
+        .children
+        .iter()
+        .map(|child| {
+            let name = if child.is_dir { format!("{}/", child.name) } else { child.name.clone() };
+            let bar = "█".repeat((child.lines * bar_width).div_ceil(max) as usize);
+            Row::new(vec![
+                Cell::from(name),
+                right(format_with_commas(child.files)),
+                right(format_with_commas(child.lines)),
+                right(format_share(child.lines, dir.lines)),
+                Cell::from(Span::styled(bar, app.settings.theme.number_style())),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let header = Row::new(vec![
+        Cell::from("Name"),
+        right("Files".to_string()),
+        right("Lines".to_string()),
+        right("Share".to_string()),
+        Cell::from(""),
+    ])
+    .style(app.settings.theme.label_style());
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(20),
+            Constraint::Length(8),
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Length(bar_width as u16),
+        ],
+    )
+    .header(header)
+    .column_spacing(2)
+    .row_highlight_style(app.settings.theme.highlight_style())
+    .block(app.settings.theme.block().title(format!("Entries ({})", dir.children.len())));
+
+    frame.render_widget(headline(app), layout.header);
+    frame.render_widget(table_hint(app), layout.hint);
+    frame.render_widget(treemap, treemap_rect);
+    let mut hits = Vec::new();
+    for (tile, rect, index) in tiles {
+        frame.render_widget(tile, rect);
+        if let Some(index) = index {
+            hits.push((rect, Hit::Row(index)));
+        }
+    }
+    frame.render_stateful_widget(table, list_rect, &mut app.dir_table);
+    hits.extend(row_hits(list_rect, app.dir_table.offset(), count));
+    app.hits.extend(hits);
+}
+
+/// Lays the children of `dir` out left to right, each as wide as its share of the lines.
+/// Children too small for a readable tile are merged into a trailing "…" tile,
+/// which is the only one without a child index.
+fn treemap_tiles(dir: &DirNode, area: Rect, selected: Option<usize>) -> Vec<(Paragraph<'static>, Rect, Option<usize>)> {
+    let mut tiles = Vec::new();
+    if area.width == 0 || area.height == 0 || dir.lines == 0 {
+        return tiles;
+    }
+    let mut x = area.x;
+    let right_edge = area.x + area.width;
+    for (index, child) in dir.children.iter().enumerate() {
+        let width = (child.lines * area.width as u64 / dir.lines) as u16;
+        let remaining = right_edge - x;
+        if width < TREEMAP_MIN_TILE || remaining < TREEMAP_MIN_TILE {
+            break;
+        }
+        let is_last = index + 1 == dir.children.len();
+        let width = if is_last { remaining } else { width.min(remaining) };
+        let mut style = Style::default()
+            .fg(Color::Black)
+            .bg(TREEMAP_PALETTE[index % TREEMAP_PALETTE.len()]);
+        if selected == Some(index) {
+            style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+        }
+        let name = if child.is_dir { format!("{}/", child.name) } else { child.name.clone() };
+        let tile = Paragraph::new(vec![Line::from(name), Line::from(format_with_commas(child.lines))]).style(style);
+        tiles.push((
+            tile,
+            Rect {
+                x,
+                width,
+                ..area
+            },
+            Some(index),
+        ));
+        x += width;
+    }
+    if x < right_edge {
+        let rest = Paragraph::new("…").style(Style::default().fg(Color::Black).bg(Color::Gray));
+        tiles.push((
+            rest,
+            Rect {
+                x,
+                width: right_edge - x,
+                ..area
+            },
+            None,
+        ));
+    }
+    tiles
+}
+
+fn draw_trend(frame: &mut Frame, app: &mut App, area: Rect) {
+    let layout = table_view_layout(area);
+    let trend = &app.trend;
+    let points = trend
+        .iter()
+        .enumerate()
+        .map(|(index, point)| (index as f64, point.lines as f64))
+        .collect::<Vec<_>>();
+    let min = trend.iter().map(|point| point.lines).min().unwrap_or(0);
+    let max = trend.iter().map(|point| point.lines).max().unwrap_or(0);
+    // Pad the y range so a flat trend still draws as a line in the middle.
+    let padding = ((max - min) / 10).max(1);
+    let y_bounds = [min.saturating_sub(padding) as f64, (max + padding) as f64];
+    let x_labels = match (trend.first(), trend.last()) {
+        (Some(first), Some(last)) => vec![
+            Line::from(format!("{} {}", first.date.format("%Y-%m-%d"), first.commit)),
+            Line::from(format!("{} {}", last.date.format("%Y-%m-%d"), last.commit)).alignment(Alignment::Right),
+        ],
+        _ => Vec::new(),
+    };
+
+    let dataset = Dataset::default()
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(app.settings.theme.number_style())
+        .data(&points);
+    let chart = Chart::new(vec![dataset])
+        .block(app.settings.theme.block().title(format!("Lines of code at {} points in git history", trend.len())))
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, trend.len().saturating_sub(1).max(1) as f64])
+                .labels(x_labels)
+                .style(app.settings.theme.label_style()),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds(y_bounds)
+                .labels(vec![
+                    Line::from(format_with_commas(min.saturating_sub(padding))),
+                    Line::from(format_with_commas(max + padding)),
+                ])
+                .style(app.settings.theme.label_style()),
+        );
+
+    frame.render_widget(headline(app), layout.header);
+    frame.render_widget(table_hint(app), layout.hint);
+    frame.render_widget(chart, layout.table);
+}
+
+pub fn draw_diff(frame: &mut Frame, diff: &ScanDiff, settings: &Settings, table: &mut TableState) {
+    let layout = table_view_layout(frame.area());
+    let theme = &settings.theme;
+    frame.render_widget(Block::default().style(theme.base_style()), frame.area());
+
+    let headline = Paragraph::new(Line::from(format!(
+        "{} → {}: {} lines ({})",
+        diff.a.display(),
+        diff.b.display(),
+        format_signed(diff.change()),
+        format_relative(diff.a_lines, diff.b_lines)
+    )))
+    .alignment(Alignment::Center)
+    .block(theme.block());
+
+    let header = Row::new(vec![
+        Cell::from("Language"),
+        right("A".to_string()),
+        right("B".to_string()),
+        right("Change".to_string()),
+        right("Relative".to_string()),
+    ])
+    .style(theme.label_style());
+    let total = [("Total".to_string(), diff.a_lines, diff.b_lines)];
+    let rows = diff
+        .languages
+        .iter()
+        .map(|language| (language.name.clone(), language.a_lines, language.b_lines))
+        .chain(total)
+        .map(|(name, a, b)| {
+            let change = b as i64 - a as i64;
+            let change_style = match change.signum() {
+                1 => Style::default().fg(Color::Green),
+                -1 => Style::default().fg(Color::Red),
+                _ => Style::default(),
+            };
+            Row::new(vec![
+                Cell::from(name),
+                right(format_with_commas(a)),
+                right(format_with_commas(b)),
+                right(format_signed(change)).style(change_style),
+                right(format_relative(a, b)).style(change_style),
+            ])
+        });
+    let table_widget = Table::new(
+        rows,
+        [
+            Constraint::Min(20),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(10),
+        ],
+    )
+    .header(header)
+    .column_spacing(2)
+    .row_highlight_style(theme.highlight_style())
+    .block(theme.block().title(format!("Languages ({})", diff.languages.len())));
+
+    frame.render_widget(headline, layout.header);
+    frame.render_widget(
+        Paragraph::new("Keys: ↑/↓ = scroll, q = quit. A is the first directory, B the second."),
+        layout.hint,
+    );
+    frame.render_stateful_widget(table_widget, layout.table, table);
+}
+
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    let x = area.x + area.width.saturating_sub(width) / 2;
+    let y = area.y + area.height.saturating_sub(height) / 2;
+    Rect {
+        x,
+        y,
+        width,
+        height,
+    }
+}
@@ -0,0 +1,182 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use globset::GlobBuilder;
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::config::Settings;
+use crate::scan::{Bucket, FileStat};
+
+/// A member crate of a Cargo workspace at the top of a scanned root, with its part of the
+/// total split by the directories Cargo looks for targets in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrateStat {
+    pub root: usize,
+    pub name: String,
+    /// The crate's directory, relative to the root; empty for the workspace's own package.
+    pub path: PathBuf,
+    pub lines: u64,
+    pub files: u64,
+    pub src: Bucket,
+    pub tests: Bucket,
+    pub benches: Bucket,
+    /// The rest of the crate: `examples/`, `build.rs`, the manifest and so on.
+    pub other: Bucket,
+}
+
+impl CrateStat {
+    /// The parts of the crate with lines in the total, by name.
+    pub fn parts(&self) -> Vec<(&'static str, Bucket)> {
+        [("src", self.src), ("tests", self.tests), ("benches", self.benches), ("other", self.other)]
+            .into_iter()
+            .filter(|(_, part)| part.lines > 0)
+            .collect()
+    }
+}
+
+/// The few keys of a `Cargo.toml` that matter here; the rest are ignored.
+#[derive(Deserialize)]
+struct Manifest {
+    package: Option<Package>,
+    workspace: Option<Workspace>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Workspace {
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+/// The member crates of every root whose `Cargo.toml` declares a `[workspace]`, each with
+/// the weighted lines of the files under its directory. A file in a crate nested inside
+/// another counts for the inner one only. Sorted by root, then largest first.
+pub fn crate_stats(roots: &[PathBuf], file_stats: &[FileStat], settings: &Settings) -> Vec<CrateStat> {
+    let mut crates = Vec::new();
+    for (index, root) in roots.iter().enumerate() {
+        let mut members = members(root)
+            .into_iter()
+            .map(|(name, path)| CrateStat {
+                root: index,
+                name,
+                path,
+                lines: 0,
+                files: 0,
+                src: Bucket::default(),
+                tests: Bucket::default(),
+                benches: Bucket::default(),
+                other: Bucket::default(),
+            })
+            .collect::<Vec<_>>();
+        for file in file_stats.iter().filter(|file| file.root == index) {
+            let relative = file.path.strip_prefix(root).unwrap_or(&file.path);
+            let Some(member) = members
+                .iter_mut()
+                .filter(|member| relative.starts_with(&member.path))
+                .max_by_key(|member| member.path.components().count())
+            else {
+                continue;
+            };
+            let lines = file.weighted_lines(&settings.categories);
+            let within = relative.strip_prefix(&member.path).unwrap_or(relative);
+            let mut components = within.components();
+            let part = match (components.next(), components.next()) {
+                (Some(dir), Some(_)) if dir.as_os_str() == "src" => &mut member.src,
+                (Some(dir), Some(_)) if dir.as_os_str() == "tests" => &mut member.tests,
+                (Some(dir), Some(_)) if dir.as_os_str() == "benches" => &mut member.benches,
+                _ => &mut member.other,
+            };
+            part.lines += lines;
+            part.files += 1;
+            member.lines += lines;
+            member.files += 1;
+        }
+        members.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.path.cmp(&b.path)));
+        crates.extend(members);
+    }
+    crates
+}
+
+/// The name and directory, relative to `root`, of each crate of the workspace declared in
+/// `root/Cargo.toml`, the workspace's own package included. None when there's no workspace.
+fn members(root: &Path) -> Vec<(String, PathBuf)> {
+    let Some(manifest) = read_manifest(&root.join("Cargo.toml")) else {
+        return Vec::new();
+    };
+    let Some(workspace) = manifest.workspace else {
+        return Vec::new();
+    };
+    let mut members = Vec::new();
+    if let Some(package) = manifest.package {
+        members.push((package.name, PathBuf::new()));
+    }
+    let exclude = workspace.exclude.iter().map(|path| clean(path)).collect::<Vec<_>>();
+    for pattern in &workspace.members {
+        for dir in expand(root, &clean(pattern)) {
+            if exclude.contains(&dir) || members.iter().any(|(_, known)| *known == dir) {
+                continue;
+            }
+            let Some(manifest) = read_manifest(&root.join(&dir).join("Cargo.toml")) else {
+                continue;
+            };
+            let name = match manifest.package {
+                Some(package) => package.name,
+                None => dir.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+            };
+            members.push((name, dir));
+        }
+    }
+    members
+}
+
+/// `path` as written in a manifest, without a leading `./` or a trailing `/`.
+fn clean(path: &str) -> PathBuf {
+    Path::new(path.trim_start_matches("./").trim_end_matches('/')).to_path_buf()
+}
+
+/// The directories under `root` that a member pattern such as `crates/*` names.
+fn expand(root: &Path, pattern: &Path) -> Vec<PathBuf> {
+    let text = pattern.to_string_lossy();
+    if !text.contains(['*', '?', '[', '{']) {
+        return vec![pattern.to_path_buf()];
+    }
+    let matcher = match GlobBuilder::new(&text).literal_separator(true).build() {
+        Ok(glob) => glob.compile_matcher(),
+        Err(err) => {
+            tracing::warn!(pattern = %text, error = %err, "invalid workspace member");
+            return Vec::new();
+        }
+    };
+    let mut dirs = WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(pattern.components().count())
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.file_type().is_dir() && !name.starts_with('.') && name != "target"
+        })
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.path().strip_prefix(root).ok().map(Path::to_path_buf))
+        .filter(|dir| matcher.is_match(dir))
+        .collect::<Vec<_>>();
+    dirs.sort();
+    dirs
+}
+
+fn read_manifest(path: &Path) -> Option<Manifest> {
+    let raw = fs::read_to_string(path).ok()?;
+    match toml::from_str(&raw) {
+        Ok(manifest) => Some(manifest),
+        Err(err) => {
+            tracing::warn!(path = %path.display(), error = %err, "unreadable Cargo manifest");
+            None
+        }
+    }
+}